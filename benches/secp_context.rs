@@ -0,0 +1,67 @@
+//! Per-call `Secp256k1::new()` against the shared `wallet::secp()` context,
+//! and `derive_many_addresses` against deriving every index from the master.
+//!
+//! Run with `cargo bench --bench secp_context`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use kaspa_graffiti::commands::derive_many_addresses;
+use kaspa_graffiti::wallet::{secp, DerivationParams, ExtendedKey};
+use secp256k1::{KeyPair, Secp256k1};
+
+const ITERATIONS: u32 = 500;
+const KEY: [u8; 32] = [0x1b; 32];
+
+fn time(iterations: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let per_call = time(ITERATIONS, || {
+        let context = Secp256k1::new();
+        black_box(KeyPair::from_seckey_slice(&context, black_box(&KEY)).unwrap());
+    });
+    let shared = time(ITERATIONS, || {
+        black_box(KeyPair::from_seckey_slice(secp(), black_box(&KEY)).unwrap());
+    });
+
+    println!("keypair, new context per call: {:?}", per_call);
+    println!("keypair, shared context:       {:?}", shared);
+    println!("speedup: {:.1}x", per_call.as_secs_f64() / shared.as_secs_f64());
+
+    let master = ExtendedKey::from_seed(&[7u8; 64]).unwrap();
+    let derive = time(ITERATIONS, || {
+        black_box(master.derive_child(black_box(0)).unwrap());
+    });
+    println!("derive_child, shared context:  {:?}", derive);
+
+    // Each derivation used to build at least one context of its own
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let seed = hex::encode([7u8; 64]);
+    let start = Instant::now();
+    black_box(runtime.block_on(derive_many_addresses(&seed, ITERATIONS, false)).unwrap());
+    let derive_many = start.elapsed();
+    let contexts = time(ITERATIONS, || {
+        black_box(Secp256k1::new());
+    }) * ITERATIONS;
+    println!("derive_many_addresses({}):     {:?}", ITERATIONS, derive_many);
+    println!("{} contexts saved:            {:?}", ITERATIONS, contexts);
+
+    // The account prefix is derived once per call, not once per index
+    let start = Instant::now();
+    black_box(runtime.block_on(derive_many_addresses(&seed, 1000, false)).unwrap());
+    let cached = start.elapsed();
+    let start = Instant::now();
+    for index in 0..1000 {
+        let params = DerivationParams::default();
+        black_box(master.account_key(params).and_then(|account| account.derive_leaf(params, false, index)).unwrap());
+    }
+    let full_path = start.elapsed();
+    println!("derive_many_addresses(1000):    {:?}", cached);
+    println!("1000 full-path derivations:     {:?}", full_path);
+}
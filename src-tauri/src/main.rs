@@ -1,6 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use kaspa_graffiti::commands::{generate_wallet, load_wallet, validate_address, get_balance, get_utxos, transfer};
+use kaspa_graffiti::commands::{generate_wallet, load_wallet, validate_address, get_balance, get_utxos, transfer, transfer_multi_on};
+use kaspa_graffiti::wallet::Network;
 use serde_json;
 
 #[tauri::command]
@@ -40,6 +41,15 @@ async fn utxos_get(address: &str, rpc_url: Option<&str>) -> Result<String, Strin
     }
 }
 
+#[tauri::command]
+async fn scan_estimate(address: &str, rpc_url: Option<&str>) -> Result<String, String> {
+    use kaspa_graffiti::commands::estimate_scan_size;
+    match estimate_scan_size(address, rpc_url).await {
+        Ok(estimate) => serde_json::to_string(&estimate).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 #[tauri::command]
 async fn wallet_hd_generate() -> Result<String, String> {
     use kaspa_graffiti::commands::generate_hd_wallet;
@@ -84,7 +94,18 @@ async fn wallet_transfer(private_key: &str, recipient: &str, amount: u64, rpc_ur
     }
 }
 
+#[tauri::command]
+async fn wallet_transfer_many(private_key: &str, recipients: Vec<(String, u64)>, rpc_url: Option<&str>, fee_rate: u64, network: &str) -> Result<String, String> {
+    let network = Network::from_name(network).map_err(|e| e.to_string())?;
+    match transfer_multi_on(private_key, recipients, rpc_url, fee_rate, network, true).await {
+        Ok(result) => serde_json::to_string(&result).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 fn main() {
+    env_logger::init();
+
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             wallet_generate,
@@ -92,11 +113,13 @@ fn main() {
             address_validate,
             balance_get,
             utxos_get,
+            scan_estimate,
             wallet_hd_generate,
             wallet_hd_load,
             derive_address,
             derive_many,
             wallet_transfer,
+            wallet_transfer_many,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
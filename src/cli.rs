@@ -0,0 +1,687 @@
+//! Argument parsing for `kaspa-graffiti-cli`.
+
+use kaspa_graffiti::commands::FeeLevel;
+use kaspa_graffiti::wallet::{DerivationParams, Network, HARDENED_OFFSET};
+use serde::Serialize;
+use thiserror::Error;
+
+const BIN: &str = "kaspa-graffiti-cli";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Rpc,
+    Network,
+    Raw,
+    AllowOrphan,
+    Wait,
+    Keystore,
+    FeePriority,
+    Resume,
+    Checkpoint,
+    Json,
+    Account,
+    Path,
+    Config,
+    Qr,
+    QrPng,
+    NoNetworkCheck,
+    ChangeAddress,
+    DryRun,
+}
+
+impl Flag {
+    const ALL: [Flag; 18] = [
+        Flag::Rpc,
+        Flag::Network,
+        Flag::Raw,
+        Flag::AllowOrphan,
+        Flag::Wait,
+        Flag::Keystore,
+        Flag::FeePriority,
+        Flag::Resume,
+        Flag::Checkpoint,
+        Flag::Json,
+        Flag::Account,
+        Flag::Path,
+        Flag::Config,
+        Flag::Qr,
+        Flag::QrPng,
+        Flag::NoNetworkCheck,
+        Flag::ChangeAddress,
+        Flag::DryRun,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Flag::Rpc => "--rpc",
+            Flag::Network => "--network",
+            Flag::Raw => "--raw",
+            Flag::AllowOrphan => "--allow-orphan",
+            Flag::Wait => "--wait",
+            Flag::Keystore => "--keystore",
+            Flag::FeePriority => "--fee-priority",
+            Flag::Resume => "--resume",
+            Flag::Checkpoint => "--checkpoint",
+            Flag::Json => "--json",
+            Flag::Account => "--account",
+            Flag::Path => "--path",
+            Flag::Config => "--config",
+            Flag::Qr => "--qr",
+            Flag::QrPng => "--qr-png",
+            Flag::NoNetworkCheck => "--no-network-check",
+            Flag::ChangeAddress => "--change-address",
+            Flag::DryRun => "--dry-run",
+        }
+    }
+
+    fn value_name(self) -> Option<&'static str> {
+        match self {
+            Flag::Rpc => Some("<url>"),
+            Flag::Network => Some("<name>"),
+            Flag::Keystore => Some("<path>"),
+            Flag::FeePriority => Some("<level>"),
+            Flag::Checkpoint => Some("<path>"),
+            Flag::Account => Some("<n>"),
+            Flag::Path => Some("<path>"),
+            Flag::Config => Some("<path>"),
+            Flag::QrPng => Some("<path>"),
+            Flag::ChangeAddress => Some("<address>"),
+            Flag::Raw | Flag::AllowOrphan | Flag::Wait | Flag::Resume | Flag::Json | Flag::Qr | Flag::NoNetworkCheck | Flag::DryRun => None,
+        }
+    }
+
+    fn help(self) -> &'static str {
+        match self {
+            Flag::Rpc => "RPC endpoint (default: the network's public endpoint)",
+            Flag::Network => "mainnet, testnet-10, testnet-11 or simnet (default: the config file's, else testnet-10)",
+            Flag::Raw => "Write the graffiti message bytes as-is instead of a GFX payload",
+            Flag::AllowOrphan => "Let the node hold a graffiti spending change it hasn't seen yet",
+            Flag::Wait => "After sending a graffiti, wait for it to be accepted",
+            Flag::Keystore => "Read the private key from a keystore file instead of an argument",
+            Flag::FeePriority => "Use the node's low, normal or priority fee rate instead of fee_rate",
+            Flag::Resume => "Continue an interrupted scan from its checkpoint file",
+            Flag::Checkpoint => "Save scan progress to this file after every page",
+            Flag::Json => "Print the result as JSON instead of text",
+            Flag::Account => "BIP44 account index to derive under (default: 0)",
+            Flag::Path => "Account path to derive under, e.g. m/44'/111111'/2' (instead of --account)",
+            Flag::Config => "Config file to read defaults from (default: ~/.config/kaspa-graffiti/config.toml)",
+            Flag::Qr => "Also print the address as a QR code",
+            Flag::QrPng => "Also save the address's QR code as a PNG image",
+            Flag::NoNetworkCheck => "Don't check that the node is on the address's network first",
+            Flag::ChangeAddress => "Send the change here instead of back to the sender",
+            Flag::DryRun => "Print the signed transaction instead of sending it",
+        }
+    }
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub about: &'static str,
+    /// Accepted in addition to `--network`, `--json` and `--config`, which every
+    /// command takes.
+    pub flags: &'static [Flag],
+    pub hd: bool,
+}
+
+const NONE: &[Flag] = &[];
+const GLOBAL_FLAGS: &[Flag] = &[Flag::Network, Flag::Json, Flag::Config];
+const RPC: &[Flag] = &[Flag::Rpc];
+/// For commands that check the node's network before using it.
+const RPC_CHECKED: &[Flag] = &[Flag::Rpc, Flag::NoNetworkCheck];
+const ACCOUNT: &[Flag] = &[Flag::Account, Flag::Path];
+const QR: &[Flag] = &[Flag::Qr, Flag::QrPng];
+
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec { name: "generate", args: "", about: "Generate a new wallet", flags: QR, hd: false },
+    CommandSpec { name: "load", args: "<private_key>", about: "Load wallet from private key", flags: &[Flag::Keystore], hd: false },
+    CommandSpec { name: "balance", args: "<address>", about: "Get address balance", flags: RPC_CHECKED, hd: false },
+    CommandSpec { name: "balances", args: "<address>...", about: "Get the balances of several addresses at once", flags: RPC, hd: false },
+    CommandSpec { name: "utxos", args: "<address>", about: "Get address UTXOs", flags: RPC_CHECKED, hd: false },
+    CommandSpec { name: "transfer", args: "<private_key> <recipient> <amount> [fee_rate]", about: "Transfer KAS (no message)", flags: &[Flag::Rpc, Flag::Keystore, Flag::NoNetworkCheck, Flag::ChangeAddress, Flag::DryRun], hd: false },
+    CommandSpec { name: "transfer-multi", args: "<private_key> <address=amount>...", about: "Pay several recipients in one transaction (amounts in KAS)", flags: &[Flag::Rpc, Flag::Keystore, Flag::NoNetworkCheck], hd: false },
+    CommandSpec { name: "sweep", args: "<private_key> <destination> [fee_rate]", about: "Send the whole balance to an address", flags: &[Flag::Rpc, Flag::Keystore, Flag::NoNetworkCheck], hd: false },
+    CommandSpec { name: "build-unsigned", args: "<sender> <recipient> <amount> [fee_rate]", about: "Print an unsigned transfer as JSON, for sign-tx on an offline machine", flags: &[Flag::Rpc, Flag::NoNetworkCheck, Flag::ChangeAddress], hd: false },
+    CommandSpec { name: "sign-tx", args: "<private_key> <unsigned.json>", about: "Sign a build-unsigned transaction without going online", flags: &[Flag::Keystore], hd: false },
+    CommandSpec { name: "sign-message", args: "<private_key> <text>", about: "Sign a message to prove control of an address", flags: &[Flag::Keystore], hd: false },
+    CommandSpec { name: "verify-message", args: "<address|public_key> <text> <signature>", about: "Check a signed message against an address or public key", flags: NONE, hd: false },
+    CommandSpec { name: "keystore-save", args: "<private_key> <path>", about: "Encrypt a key into a password-protected file", flags: NONE, hd: false },
+    CommandSpec { name: "config-init", args: "", about: "Write a config file template for default RPC, network and fee rate", flags: NONE, hd: false },
+    CommandSpec { name: "selftest", args: "", about: "Check keys, signing and decoding work (offline)", flags: NONE, hd: false },
+    CommandSpec {
+        name: "graffiti",
+        args: "<private_key> <message> [mimetype] [fee_rate|auto]",
+        about: "Send graffiti (with message)",
+        flags: &[Flag::Rpc, Flag::Raw, Flag::AllowOrphan, Flag::Wait, Flag::Keystore, Flag::FeePriority, Flag::NoNetworkCheck, Flag::ChangeAddress, Flag::DryRun],
+        hd: false,
+    },
+    CommandSpec { name: "graffiti-priority", args: "<private_key> <message> [mimetype]", about: "Send graffiti at the node's priority fee rate", flags: RPC_CHECKED, hd: false },
+    CommandSpec { name: "read-graffiti", args: "<txid>", about: "Decode the graffiti message in a transaction", flags: RPC, hd: false },
+    CommandSpec { name: "read", args: "<address>", about: "List graffiti messages sent from or to an address", flags: RPC, hd: false },
+    CommandSpec { name: "scan", args: "<address>", about: "Page through an address's whole history for graffiti", flags: &[Flag::Rpc, Flag::Resume, Flag::Checkpoint], hd: false },
+    CommandSpec { name: "qr", args: "<address>", about: "Print an address as a QR code to scan", flags: &[Flag::QrPng], hd: false },
+    CommandSpec { name: "fee", args: "", about: "Show the node's current fee rates", flags: RPC, hd: false },
+    CommandSpec { name: "confirmations", args: "<txid>", about: "Show DAA and blue score confirmation depth", flags: RPC, hd: false },
+    CommandSpec { name: "hd-generate", args: "", about: "Generate a new HD wallet", flags: QR, hd: true },
+    CommandSpec { name: "hd-load", args: "<seed|mnemonic>", about: "Load HD wallet from hex seed or mnemonic", flags: NONE, hd: true },
+    CommandSpec { name: "mnemonic-generate", args: "[12|15|18|21|24]", about: "Generate an HD wallet from a new 12-24 word mnemonic", flags: NONE, hd: true },
+    CommandSpec { name: "mnemonic-load", args: "\"<phrase>\" [passphrase]", about: "Load HD wallet from mnemonic and optional passphrase", flags: NONE, hd: true },
+    CommandSpec { name: "hd-balance", args: "<seed|mnemonic> [gap_limit]", about: "Total balance across the wallet's derived addresses", flags: &[Flag::Rpc, Flag::NoNetworkCheck, Flag::Account, Flag::Path], hd: true },
+    CommandSpec { name: "hd-scan", args: "<seed|mnemonic> [gap_limit]", about: "Find the wallet's used addresses and the next unused index", flags: &[Flag::Rpc, Flag::NoNetworkCheck, Flag::Account, Flag::Path], hd: true },
+    CommandSpec { name: "derive-address", args: "<seed> <index> [change]", about: "Derive address from seed", flags: &[Flag::Account, Flag::Path, Flag::Qr, Flag::QrPng], hd: true },
+    CommandSpec { name: "derive-path", args: "<seed> <path>", about: "Derive the address at a path such as m/44'/111111'/3'/0/7", flags: NONE, hd: true },
+    CommandSpec { name: "derive-many", args: "<private_key> <count>", about: "Derive multiple addresses", flags: ACCOUNT, hd: true },
+    CommandSpec { name: "account-xpub", args: "<seed|mnemonic>", about: "Print the account's extended public key", flags: ACCOUNT, hd: true },
+    CommandSpec { name: "derive-xpub", args: "<xpub> <count>", about: "Derive receive addresses from an xpub, watch-only", flags: NONE, hd: true },
+    CommandSpec { name: "backup", args: "<seed|mnemonic> <path>", about: "Write a wallet backup file", flags: NONE, hd: true },
+    CommandSpec { name: "verify-backup", args: "<path>", about: "Check a backup's checksum and addresses", flags: NONE, hd: true },
+];
+
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.name == name)
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("No command given")]
+    MissingCommand,
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+    #[error("Unknown flag: {0}")]
+    UnknownFlag(String),
+    #[error("{flag} is not accepted by {command}")]
+    UnsupportedFlag { command: &'static str, flag: &'static str },
+    #[error("{0} needs a value")]
+    MissingValue(&'static str),
+    #[error("Unknown network: {0} (expected mainnet, testnet-10, testnet-11 or simnet)")]
+    InvalidNetwork(String),
+    #[error("Unknown fee priority: {0} (expected low, normal or priority)")]
+    InvalidFeePriority(String),
+    #[error("Invalid account index: {0} (expected 0 to 2147483647)")]
+    InvalidAccount(String),
+    #[error("Invalid account path {path}: {reason}")]
+    InvalidPath { path: String, reason: String },
+    #[error("--account and --path both pick the account; pass only one")]
+    AccountAndPath,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub rpc_url: Option<String>,
+    /// `None` unless `--network` was given, so a config file can fill it in.
+    pub network: Option<Network>,
+    pub raw: bool,
+    pub allow_orphan: bool,
+    pub wait: bool,
+    pub keystore: Option<String>,
+    pub fee_priority: Option<FeeLevel>,
+    pub resume: bool,
+    pub checkpoint: Option<String>,
+    pub json: bool,
+    pub account: u32,
+    pub config: Option<String>,
+    pub qr: bool,
+    pub qr_png: Option<String>,
+    pub no_network_check: bool,
+    pub change_address: Option<String>,
+    pub dry_run: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            rpc_url: None,
+            network: None,
+            raw: false,
+            allow_orphan: false,
+            wait: false,
+            keystore: None,
+            fee_priority: None,
+            resume: false,
+            checkpoint: None,
+            json: false,
+            account: 0,
+            config: None,
+            qr: false,
+            qr_png: None,
+            no_network_check: false,
+            change_address: None,
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParsedCommand {
+    /// `--help` or `help`, with the command to describe, if any.
+    Help(Option<&'static str>),
+    Run {
+        command: &'static str,
+        /// Positional arguments after the command name.
+        args: Vec<String>,
+        options: Options,
+    },
+}
+
+/// Parse the arguments after the program name. Flags may appear anywhere;
+/// everything after a bare `--` is positional, e.g. a message starting `--`.
+pub fn parse_args(args: &[String]) -> Result<ParsedCommand, ParseError> {
+    let mut options = Options::default();
+    let mut flags = Vec::new();
+    let mut positional = Vec::new();
+    let mut help = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            positional.extend(iter.by_ref().cloned());
+            break;
+        }
+        if arg == "--help" || arg == "-h" {
+            help = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            positional.push(arg.clone());
+            continue;
+        }
+
+        let flag = Flag::ALL
+            .into_iter()
+            .find(|flag| flag.name() == arg)
+            .ok_or_else(|| ParseError::UnknownFlag(arg.clone()))?;
+        let value = match flag.value_name() {
+            Some(_) => Some(iter.next().ok_or(ParseError::MissingValue(flag.name()))?.clone()),
+            None => None,
+        };
+
+        match (flag, value) {
+            (Flag::Rpc, Some(url)) => options.rpc_url = Some(url),
+            (Flag::Network, Some(name)) => {
+                options.network =
+                    Some(Network::from_name(&name).map_err(|_| ParseError::InvalidNetwork(name))?)
+            }
+            (Flag::Keystore, Some(path)) => options.keystore = Some(path),
+            (Flag::Checkpoint, Some(path)) => options.checkpoint = Some(path),
+            (Flag::Config, Some(path)) => options.config = Some(path),
+            (Flag::QrPng, Some(path)) => options.qr_png = Some(path),
+            (Flag::ChangeAddress, Some(address)) => options.change_address = Some(address),
+            (Flag::Account, Some(account)) => {
+                options.account = match account.parse() {
+                    Ok(index) if index < HARDENED_OFFSET => index,
+                    _ => return Err(ParseError::InvalidAccount(account)),
+                }
+            }
+            (Flag::Path, Some(path)) => {
+                options.account = match DerivationParams::from_path(&path) {
+                    Ok(params) => params.account,
+                    Err(e) => return Err(ParseError::InvalidPath { path, reason: e.to_string() }),
+                }
+            }
+            (Flag::FeePriority, Some(level)) => {
+                options.fee_priority =
+                    Some(FeeLevel::from_name(&level).ok_or(ParseError::InvalidFeePriority(level))?)
+            }
+            (Flag::Raw, _) => options.raw = true,
+            (Flag::AllowOrphan, _) => options.allow_orphan = true,
+            (Flag::Wait, _) => options.wait = true,
+            (Flag::Resume, _) => options.resume = true,
+            (Flag::Json, _) => options.json = true,
+            (Flag::Qr, _) => options.qr = true,
+            (Flag::NoNetworkCheck, _) => options.no_network_check = true,
+            (Flag::DryRun, _) => options.dry_run = true,
+            _ => unreachable!("value flags always carry a value"),
+        }
+        if matches!(flag, Flag::Account | Flag::Path) && flags.iter().any(|&seen| seen != flag && matches!(seen, Flag::Account | Flag::Path)) {
+            return Err(ParseError::AccountAndPath);
+        }
+        flags.push(flag);
+    }
+
+    let mut positional = positional.into_iter();
+    let name = match positional.next() {
+        Some(name) if name == "help" => {
+            return match positional.next() {
+                Some(topic) => find_command(&topic)
+                    .map(|spec| ParsedCommand::Help(Some(spec.name)))
+                    .ok_or(ParseError::UnknownCommand(topic)),
+                None => Ok(ParsedCommand::Help(None)),
+            };
+        }
+        Some(name) => name,
+        None if help => return Ok(ParsedCommand::Help(None)),
+        None => return Err(ParseError::MissingCommand),
+    };
+    let spec = find_command(&name).ok_or(ParseError::UnknownCommand(name))?;
+
+    if help {
+        return Ok(ParsedCommand::Help(Some(spec.name)));
+    }
+    if let Some(flag) = flags
+        .into_iter()
+        .find(|flag| !GLOBAL_FLAGS.contains(flag) && !spec.flags.contains(flag))
+    {
+        return Err(ParseError::UnsupportedFlag { command: spec.name, flag: flag.name() });
+    }
+
+    Ok(ParsedCommand::Run { command: spec.name, args: positional.collect(), options })
+}
+
+fn usage_line(spec: &CommandSpec) -> String {
+    let mut line = format!("{} {}", BIN, spec.name);
+    if !spec.args.is_empty() {
+        line.push(' ');
+        line.push_str(spec.args);
+    }
+    for flag in spec.flags {
+        match flag.value_name() {
+            Some(value) => line.push_str(&format!(" [{} {}]", flag.name(), value)),
+            None => line.push_str(&format!(" [{}]", flag.name())),
+        }
+    }
+    line
+}
+
+const SOMPI_PER_KAS: u64 = 100_000_000;
+
+/// A KAS amount such as `1.5` or `0.29`, as exact sompi. Only plain decimals
+/// with at most 8 fractional digits parse; signs, exponents and amounts that
+/// overflow a u64 don't.
+pub fn parse_kas(amount: &str) -> Option<u64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let digits = format!("{}{}", whole, fraction);
+    if digits.is_empty() || fraction.len() > 8 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let fraction: u64 = format!("{:0<8}", fraction).parse().ok()?;
+    whole.checked_mul(SOMPI_PER_KAS)?.checked_add(fraction)
+}
+
+/// A `transfer-multi` recipient, `address=amount` or `address:amount` with
+/// the amount in KAS, as the address and sompi.
+pub fn parse_recipient(arg: &str) -> Option<(String, u64)> {
+    let (address, amount) = arg.split_once('=').or_else(|| arg.rsplit_once(':'))?;
+    Some((address.to_string(), parse_kas(amount)?))
+}
+
+/// `value` as pretty-printed JSON, for `--json` output.
+pub fn to_json<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(value)
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+    match to_json(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Print a command's usage line after it was given too few arguments.
+pub fn print_usage_error(command: &str) {
+    if let Some(spec) = find_command(command) {
+        eprintln!("Usage: {}", usage_line(spec));
+    }
+}
+
+pub fn print_command_help(spec: &CommandSpec) {
+    println!("Usage: {}", usage_line(spec));
+    println!();
+    println!("{}", spec.about);
+    println!();
+    println!("Options:");
+    for flag in spec.flags.iter().chain(GLOBAL_FLAGS) {
+        let name = match flag.value_name() {
+            Some(value) => format!("{} {}", flag.name(), value),
+            None => flag.name().to_string(),
+        };
+        println!("  {:<18} {}", name, flag.help());
+    }
+}
+
+pub fn print_usage() {
+    println!("Kaspa Graffiti CLI");
+    println!();
+    println!("Usage:");
+    let width = COMMANDS.iter().map(|spec| spec.name.len() + spec.args.len()).max().unwrap_or(0) + 1;
+    for (hd, header) in [(false, None), (true, Some("HD Wallet Commands:"))] {
+        if let Some(header) = header {
+            println!();
+            println!("{}", header);
+        }
+        for spec in COMMANDS.iter().filter(|spec| spec.hd == hd) {
+            let call = format!("{} {}", spec.name, spec.args);
+            println!("  {} {:<width$}  {}", BIN, call, spec.about, width = width);
+        }
+    }
+    println!();
+    println!("Options:");
+    for flag in Flag::ALL {
+        let name = match flag.value_name() {
+            Some(value) => format!("{} {}", flag.name(), value),
+            None => flag.name().to_string(),
+        };
+        println!("  {:<18} {}", name, flag.help());
+    }
+    println!();
+    println!("Run `{} <command> --help` for a command's arguments and options.", BIN);
+    println!();
+    println!("Examples:");
+    println!("  {} generate", BIN);
+    println!("  {} hd-generate", BIN);
+    println!("  {} derive-address <seed> 0", BIN);
+    println!("  {} derive-many <private_key> 5", BIN);
+    println!("  {} balance kaspatest:qq...", BIN);
+    println!("  {} transfer <key> <addr> 1.0", BIN);
+    println!("  {} graffiti <private_key> \"Hello Kaspa!\" text/plain 1", BIN);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kaspa_graffiti::{SendResult, UtxoInfo, WalletInfo};
+
+    fn parse(args: &[&str]) -> Result<ParsedCommand, ParseError> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_args(&args)
+    }
+
+    fn run(args: &[&str]) -> (&'static str, Vec<String>, Options) {
+        match parse(args).unwrap() {
+            ParsedCommand::Run { command, args, options } => (command, args, options),
+            other => panic!("expected a command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_flag_placement() {
+        let expected = vec!["key".to_string(), "hello".to_string()];
+        for args in [
+            &["--rpc", "http://node", "graffiti", "key", "hello", "--wait"][..],
+            &["graffiti", "--wait", "key", "--rpc", "http://node", "hello"],
+            &["graffiti", "key", "hello", "--rpc", "http://node", "--wait"],
+        ] {
+            let (command, positional, options) = run(args);
+            assert_eq!(command, "graffiti");
+            assert_eq!(positional, expected);
+            assert_eq!(options.rpc_url.as_deref(), Some("http://node"));
+            assert!(options.wait);
+            assert!(!options.raw);
+        }
+
+        let (_, _, options) = run(&["--network", "mainnet", "generate"]);
+        assert_eq!(options.network, Some(Network::Mainnet));
+        assert_eq!(run(&["generate"]).2.network, None);
+
+        let (_, _, options) = run(&["balance", "addr", "--config", "alt.toml"]);
+        assert_eq!(options.config.as_deref(), Some("alt.toml"));
+
+        let (_, _, options) = run(&["graffiti", "key", "hi", "--fee-priority", "priority"]);
+        assert_eq!(options.fee_priority, Some(FeeLevel::Priority));
+
+        // A message that looks like a flag
+        let (_, positional, _) = run(&["graffiti", "key", "--", "--wait"]);
+        assert_eq!(positional, vec!["key".to_string(), "--wait".to_string()]);
+    }
+
+    #[test]
+    fn test_flag_errors() {
+        assert_eq!(parse(&["balance", "addr", "--rpc"]), Err(ParseError::MissingValue("--rpc")));
+        assert_eq!(parse(&["balance", "--verbose", "addr"]), Err(ParseError::UnknownFlag("--verbose".to_string())));
+        assert_eq!(
+            parse(&["balance", "addr", "--wait"]),
+            Err(ParseError::UnsupportedFlag { command: "balance", flag: "--wait" })
+        );
+        assert_eq!(
+            parse(&["selftest", "--keystore", "k.json"]),
+            Err(ParseError::UnsupportedFlag { command: "selftest", flag: "--keystore" })
+        );
+        assert_eq!(parse(&["generate", "--network", "devnet"]), Err(ParseError::InvalidNetwork("devnet".to_string())));
+        assert_eq!(
+            parse(&["graffiti", "key", "hi", "--fee-priority", "urgent"]),
+            Err(ParseError::InvalidFeePriority("urgent".to_string()))
+        );
+        assert_eq!(parse(&["frobnicate"]), Err(ParseError::UnknownCommand("frobnicate".to_string())));
+        assert_eq!(parse(&["--wait"]), Err(ParseError::MissingCommand));
+    }
+
+    #[test]
+    fn test_json_flag() {
+        let (_, _, options) = run(&["balance", "addr", "--json"]);
+        assert!(options.json);
+        let (_, _, options) = run(&["--json", "selftest"]);
+        assert!(options.json);
+        assert!(!run(&["balance", "addr"]).2.json);
+    }
+
+    #[test]
+    fn test_account_flag() {
+        let (_, _, options) = run(&["derive-many", "seed", "5", "--account", "2"]);
+        assert_eq!(options.account, 2);
+        assert_eq!(run(&["derive-many", "seed", "5"]).2.account, 0);
+        assert_eq!(
+            parse(&["derive-many", "seed", "5", "--account", "2147483648"]),
+            Err(ParseError::InvalidAccount("2147483648".to_string()))
+        );
+        assert_eq!(
+            parse(&["balance", "addr", "--account", "1"]),
+            Err(ParseError::UnsupportedFlag { command: "balance", flag: "--account" })
+        );
+    }
+
+    #[test]
+    fn test_parse_kas() {
+        assert_eq!(parse_kas("1"), Some(100_000_000));
+        assert_eq!(parse_kas("0.29"), Some(29_000_000));
+        assert_eq!(parse_kas(".5"), Some(50_000_000));
+        assert_eq!(parse_kas("2."), Some(200_000_000));
+        assert_eq!(parse_kas("0.00000001"), Some(1));
+        assert_eq!(parse_kas("184467440737.09551615"), Some(u64::MAX));
+        assert_eq!(parse_kas("184467440737.09551616"), None);
+        assert_eq!(parse_kas("0.000000001"), None);
+        assert_eq!(parse_kas(""), None);
+        assert_eq!(parse_kas("."), None);
+        assert_eq!(parse_kas("-1"), None);
+        assert_eq!(parse_kas("+1"), None);
+        assert_eq!(parse_kas("1e3"), None);
+        assert_eq!(parse_kas("1.2.3"), None);
+    }
+
+    #[test]
+    fn test_parse_recipient() {
+        let address = "kaspatest:qr0lr4ml9fn3chekrqmjdkergxl93l4wrk3dankcgvjq776s9wn9jhtkdksae";
+        let expected = Some((address.to_string(), 150_000_000));
+        assert_eq!(parse_recipient(&format!("{}=1.5", address)), expected);
+        assert_eq!(parse_recipient(&format!("{}:1.5", address)), expected);
+        assert_eq!(parse_recipient(address), None);
+        assert_eq!(parse_recipient(&format!("{}:", address)), None);
+        assert_eq!(parse_recipient(&format!("{}=-1", address)), None);
+        assert_eq!(parse_recipient(&format!("{}:NaN", address)), None);
+        assert_eq!(parse_recipient(&format!("{}=0.29", address)), Some((address.to_string(), 29_000_000)));
+    }
+
+    #[test]
+    fn test_path_flag() {
+        assert_eq!(run(&["account-xpub", "seed", "--path", "m/44'/111111'/3'"]).2.account, 3);
+        assert_eq!(run(&["derive-address", "seed", "0", "--path", "m/44'/111111'/0'"]).2.account, 0);
+        assert_eq!(
+            parse(&["derive-many", "seed", "5", "--path", "m/44'/972'/0'"]),
+            Err(ParseError::InvalidPath {
+                path: "m/44'/972'/0'".to_string(),
+                reason: "Coin type 972 is not Kaspa's (111111)".to_string()
+            })
+        );
+        assert!(matches!(
+            parse(&["derive-many", "seed", "5", "--path", "m/44'/111111'/0'/0/1"]),
+            Err(ParseError::InvalidPath { .. })
+        ));
+        assert_eq!(
+            parse(&["derive-many", "seed", "5", "--account", "1", "--path", "m/44'/111111'/1'"]),
+            Err(ParseError::AccountAndPath)
+        );
+    }
+
+    #[test]
+    fn test_no_network_check_flag() {
+        assert!(run(&["transfer", "key", "addr", "1", "--no-network-check"]).2.no_network_check);
+        assert!(!run(&["balance", "addr"]).2.no_network_check);
+        assert_eq!(
+            parse(&["fee", "--no-network-check"]),
+            Err(ParseError::UnsupportedFlag { command: "fee", flag: "--no-network-check" })
+        );
+    }
+
+    #[test]
+    fn test_change_address_flag() {
+        let (_, _, options) = run(&["transfer", "key", "addr", "1", "--change-address", "kaspatest:qq"]);
+        assert_eq!(options.change_address.as_deref(), Some("kaspatest:qq"));
+        assert_eq!(run(&["graffiti", "key", "gm"]).2.change_address, None);
+        assert!(run(&["graffiti", "key", "gm", "--dry-run"]).2.dry_run);
+        assert!(!run(&["transfer", "key", "addr", "1"]).2.dry_run);
+        assert_eq!(
+            parse(&["sweep", "key", "addr", "--change-address", "kaspatest:qq"]),
+            Err(ParseError::UnsupportedFlag { command: "sweep", flag: "--change-address" })
+        );
+    }
+
+    #[test]
+    fn test_json_output_round_trips() {
+        let wallet = WalletInfo {
+            private_key: "11".repeat(32),
+            public_key: format!("02{}", "22".repeat(32)),
+            address: "kaspatest:qq".to_string(),
+            network: "testnet-10".to_string(),
+        };
+        let parsed: WalletInfo = serde_json::from_str(&to_json(&wallet).unwrap()).unwrap();
+        assert_eq!(parsed, wallet);
+
+        let utxos = vec![
+            UtxoInfo { txid: "aa".repeat(32), vout: 0, amount: 100_000, script_pubkey: "20ac".to_string(), confirmations: Some(12) },
+            UtxoInfo { txid: "bb".repeat(32), vout: 3, amount: 5, script_pubkey: "20ac".to_string(), confirmations: None },
+        ];
+        let json = to_json(&utxos).unwrap();
+        let parsed: Vec<UtxoInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, utxos);
+
+        let sent = SendResult {
+            txid: "cc".repeat(32),
+            fee: 2036,
+            change: 49_997_964,
+            address: "kaspatest:qq".to_string(),
+            payload: b"GFX".to_vec(),
+        };
+        let parsed: SendResult = serde_json::from_str(&to_json(&sent).unwrap()).unwrap();
+        assert_eq!(parsed, sent);
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(parse(&[]), Err(ParseError::MissingCommand));
+        assert_eq!(parse(&["--help"]), Ok(ParsedCommand::Help(None)));
+        assert_eq!(parse(&["help"]), Ok(ParsedCommand::Help(None)));
+        assert_eq!(parse(&["sweep", "--help"]), Ok(ParsedCommand::Help(Some("sweep"))));
+        assert_eq!(parse(&["help", "sweep"]), Ok(ParsedCommand::Help(Some("sweep"))));
+        // Help wins over flags the command wouldn't accept
+        assert_eq!(parse(&["-h", "balance", "--wait"]), Ok(ParsedCommand::Help(Some("balance"))));
+    }
+}
@@ -1,137 +1,298 @@
-use crate::wallet::{KeyPair, Network, KaspaTransactionSigner};
-use crate::rpc::RpcClient;
+use crate::wallet::{is_mature, ScriptData, select_spendable, select_utxos, CoinSelectionError, input_mass, SelectionStrategy, KeyPair, Mnemonic, Network, SignerInput, JsonTransaction, KaspaSignedTransaction, KaspaTransactionSigner, TxSigner, UnsignedTx, DerivationParams, HARDENED_OFFSET, parse_path, secp, dust_threshold, is_dust, P2PK_SCRIPT_LEN};
+use crate::graffiti::{GraffitiError, GraffitiMessage, PayloadEncoder, DEFAULT_MAX_CLOCK_SKEW, MAX_PAYLOAD_SIZE};
+use crate::rpc::{RpcClient, RpcError, RpcTransport, SubmitError};
+use crate::rpc::client::{ChainTx, GetFeeBucket, GetFeeEstimateResponse, GetOutPoint, GetScriptPublicKey, GetUtxoEntry, GetUtxosByAddressesEntry};
 use crate::{KaspaGraffitiError, Result};
-use secp256k1::Secp256k1;
+use kaspa_consensus_core::constants::LOCK_TIME_THRESHOLD;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub async fn generate_wallet() -> Result<WalletInfo> {
+    generate_wallet_on(Network::Testnet10).await
+}
+
+pub async fn generate_wallet_on(network: Network) -> Result<WalletInfo> {
     let keypair = KeyPair::new();
-    let address = crate::wallet::generate_address(keypair.public_key(), Network::Testnet10);
+    let address = crate::wallet::generate_address(keypair.public_key(), network);
 
     Ok(WalletInfo {
         private_key: keypair.to_hex(),
         public_key: keypair.public_key_hex(),
         address,
-        network: "testnet-10".to_string(),
+        network: network.name().to_string(),
     })
 }
 
 pub async fn load_wallet(private_key: &str) -> Result<WalletInfo> {
+    load_wallet_on(private_key, Network::Testnet10).await
+}
+
+pub async fn load_wallet_on(private_key: &str, network: Network) -> Result<WalletInfo> {
+    // Accept either raw 32-byte hex or a WIF string
     let keypair = KeyPair::from_hex(private_key)
+        .or_else(|_| KeyPair::from_wif(private_key).map(|(keypair, _)| keypair))
         .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    let address = crate::wallet::generate_address(keypair.public_key(), Network::Testnet10);
+    let address = crate::wallet::generate_address(keypair.public_key(), network);
 
     Ok(WalletInfo {
         private_key: keypair.to_hex(),
         public_key: keypair.public_key_hex(),
         address,
-        network: "testnet-10".to_string(),
+        network: network.name().to_string(),
     })
 }
 
+/// Default public REST endpoint for a network, if one exists.
+pub fn default_rpc_url(network: Network) -> Option<&'static str> {
+    match network {
+        Network::Mainnet => Some(crate::rpc::PUBLIC_MAINNET_RPC),
+        Network::Testnet10 => Some(crate::rpc::PUBLIC_TESTNET10_RPC),
+        Network::Testnet11 => Some(crate::rpc::PUBLIC_TESTNET11_RPC),
+        Network::Simnet => None,
+    }
+}
+
+/// Returns a warning when `rpc_url` is one of the known public endpoints for a
+/// different network than `network`. Unknown (e.g. self-hosted) URLs pass.
+pub fn check_rpc_network(rpc_url: &str, network: Network) -> Option<String> {
+    let rpc_url = rpc_url.trim_end_matches('/');
+    let known = [Network::Mainnet, Network::Testnet10, Network::Testnet11];
+
+    known.iter()
+        .find(|n| default_rpc_url(**n) == Some(rpc_url))
+        .filter(|n| **n != network)
+        .map(|n| format!(
+            "RPC endpoint {} serves {} but the wallet network is {}",
+            rpc_url, n.name(), network.name()
+        ))
+}
+
+/// The network a command expects the node to be on.
+enum NodeNetwork<'a> {
+    /// Exactly this network.
+    Is(Network),
+    /// Whichever network `address` belongs to. Only the prefix is compared,
+    /// so testnet-10 and testnet-11 both serve a testnet address.
+    Serves(&'a str),
+}
+
+/// Error with `NetworkMismatch` unless the node is on the `expected`
+/// network. `check_network: false` skips the check, e.g. for a node that
+/// reports a network name this wallet doesn't know.
+async fn require_node_network(client: &RpcClient, expected: NodeNetwork<'_>, check_network: bool) -> Result<()> {
+    use kaspa_addresses::{Address, Prefix};
+
+    if !check_network {
+        return Ok(());
+    }
+    let name = client.get_network_name().await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    let node = name.strip_prefix("kaspa-").unwrap_or(&name);
+    let actual = Network::from_name(node).ok();
+
+    let (matches, wanted) = match expected {
+        NodeNetwork::Is(network) => (actual == Some(network), network.name().to_string()),
+        NodeNetwork::Serves(address) => {
+            let prefix = Address::try_from(address)
+                .map_err(|e| KaspaGraffitiError::InvalidAddress(format!("{}: {}", address, e)))?
+                .prefix;
+            let wanted = match prefix {
+                Prefix::Mainnet => "mainnet".to_string(),
+                Prefix::Testnet => "testnet".to_string(),
+                Prefix::Simnet => "simnet".to_string(),
+                other => other.to_string(),
+            };
+            (actual.is_some_and(|n| n.to_prefix() == prefix), wanted)
+        }
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(KaspaGraffitiError::NetworkMismatch { address: wanted, node: node.to_string() })
+    }
+}
+
 pub async fn validate_address(address: &str) -> bool {
     crate::wallet::validate_address(address, Network::Testnet10).unwrap_or(false)
 }
 
 pub async fn generate_hd_wallet() -> Result<HDWalletInfo> {
-    use rand::RngCore;
+    generate_hd_wallet_on(Network::Testnet10).await
+}
+
+pub async fn generate_hd_wallet_on(network: Network) -> Result<HDWalletInfo> {
+    generate_mnemonic_wallet_on(24, network).await
+}
+
+pub async fn generate_mnemonic_wallet() -> Result<HDWalletInfo> {
+    generate_mnemonic_wallet_on(24, Network::Testnet10).await
+}
+
+/// Generate an HD wallet from a new `words`-word mnemonic (no passphrase).
+pub async fn generate_mnemonic_wallet_on(words: usize, network: Network) -> Result<HDWalletInfo> {
+    let mnemonic = Mnemonic::generate(words)
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
 
-    let mut seed = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut seed);
+    mnemonic_wallet_info(&mnemonic, "", network)
+}
+
+pub async fn load_mnemonic_wallet(phrase: &str, passphrase: &str) -> Result<HDWalletInfo> {
+    load_mnemonic_wallet_on(phrase, passphrase, Network::Testnet10).await
+}
+
+pub async fn load_mnemonic_wallet_on(phrase: &str, passphrase: &str, network: Network) -> Result<HDWalletInfo> {
+    let mnemonic = Mnemonic::from_phrase(phrase)
+        .map_err(|e| KaspaGraffitiError::Wallet(format!("Invalid mnemonic: {}", e)))?;
+
+    mnemonic_wallet_info(&mnemonic, passphrase, network)
+}
+
+fn mnemonic_wallet_info(mnemonic: &Mnemonic, passphrase: &str, network: Network) -> Result<HDWalletInfo> {
+    let seed = mnemonic.to_seed(passphrase);
 
     let extended_key = crate::wallet::ExtendedKey::from_seed(&seed)
         .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
-    
-    let address0 = extended_key.derive_address_index(0)
+
+    let params = DerivationParams::default();
+    let address0 = extended_key.account_key(params)
+        .and_then(|account| account.derive_leaf(params, false, 0))
         .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
-    
-    let address = crate::wallet::generate_address(address0.keypair().public_key(), Network::Testnet10);
-    
+
+    let address = crate::wallet::generate_address(address0.keypair().public_key(), network);
+
     Ok(HDWalletInfo {
         seed: hex::encode(seed),
+        mnemonic: Some(mnemonic.to_string()),
         address,
-        network: "testnet-10".to_string(),
+        network: network.name().to_string(),
     })
 }
 
-pub async fn load_hd_wallet(seed_hex: &str) -> Result<HDWalletInfo> {
-    let seed = hex::decode(seed_hex)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    if seed.len() != 32 {
-        return Err(KaspaGraffitiError::InvalidPrivateKey);
+/// Decode an HD wallet seed given either as hex (16 to 64 bytes, checked by
+/// `ExtendedKey::from_seed`) or as a BIP39 mnemonic phrase (no passphrase).
+fn decode_seed(seed: &str) -> Result<Vec<u8>> {
+    if let Ok(bytes) = hex::decode(seed.trim()) {
+        return Ok(bytes);
     }
+
+    let mnemonic = Mnemonic::from_phrase(seed)
+        .map_err(|e| KaspaGraffitiError::Wallet(format!("Invalid mnemonic: {}", e)))?;
+    Ok(mnemonic.to_seed("").to_vec())
+}
+
+/// The `params` account node of the HD wallet with seed `seed`.
+fn hd_account_key(seed: &str, params: DerivationParams) -> Result<crate::wallet::ExtendedKey> {
+    let seed = decode_seed(seed)?;
+    crate::wallet::ExtendedKey::from_seed(seed.as_slice())
+        .and_then(|master| master.account_key(params))
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))
+}
+
+/// Receive or change key `index` under an account node from `hd_account_key`.
+fn derive_hd_key(account_key: &crate::wallet::ExtendedKey, params: DerivationParams, is_change: bool, index: u32) -> Result<crate::wallet::ExtendedKey> {
+    account_key.derive_leaf(params, is_change, index)
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))
+}
+
+pub async fn load_hd_wallet(seed_hex: &str) -> Result<HDWalletInfo> {
+    load_hd_wallet_on(seed_hex, Network::Testnet10).await
+}
+
+pub async fn load_hd_wallet_on(seed_hex: &str, network: Network) -> Result<HDWalletInfo> {
+    let seed = decode_seed(seed_hex)?;
+    let mnemonic = if hex::decode(seed_hex.trim()).is_err() {
+        Some(seed_hex.split_whitespace().collect::<Vec<_>>().join(" "))
+    } else {
+        None
+    };
     
     let extended_key = crate::wallet::ExtendedKey::from_seed(seed.as_slice())
         .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
     
-    let address0 = extended_key.derive_address_index(0)
+    let params = DerivationParams::default();
+    let address0 = extended_key.account_key(params)
+        .and_then(|account| account.derive_leaf(params, false, 0))
         .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
     
-    let address = crate::wallet::generate_address(address0.keypair().public_key(), Network::Testnet10);
+    let address = crate::wallet::generate_address(address0.keypair().public_key(), network);
     
     Ok(HDWalletInfo {
         seed: hex::encode(seed),
+        mnemonic,
         address,
-        network: "testnet-10".to_string(),
+        network: network.name().to_string(),
     })
 }
 
 pub async fn derive_address_from_seed(seed_hex: &str, index: u32, is_change: bool) -> Result<DerivedAddressInfo> {
-    let seed = hex::decode(seed_hex)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    if seed.len() != 32 {
-        return Err(KaspaGraffitiError::InvalidPrivateKey);
-    }
+    derive_address_from_seed_on(seed_hex, index, is_change, None, DerivationParams::default(), Network::Testnet10).await
+}
+
+/// Derive address `index` at `m/44'/coin_type'/account'/{0,1}/index` for
+/// `params`, or at `path` when given. A path's last two levels are reported
+/// as the index and chain instead of the `index` and `is_change` arguments.
+pub async fn derive_address_from_seed_on(seed_hex: &str, index: u32, is_change: bool, path: Option<&str>, params: DerivationParams, network: Network) -> Result<DerivedAddressInfo> {
+    let seed = decode_seed(seed_hex)?;
     
     let extended_key = crate::wallet::ExtendedKey::from_seed(seed.as_slice())
         .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
     
-    let derived = if is_change {
-        extended_key.derive_change_index(index)
-            .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?
-    } else {
-        extended_key.derive_address_index(index)
-            .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?
+    let derived = match path {
+        Some(path) => extended_key.derive_path(path),
+        None => extended_key.account_key(params)
+            .and_then(|account| account.derive_leaf(params, is_change, index)),
+    }
+    .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+    let (index, is_change) = match path {
+        Some(path) => {
+            let levels = parse_path(path).map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+            let chain = levels.len().checked_sub(2).map(|i| levels[i]);
+            (derived.child_index() % HARDENED_OFFSET, chain == Some(1))
+        }
+        None => (index, is_change),
     };
     
     let keypair = derived.keypair();
-    let address = crate::wallet::generate_address(keypair.public_key(), Network::Testnet10);
+    let address = crate::wallet::generate_address(keypair.public_key(), network);
     
     Ok(DerivedAddressInfo {
         address,
         index,
         is_change,
+        chain: Chain::from_is_change(is_change),
         private_key: keypair.to_hex(),
         public_key: keypair.public_key_hex(),
     })
 }
 
 pub async fn derive_many_addresses(seed_hex: &str, count: u32, is_change: bool) -> Result<Vec<DerivedAddressInfo>> {
-    let seed = hex::decode(seed_hex)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    if seed.len() != 32 {
-        return Err(KaspaGraffitiError::InvalidPrivateKey);
-    }
+    derive_many_addresses_on(seed_hex, count, is_change, DerivationParams::default(), Network::Testnet10).await
+}
+
+pub async fn derive_many_addresses_on(seed_hex: &str, count: u32, is_change: bool, params: DerivationParams, network: Network) -> Result<Vec<DerivedAddressInfo>> {
+    let seed = decode_seed(seed_hex)?;
     
     let extended_key = crate::wallet::ExtendedKey::from_seed(seed.as_slice())
         .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
     
-    let mut addresses = Vec::new();
+    let account = extended_key.account_key(params)
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+    
+    let mut addresses = Vec::with_capacity(count as usize);
     for i in 0..count {
-        let derived = if is_change {
-            extended_key.derive_change_index(i)
-                .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?
-        } else {
-            extended_key.derive_address_index(i)
-                .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?
-        };
+        let derived = account.derive_leaf(params, is_change, i)
+            .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
         
         let keypair = derived.keypair();
-        let address = crate::wallet::generate_address(keypair.public_key(), Network::Testnet10);
+        let address = crate::wallet::generate_address(keypair.public_key(), network);
         
         addresses.push(DerivedAddressInfo {
             address,
             index: i,
             is_change,
+            chain: Chain::from_is_change(is_change),
             private_key: keypair.to_hex(),
             public_key: keypair.public_key_hex(),
         });
@@ -140,301 +301,4505 @@ pub async fn derive_many_addresses(seed_hex: &str, count: u32, is_change: bool)
     Ok(addresses)
 }
 
+pub async fn account_xpub(seed_hex: &str) -> Result<String> {
+    account_xpub_on(seed_hex, DerivationParams::default(), Network::Testnet10).await
+}
+
+/// Extended public key of the seed's `m/44'/coin_type'/account'` node, for
+/// `derive_many_addresses_from_xpub` on a machine without the seed.
+pub async fn account_xpub_on(seed_hex: &str, params: DerivationParams, network: Network) -> Result<String> {
+    let seed = decode_seed(seed_hex)?;
+    let account = crate::wallet::ExtendedKey::from_seed(seed.as_slice())
+        .and_then(|key| key.account_key(params))
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+    Ok(account.neuter().to_xpub(network))
+}
+
 #[derive(serde::Serialize)]
-pub struct SendResult {
-    pub txid: String,
-    pub fee: u64,
-    pub change: u64,
+pub struct WatchOnlyAddress {
     pub address: String,
+    pub index: u32,
+    pub public_key: String,
 }
 
-#[derive(serde::Serialize)]
-pub struct UtxoInfo {
-    pub txid: String,
-    pub vout: u32,
-    pub amount: u64,
-    pub script_pubkey: String,
+/// The first `count` receive addresses under an account xpub, without any
+/// private key. The xpub's version picks the network: mainnet for `xpub`,
+/// testnet-10 for `tpub`.
+pub async fn derive_many_addresses_from_xpub(xpub: &str, count: u32) -> Result<Vec<WatchOnlyAddress>> {
+    let (account, network) = crate::wallet::ExtendedKey::from_xpub(xpub.trim())
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+    let mut addresses = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let derived = account.derive_leaf(DerivationParams::default(), false, index)
+            .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+
+        addresses.push(WatchOnlyAddress {
+            address: crate::wallet::generate_address(derived.public_key(), network),
+            index,
+            public_key: hex::encode(derived.public_key().serialize()),
+        });
+    }
+
+    Ok(addresses)
 }
 
-pub async fn get_balance(
-    address: &str,
-    rpc_url: Option<&str>,
-) -> Result<BalanceInfo> {
-    let client = RpcClient::new(rpc_url);
+/// External addresses recorded in a backup and re-derived by `verify_backup`.
+pub const BACKUP_SAMPLE_SIZE: u32 = 5;
+const BACKUP_VERSION: u32 = 1;
 
-    let response = client.get_balance_by_address(address).await
-        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+/// HD wallet backup file: the seed in plain hex, with the first external
+/// addresses it derives so a restore can be checked. Keep it offline.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct WalletBackup {
+    pub version: u32,
+    pub network: String,
+    pub seed: String,
+    pub addresses: Vec<String>,
+    pub checksum: String,
+}
 
-    Ok(BalanceInfo {
-        balance: response.balance,
-        address: address.to_string(),
-    })
+impl WalletBackup {
+    /// SHA-256 over every other field, one per line.
+    fn compute_checksum(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}\n{}\n{}\n", self.version, self.network, self.seed));
+        for address in &self.addresses {
+            hasher.update(address.as_bytes());
+            hasher.update(b"\n");
+        }
+        hex::encode(hasher.finalize())
+    }
 }
 
-pub async fn get_utxos(
-    address: &str,
-    rpc_url: Option<&str>,
-) -> Result<Vec<UtxoInfo>> {
-    let client = RpcClient::new(rpc_url);
+/// An address in a backup that the stored seed does not derive.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct AddressMismatch {
+    pub index: u32,
+    pub expected: String,
+    pub found: String,
+}
 
-    let response = client.get_utxos_by_address(address).await
-        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+#[derive(Debug, serde::Serialize)]
+pub struct BackupVerification {
+    pub path: String,
+    pub network: String,
+    pub checksum_valid: bool,
+    pub addresses_checked: u32,
+    pub mismatches: Vec<AddressMismatch>,
+    pub is_valid: bool,
+}
 
-    let utxos: Vec<UtxoInfo> = response.entries.into_iter().map(|e| UtxoInfo {
-        txid: e.outpoint.transaction_id,
-        vout: e.outpoint.index,
-        amount: e.utxo_entry.amount,
-        script_pubkey: e.utxo_entry.script_public_key.script,
-    }).collect();
+/// Write a backup of `seed_hex` (hex seed or mnemonic) to `path`.
+pub async fn export_backup(seed_hex: &str, path: &str, network: Network) -> Result<WalletBackup> {
+    let seed = hex::encode(decode_seed(seed_hex)?);
+    let addresses = derive_many_addresses_on(&seed, BACKUP_SAMPLE_SIZE, false, DerivationParams::default(), network).await?
+        .into_iter()
+        .map(|derived| derived.address)
+        .collect();
 
-    Ok(utxos)
+    let mut backup = WalletBackup {
+        version: BACKUP_VERSION,
+        network: network.name().to_string(),
+        seed,
+        addresses,
+        checksum: String::new(),
+    };
+    backup.checksum = backup.compute_checksum();
+
+    let json = serde_json::to_string_pretty(&backup)
+        .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
+    std::fs::write(path, json)?;
+
+    Ok(backup)
 }
 
-pub async fn send_graffiti(
-    private_key: &str,
-    message: &str,
-    _mimetype: Option<&str>,
+/// Check a backup written by `export_backup`: its checksum, and that the
+/// stored seed still derives every listed address.
+pub async fn verify_backup(path: &str) -> Result<BackupVerification> {
+    let json = std::fs::read_to_string(path)?;
+    let backup: WalletBackup = serde_json::from_str(&json)
+        .map_err(|e| KaspaGraffitiError::Encoding(format!("Invalid backup file: {}", e)))?;
+    let network = Network::from_name(&backup.network)
+        .map_err(|e| KaspaGraffitiError::Wallet(e.to_string()))?;
+
+    let checksum_valid = backup.compute_checksum() == backup.checksum;
+
+    let count = backup.addresses.len() as u32;
+    let derived = derive_many_addresses_on(&backup.seed, count, false, DerivationParams::default(), network).await?;
+    let mismatches: Vec<AddressMismatch> = derived.into_iter()
+        .zip(&backup.addresses)
+        .filter(|(derived, found)| derived.address != **found)
+        .map(|(derived, found)| AddressMismatch {
+            index: derived.index,
+            expected: derived.address,
+            found: found.clone(),
+        })
+        .collect();
+
+    Ok(BackupVerification {
+        path: path.to_string(),
+        network: backup.network,
+        checksum_valid,
+        addresses_checked: count,
+        is_valid: checksum_valid && mismatches.is_empty() && count > 0,
+        mismatches,
+    })
+}
+
+/// Consecutive unused addresses after which `scan_hd_wallet` stops, per BIP44.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+pub async fn scan_hd_wallet(seed_hex: &str, rpc_url: Option<&str>, gap_limit: Option<u32>) -> Result<HDScanResult> {
+    scan_hd_wallet_on(seed_hex, rpc_url, gap_limit, DerivationParams::default(), Network::Testnet10, true).await
+}
+
+/// Find the used addresses of an HD wallet's `params` account on both the
+/// external and change chains, stopping each chain after `gap_limit`
+/// (default `DEFAULT_GAP_LIMIT`) consecutive unused addresses.
+///
+/// An address counts as used if it has any transaction in its history, so
+/// one that has since been emptied doesn't end the scan early. Both chains
+/// are checked together in one batched request per round, followed by one
+/// for the balances of the used addresses.
+pub async fn scan_hd_wallet_on(
+    seed_hex: &str,
     rpc_url: Option<&str>,
-    _fee_rate: u64,
-) -> Result<SendResult> {
-    let private_bytes = hex::decode(private_key)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    if private_bytes.len() != 32 {
-        return Err(KaspaGraffitiError::InvalidPrivateKey);
-    }
-    let private_key_array: [u8; 32] = private_bytes.try_into()
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    gap_limit: Option<u32>,
+    params: DerivationParams,
+    network: Network,
+    check_network: bool,
+) -> Result<HDScanResult> {
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+    scan_hd_wallet_with(&client, seed_hex, gap_limit, params, network).await
+}
 
-    let secp = Secp256k1::new();
-    let keypair = secp256k1::KeyPair::from_seckey_slice(&secp, &private_key_array)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    let (xonly_pubkey, _) = keypair.x_only_public_key();
-    let xonly_bytes: [u8; 32] = xonly_pubkey.serialize();
+/// `scan_hd_wallet_on` against a client whose network has been checked.
+async fn scan_hd_wallet_with(
+    client: &RpcClient,
+    seed_hex: &str,
+    gap_limit: Option<u32>,
+    params: DerivationParams,
+    network: Network,
+) -> Result<HDScanResult> {
+    let gap_limit = gap_limit.unwrap_or(DEFAULT_GAP_LIMIT).max(1);
 
-    // Create address directly using kaspa-addresses API
-    use kaspa_addresses::{Address, Version};
-    let prefix = Network::Testnet10.to_prefix();
-    let address = Address::new(prefix, Version::PubKey, &xonly_bytes);
-    let address = address.to_string();
+    let account_key = hd_account_key(seed_hex, params)?;
 
-    let client = RpcClient::new(rpc_url);
+    let mut chains = [GapScan::default(), GapScan::default()];
+    let mut result = HDScanResult {
+        addresses: Vec::new(),
+        total_balance: 0,
+        highest_external_index: None,
+        highest_change_index: None,
+    };
 
-    let utxos_response = client.get_utxos_by_addresses(vec![address.clone()]).await
-        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    while chains.iter().any(|c| !c.done) {
+        let mut batch = Vec::new();
+        for (is_change, chain) in [false, true].into_iter().zip(&chains) {
+            if chain.done {
+                continue;
+            }
+            for index in chain.next_index..chain.next_index + gap_limit {
+                let derived = derive_hd_key(&account_key, params, is_change, index)?;
+                let address = crate::wallet::generate_address(derived.keypair().public_key(), network);
+                batch.push((is_change, index, address));
+            }
+        }
 
-    if utxos_response.entries.is_empty() {
-        return Err(KaspaGraffitiError::NoUtxos);
-    }
+        let active = client.get_active_addresses(batch.iter().map(|(_, _, a)| a.clone()).collect()).await
+            .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+        let used: Vec<String> = batch.iter().map(|(_, _, a)| a).filter(|a| active.contains(*a)).cloned().collect();
+        let mut balances: HashMap<String, u64> = used.iter().map(|a| (a.clone(), 0)).collect();
+        if !used.is_empty() {
+            let response = client.get_unspent_utxos_by_addresses(used).await
+                .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+            for entry in response.entries {
+                if let Some(balance) = balances.get_mut(&entry.address) {
+                    *balance += entry.utxo_entry.amount;
+                }
+            }
+        }
+
+        for (is_change, index, address) in batch {
+            let chain = &mut chains[is_change as usize];
+            if chain.done {
+                continue;
+            }
+            chain.next_index = index + 1;
 
-    let message_bytes = message.as_bytes().to_vec();
-    if message_bytes.len() > 100 {
-        return Err(KaspaGraffitiError::Encoding(
-            format!("Message too long: {} bytes (max: 100)", message_bytes.len())
-        ));
+            match balances.get(address.as_str()) {
+                Some(&balance) => {
+                    chain.gap = 0;
+                    if is_change {
+                        result.highest_change_index = Some(index);
+                    } else {
+                        result.highest_external_index = Some(index);
+                    }
+                    result.total_balance += balance;
+                    result.addresses.push(ScannedAddress { address, index, is_change, balance });
+                }
+                None => {
+                    chain.gap += 1;
+                    chain.done = chain.gap >= gap_limit;
+                }
+            }
+        }
     }
 
-    let mut signer = KaspaTransactionSigner::new();
+    Ok(result)
+}
 
-    let mut total_input: u64 = 0;
-    for utxo in &utxos_response.entries {
-        let script_pubkey_hex = &utxo.utxo_entry.script_public_key.script;
-        let script_pubkey: Vec<u8> = hex::decode(script_pubkey_hex)
-            .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
+pub async fn get_hd_balance(seed_hex: &str, gap_limit: u32, rpc_url: Option<&str>) -> Result<HDBalanceInfo> {
+    get_hd_balance_on(seed_hex, gap_limit, rpc_url, DerivationParams::default(), Network::Testnet10, true).await
+}
 
-        signer.add_input(
-            &utxo.outpoint.transaction_id,
-            utxo.outpoint.index,
-            utxo.utxo_entry.amount,
-            &script_pubkey,
-        ).map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-        total_input += utxo.utxo_entry.amount;
-    }
+/// Total balance of an HD wallet across its receive and change addresses,
+/// found by a `scan_hd_wallet_on` gap-limit scan. Each round of the scan is
+/// one batched UTXO request covering both chains.
+pub async fn get_hd_balance_on(
+    seed_hex: &str,
+    gap_limit: u32,
+    rpc_url: Option<&str>,
+    params: DerivationParams,
+    network: Network,
+    check_network: bool,
+) -> Result<HDBalanceInfo> {
+    let scan = scan_hd_wallet_on(seed_hex, rpc_url, Some(gap_limit), params, network, check_network).await?;
+    Ok(HDBalanceInfo {
+        balance: scan.total_balance,
+        addresses: scan.addresses.into_iter().filter(|a| a.balance > 0).collect(),
+    })
+}
 
-    let estimated_fee = 1000;
-    let change_amount = total_input.saturating_sub(estimated_fee);
+pub async fn scan_used_addresses(seed_hex: &str, gap_limit: u32, rpc_url: Option<&str>) -> Result<UsedAddresses> {
+    scan_used_addresses_on(seed_hex, gap_limit, rpc_url, DerivationParams::default(), Network::Testnet10, true).await
+}
 
-    if change_amount < 1000 {
-        return Err(KaspaGraffitiError::InsufficientBalance(total_input, estimated_fee));
-    }
+/// The addresses of an HD wallet that have ever received funds, with their
+/// keys, found by a `scan_hd_wallet_on` gap-limit scan of both chains.
+pub async fn scan_used_addresses_on(
+    seed_hex: &str,
+    gap_limit: u32,
+    rpc_url: Option<&str>,
+    params: DerivationParams,
+    network: Network,
+    check_network: bool,
+) -> Result<UsedAddresses> {
+    let scan = scan_hd_wallet_on(seed_hex, rpc_url, Some(gap_limit), params, network, check_network).await?;
 
-    signer.add_output(&address, change_amount)
-        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+    let account_key = hd_account_key(seed_hex, params)?;
+    let mut addresses = scan.addresses.into_iter().map(|used| {
+        let derived = derive_hd_key(&account_key, params, used.is_change, used.index)?;
+        let keypair = derived.keypair();
+        Ok(DerivedAddressInfo {
+            address: used.address,
+            index: used.index,
+            is_change: used.is_change,
+            chain: Chain::from_is_change(used.is_change),
+            private_key: keypair.to_hex(),
+            public_key: keypair.public_key_hex(),
+        })
+    }).collect::<Result<Vec<_>>>()?;
+    addresses.sort_by_key(|a| (a.is_change, a.index));
 
-    signer.set_payload(&message_bytes);
+    Ok(UsedAddresses {
+        addresses,
+        next_index: scan.highest_external_index.map_or(0, |i| i + 1),
+        next_change_index: scan.highest_change_index.map_or(0, |i| i + 1),
+    })
+}
 
-    let signed_tx = signer.sign(&private_key_array)
-        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+pub async fn send_graffiti_hd(
+    seed_hex: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<HDSendResult> {
+    send_graffiti_hd_on(seed_hex, message, mimetype, rpc_url, fee_rate, DerivationParams::default(), Network::Testnet10, true).await
+}
 
-    let json_tx = serde_json::to_value(signed_tx.json())
-        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+/// Send a graffiti funded by UTXOs across all used addresses of an HD
+/// wallet's `params` account.
+///
+/// Addresses are found with `scan_hd_wallet`, and each input is signed with
+/// the key of the address it belongs to. Change goes to the first unused
+/// change address.
+pub async fn send_graffiti_hd_on(
+    seed_hex: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    params: DerivationParams,
+    network: Network,
+    check_network: bool,
+) -> Result<HDSendResult> {
+    let payload = graffiti_payload(message, mimetype, false)?;
 
-    let submit_response = client.submit_transaction_json(&json_tx).await
-        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+    let scan = scan_hd_wallet_with(&client, seed_hex, None, params, network).await?;
 
-    Ok(SendResult {
-        txid: submit_response.transaction_id,
-        fee: estimated_fee,
+    let account_key = hd_account_key(seed_hex, params)?;
+    let mut keypairs = Vec::new();
+    let mut funded = Vec::new();
+    for scanned in scan.addresses.iter().filter(|a| a.balance > 0) {
+        let derived = derive_hd_key(&account_key, params, scanned.is_change, scanned.index)?;
+        keypairs.push(secp256k1::KeyPair::from_secret_key(secp(), derived.keypair().secret_key()));
+        funded.push(scanned.address.clone());
+    }
+
+    if funded.is_empty() {
+        return Err(KaspaGraffitiError::NoUtxos);
+    }
+
+    let change_index = scan.highest_change_index.map_or(0, |i| i + 1);
+    let change_key = derive_hd_key(&account_key, params, true, change_index)?;
+    let change_address = crate::wallet::generate_address(change_key.keypair().public_key(), network);
+
+    let base_fee = graffiti_signer(&[], &change_address, 0, &payload)?.estimate_mass() * fee_rate;
+    let (signed_tx, fee, change_amount, inputs_from) = select_and_build(
+        &client,
+        funded,
+        Spend::Covering(base_fee + MIN_CHANGE),
+        fee_rate,
+        |selected| {
+            let (signed_tx, fee, change) =
+                build_graffiti_transaction_with(selected, &change_address, &payload, &keypairs, fee_rate, 0)?;
+            Ok((signed_tx, fee, change, selected.iter().map(|e| e.address.clone()).collect()))
+        },
+    ).await?;
+
+    let txid = submit_signed(&client, &signed_tx, false).await?;
+
+    Ok(HDSendResult {
+        txid,
+        fee,
         change: change_amount,
-        address,
+        change_address,
+        inputs_from,
+        payload,
     })
 }
 
-#[derive(serde::Serialize)]
-pub struct WalletInfo {
-    pub private_key: String,
-    pub public_key: String,
-    pub address: String,
-    pub network: String,
+/// Progress of `scan_hd_wallet` along one derivation chain.
+#[derive(Default)]
+struct GapScan {
+    next_index: u32,
+    gap: u32,
+    done: bool,
 }
 
-#[derive(serde::Serialize)]
-pub struct BalanceInfo {
-    pub balance: u64,
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SendResult {
+    pub txid: String,
+    pub fee: u64,
+    pub change: u64,
     pub address: String,
+    /// Exact payload bytes written to the transaction.
+    pub payload: Vec<u8>,
 }
 
-#[derive(serde::Serialize)]
-pub struct HDWalletInfo {
-    pub seed: String,
+/// A signed send that hasn't been broadcast; see `build_graffiti_on` and
+/// `build_transfer_on`.
+#[derive(Debug, Clone)]
+pub struct BuiltSend {
+    pub transaction: KaspaSignedTransaction,
+    pub fee: u64,
+    pub change: u64,
+    /// The sender's address.
     pub address: String,
-    pub network: String,
+    /// Empty for a plain transfer.
+    pub payload: Vec<u8>,
 }
 
+/// What `--dry-run` prints: everything needed to submit the send later.
 #[derive(serde::Serialize)]
-pub struct DerivedAddressInfo {
+pub struct DryRun {
+    pub txid: String,
+    pub fee: u64,
+    pub change: u64,
     pub address: String,
-    pub index: u32,
-    pub is_change: bool,
-    pub private_key: String,
-    pub public_key: String,
+    pub tx_hex: String,
+    pub transaction: JsonTransaction,
+}
+
+impl BuiltSend {
+    pub fn dry_run(&self) -> DryRun {
+        DryRun {
+            txid: self.transaction.id().to_string(),
+            fee: self.fee,
+            change: self.change,
+            address: self.address.clone(),
+            tx_hex: self.transaction.hex().to_string(),
+            transaction: self.transaction.json().clone(),
+        }
+    }
 }
 
+/// A signed graffiti held back until its lock time; see
+/// `send_graffiti_timelocked_on`.
 #[derive(serde::Serialize)]
-pub struct TransferResult {
+pub struct TimelockedGraffiti {
     pub txid: String,
-    pub amount: u64,
-    pub recipient: String,
+    pub lock_time: u64,
     pub fee: u64,
+    pub change: u64,
+    pub address: String,
+    /// Submit this once `lock_time` has passed.
+    pub transaction: JsonTransaction,
 }
 
-pub async fn transfer(
+/// A graffiti that also burned coins; see `send_graffiti_burn_on`.
+#[derive(serde::Serialize)]
+pub struct BurnSendResult {
+    pub txid: String,
+    pub fee: u64,
+    /// Sompi sent to `burn_address`, the transaction's first output.
+    pub burned: u64,
+    pub burn_address: String,
+    pub change: u64,
+    pub address: String,
+    pub payload: Vec<u8>,
+}
+
+impl SendResult {
+    pub fn payload_hex(&self) -> String {
+        hex::encode(&self.payload)
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct HDSendResult {
+    pub txid: String,
+    pub fee: u64,
+    pub change: u64,
+    pub change_address: String,
+    /// Address of each spent input, in input order.
+    pub inputs_from: Vec<String>,
+    pub payload: Vec<u8>,
+}
+
+#[derive(serde::Serialize)]
+pub struct PrioritySendResult {
+    pub txid: String,
+    pub fee: u64,
+    pub fee_rate: u64,
+    pub estimated_seconds: Option<f64>,
+    pub change: u64,
+    pub address: String,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UtxoInfo {
+    pub txid: String,
+    pub vout: u32,
+    pub amount: u64,
+    pub script_pubkey: String,
+    /// DAA scores since the output was included, when the node was asked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+}
+
+impl TryFrom<&UtxoInfo> for SignerInput {
+    type Error = KaspaGraffitiError;
+
+    fn try_from(utxo: &UtxoInfo) -> Result<Self> {
+        let script_pubkey = hex::decode(&utxo.script_pubkey)
+            .map_err(|e| KaspaGraffitiError::Encoding(format!("Invalid script_pubkey for {}:{}: {}", utxo.txid, utxo.vout, e)))?;
+
+        Ok(SignerInput {
+            txid: utxo.txid.clone(),
+            vout: utxo.vout,
+            amount: utxo.amount,
+            script_pubkey,
+        })
+    }
+}
+
+/// Balance of `address`, from a node on whichever network it belongs to.
+pub async fn get_balance(
+    address: &str,
+    rpc_url: Option<&str>,
+) -> Result<BalanceInfo> {
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Serves(address), true).await?;
+    get_balance_with(&client, address).await
+}
+
+/// Balance of a `network` address, from a node on `network`.
+pub async fn get_balance_on(
+    address: &str,
+    rpc_url: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<BalanceInfo> {
+    require_address(address, network)?;
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+    get_balance_with(&client, address).await
+}
+
+/// `get_balance` over any transport, such as `GrpcRpcClient`.
+pub async fn get_balance_with(rpc: &impl RpcTransport, address: &str) -> Result<BalanceInfo> {
+    let response = rpc.get_balance_by_address(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    Ok(BalanceInfo {
+        balance: response.balance,
+        address: address.to_string(),
+    })
+}
+
+/// Balances of several addresses with one batched request, in the order
+/// the addresses were given.
+pub async fn get_balances(addresses: &[&str], rpc_url: Option<&str>) -> Result<Vec<BalanceInfo>> {
+    let addresses = addresses.iter().map(|a| a.to_string()).collect();
+    let balances = rpc_client(rpc_url)?.get_balances_by_addresses(addresses).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    Ok(balances.into_iter().map(|(address, balance)| BalanceInfo { balance, address }).collect())
+}
+
+/// Unspent outputs of `address` over any transport.
+pub async fn get_utxos_with(rpc: &impl RpcTransport, address: &str) -> Result<Vec<UtxoInfo>> {
+    let response = rpc.get_utxos_by_addresses(vec![address.to_string()]).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    Ok(response.entries.into_iter().filter(|e| !e.is_spent).map(|e| UtxoInfo {
+        txid: e.outpoint.transaction_id,
+        vout: e.outpoint.index,
+        amount: e.utxo_entry.amount,
+        script_pubkey: e.utxo_entry.script_public_key.script,
+        confirmations: None,
+    }).collect())
+}
+
+/// Submit a signed transaction over any transport, returning its id.
+pub async fn submit_with(rpc: &impl RpcTransport, tx: &KaspaSignedTransaction) -> Result<String> {
+    let response = rpc.submit_transaction(tx, false).await
+        .map_err(submit_error)?;
+    Ok(response.transaction_id)
+}
+
+/// Unspent outputs of `address`, from a node on whichever network it
+/// belongs to.
+pub async fn get_utxos(
+    address: &str,
+    rpc_url: Option<&str>,
+) -> Result<Vec<UtxoInfo>> {
+    utxos_from(address, rpc_url, NodeNetwork::Serves(address), true).await
+}
+
+/// Unspent outputs of a `network` address, from a node on `network`.
+pub async fn get_utxos_on(
+    address: &str,
+    rpc_url: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<Vec<UtxoInfo>> {
+    require_address(address, network)?;
+    utxos_from(address, rpc_url, NodeNetwork::Is(network), check_network).await
+}
+
+async fn utxos_from(
+    address: &str,
+    rpc_url: Option<&str>,
+    expected: NodeNetwork<'_>,
+    check_network: bool,
+) -> Result<Vec<UtxoInfo>> {
+    let client = rpc_client(rpc_url)?;
+
+    // The DAG info names the node's network, so the check below is free.
+    // Another network's address would just come back empty.
+    let dag = client.get_block_dag_info().await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    require_node_network(&client, expected, check_network).await?;
+
+    let response = client.get_utxos_by_address(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    let utxos: Vec<UtxoInfo> = response.entries.into_iter().map(|e| UtxoInfo {
+        txid: e.outpoint.transaction_id,
+        vout: e.outpoint.index,
+        amount: e.utxo_entry.amount,
+        script_pubkey: e.utxo_entry.script_public_key.script,
+        confirmations: Some(confirmation_depth(dag.virtual_daa_score, e.utxo_entry.block_daa_score)),
+    }).collect();
+
+    Ok(utxos)
+}
+
+/// Rough size of one transaction as returned by the history endpoint, with
+/// resolved inputs and outputs.
+const APPROX_TX_JSON_BYTES: u64 = 1_500;
+
+/// How much data scanning `address`'s full history would fetch, from the
+/// node's transaction count alone.
+pub async fn estimate_scan_size(
+    address: &str,
+    rpc_url: Option<&str>,
+) -> Result<ScanEstimate> {
+    let client = rpc_client(rpc_url)?;
+
+    let count = client.get_transaction_count(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    Ok(ScanEstimate {
+        address: address.to_string(),
+        transaction_count: count.total,
+        approx_bytes: count.total * APPROX_TX_JSON_BYTES,
+        limit_exceeded: count.limit_exceeded,
+    })
+}
+
+/// Sum of unconfirmed mempool outputs paying `address` (e.g. change from a
+/// recent send that the node does not yet report as a spendable UTXO).
+pub async fn pending_change(
+    address: &str,
+    rpc_url: Option<&str>,
+) -> Result<u64> {
+    let client = rpc_client(rpc_url)?;
+    pending_change_with(&client, address).await
+}
+
+/// `pending_change` through an existing client.
+async fn pending_change_with(client: &RpcClient, address: &str) -> Result<u64> {
+    let response = client.get_mempool_entries_by_address(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    let pending = response.entries.iter()
+        .flat_map(|entry| entry.outputs.iter())
+        .filter(|output| output.address == address)
+        .map(|output| output.amount)
+        .sum();
+
+    Ok(pending)
+}
+
+/// Confirmed, unspent balance alongside the amount still pending in the mempool.
+pub async fn get_spendable_balance(
+    address: &str,
+    rpc_url: Option<&str>,
+) -> Result<SpendableBalanceInfo> {
+    let client = rpc_client(rpc_url)?;
+
+    let response = client.get_utxos_by_address(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    let spendable = response.entries.iter()
+        .filter(|e| !e.is_spent)
+        .map(|e| e.utxo_entry.amount)
+        .sum();
+
+    let pending = pending_change_with(&client, address).await?;
+
+    Ok(SpendableBalanceInfo {
+        address: address.to_string(),
+        spendable,
+        pending,
+    })
+}
+
+pub async fn send_graffiti(
     private_key: &str,
-    recipient: &str,
-    amount: u64,
+    message: &str,
+    mimetype: Option<&str>,
     rpc_url: Option<&str>,
-) -> Result<TransferResult> {
-    let private_bytes = hex::decode(private_key)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    if private_bytes.len() != 32 {
-        return Err(KaspaGraffitiError::InvalidPrivateKey);
+    fee: FeePriority,
+    raw: bool,
+    allow_orphan: bool,
+) -> Result<SendResult> {
+    send_graffiti_on(private_key, message, mimetype, rpc_url, fee, raw, allow_orphan, None, Network::Testnet10, true).await
+}
+
+/// Send `message` as a transaction payload, with the change going back to
+/// the sender's own address unless `change_address` is given.
+///
+/// The payload is a `PayloadEncoder` GFX message unless `raw` is set, in
+/// which case the message bytes are written as-is. Set `allow_orphan` when the
+/// inputs include change of a transaction the node may not have seen yet.
+#[allow(clippy::too_many_arguments)]
+pub async fn send_graffiti_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee: FeePriority,
+    raw: bool,
+    allow_orphan: bool,
+    change_address: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<SendResult> {
+    let built = build_graffiti_on(private_key, message, mimetype, rpc_url, fee, raw, change_address, network, check_network).await?;
+
+    let client = rpc_client(rpc_url)?;
+    let txid = submit_signed(&client, &built.transaction, allow_orphan).await?;
+
+    Ok(SendResult {
+        txid,
+        fee: built.fee,
+        change: built.change,
+        address: built.address,
+        payload: built.payload,
+    })
+}
+
+pub async fn build_graffiti(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee: FeePriority,
+    raw: bool,
+) -> Result<BuiltSend> {
+    build_graffiti_on(private_key, message, mimetype, rpc_url, fee, raw, None, Network::Testnet10, true).await
+}
+
+/// Select inputs and sign the graffiti `send_graffiti_on` would send,
+/// without submitting it. The node is still asked for UTXOs and fee rates.
+#[allow(clippy::too_many_arguments)]
+pub async fn build_graffiti_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee: FeePriority,
+    raw: bool,
+    change_address: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<BuiltSend> {
+    let payload = graffiti_payload(message, mimetype, raw)?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+    if let Some(change_address) = change_address {
+        require_address(change_address, network)?;
     }
-    let private_key_array: [u8; 32] = private_bytes.try_into()
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let change_address = change_address.unwrap_or(&address);
 
-    let secp = Secp256k1::new();
-    let keypair = secp256k1::KeyPair::from_seckey_slice(&secp, &private_key_array)
-        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
-    let (xonly_pubkey, _) = keypair.x_only_public_key();
-    let xonly_bytes: [u8; 32] = xonly_pubkey.serialize();
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
 
-    let prefix = Network::Testnet10.to_prefix();
-    let sender_address = kaspa_addresses::Address::new(prefix, kaspa_addresses::Version::PubKey, &xonly_bytes);
-    let sender_address_str = sender_address.to_string();
+    let (fee_rate, _) = resolve_fee_rate(&client, fee).await;
+
+    // Cover the fee for everything but the inputs, plus the minimum change
+    let base_fee = graffiti_signer(&[], change_address, 0, &payload)?.estimate_mass() * fee_rate;
+    let (transaction, fee, change) = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::Covering(base_fee + MIN_CHANGE),
+        fee_rate,
+        |selected| build_graffiti_transaction(selected, change_address, &payload, &private_key_array, fee_rate),
+    ).await?;
+
+    Ok(BuiltSend {
+        transaction,
+        fee,
+        change,
+        address,
+        payload,
+    })
+}
+
+/// An `RpcClient` for `rpc_url`, the public testnet-10 node if `None`.
+fn rpc_client(rpc_url: Option<&str>) -> Result<RpcClient> {
+    RpcClient::new(rpc_url).map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))
+}
+
+/// Keep node rejections typed so callers can tell them apart.
+fn submit_error(e: RpcError) -> KaspaGraffitiError {
+    match e {
+        RpcError::Rejected(reason) => KaspaGraffitiError::Rejected(reason),
+        e => KaspaGraffitiError::Rpc(e.to_string()),
+    }
+}
 
-    let client = RpcClient::new(rpc_url);
+/// Which of the sender's outputs `select_and_build` spends.
+enum Spend<'a> {
+    /// Largest first until they cover this much plus their own fee. The
+    /// amount already includes the outputs, the fee for everything but the
+    /// inputs and any minimum change.
+    Covering(u64),
+    /// Every mature output, for a sweep to spend or `build` to pick from.
+    All,
+    /// This output first, then largest first as for `Covering` until the
+    /// amount is covered.
+    Anchored(&'a GetOutPoint, u64),
+}
 
-    let utxos_response = client.get_utxos_by_addresses(vec![sender_address_str.clone()]).await
+/// Fetch the unspent outputs of `addresses`, pick inputs from them by
+/// `spend` and hand those to `build` to sign. Errors with `NoUtxos` when
+/// there is nothing to spend and `InsufficientBalance` when it falls short.
+async fn select_and_build<T>(
+    client: &RpcClient,
+    addresses: Vec<String>,
+    spend: Spend<'_>,
+    fee_rate: u64,
+    build: impl FnOnce(&[GetUtxosByAddressesEntry]) -> Result<T>,
+) -> Result<T> {
+    let owner = addresses.join(", ");
+    let utxos_response = client.get_unspent_utxos_by_addresses(addresses).await
         .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
 
     if utxos_response.entries.is_empty() {
         return Err(KaspaGraffitiError::NoUtxos);
     }
 
-    let mut signer = KaspaTransactionSigner::new();
+    let virtual_daa_score = client.get_virtual_daa_score().await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
 
-    let mut total_input: u64 = 0;
-    for utxo in &utxos_response.entries {
-        let script_pubkey_hex = &utxo.utxo_entry.script_public_key.script;
-        let script_pubkey: Vec<u8> = hex::decode(script_pubkey_hex)
-            .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
+    let selected = match spend {
+        Spend::Covering(target) => select_utxos(
+            &utxos_response.entries,
+            target,
+            fee_rate,
+            virtual_daa_score,
+            SelectionStrategy::LargestFirst,
+        )
+        .map_err(|e| insufficient_balance(e, 0))?,
+        Spend::All => {
+            let mature: Vec<GetUtxosByAddressesEntry> = utxos_response.entries
+                .into_iter()
+                .filter(|e| is_mature(e, virtual_daa_score))
+                .collect();
+            if mature.is_empty() {
+                return Err(KaspaGraffitiError::NoUtxos);
+            }
+            mature
+        }
+        Spend::Anchored(outpoint, target) => {
+            let anchor_id = format!("{}:{}", outpoint.transaction_id, outpoint.index);
+            let (anchor, others): (Vec<_>, Vec<_>) = utxos_response.entries.into_iter()
+                .partition(|e| e.outpoint.transaction_id == outpoint.transaction_id && e.outpoint.index == outpoint.index);
+            let anchor = anchor.into_iter().next()
+                .ok_or_else(|| KaspaGraffitiError::AnchorUnavailable(format!("{} is not an unspent output of {}", anchor_id, owner)))?;
+            if !is_mature(&anchor, virtual_daa_score) {
+                return Err(KaspaGraffitiError::AnchorUnavailable(format!("{} is an immature coinbase output", anchor_id)));
+            }
+            let others: Vec<GetUtxosByAddressesEntry> = others.into_iter()
+                .filter(|e| is_mature(e, virtual_daa_score))
+                .collect();
+            select_with_anchor(anchor, &others, target, fee_rate)?
+        }
+    };
 
-        signer.add_input(
-            &utxo.outpoint.transaction_id,
-            utxo.outpoint.index,
-            utxo.utxo_entry.amount,
-            &script_pubkey,
-        ).map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-        total_input += utxo.utxo_entry.amount;
-    }
+    build(&selected)
+}
 
-    let fee_buffer = 500u64; // Buffer for minimum fee enforcement
-    let estimated_fee = 5000u64; // Initial estimate
-    let change_amount = total_input.saturating_sub(amount).saturating_sub(estimated_fee);
-    if total_input < amount + estimated_fee {
-        return Err(KaspaGraffitiError::InsufficientBalance(total_input, amount + estimated_fee));
+/// `InsufficientBalance` for a failed selection, counting `held` sompi
+/// already committed to the transaction outside of it.
+fn insufficient_balance(e: CoinSelectionError, held: u64) -> KaspaGraffitiError {
+    match e {
+        CoinSelectionError::InsufficientFunds { available, required } => {
+            KaspaGraffitiError::InsufficientBalance(available.saturating_add(held), required)
+        }
     }
+}
 
-    signer.add_output(recipient, amount)
+/// Submit `signed_tx`, returning its id. A resubmission of a transaction the
+/// node already has counts as sent.
+async fn submit_signed(client: &RpcClient, signed_tx: &KaspaSignedTransaction, allow_orphan: bool) -> Result<String> {
+    let json_tx = serde_json::to_value(signed_tx.json())
         .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
 
-    if change_amount > 0 {
-        signer.add_output(&sender_address_str, change_amount)
-            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+    match client.submit_transaction_json(&json_tx, allow_orphan).await {
+        Ok(response) => Ok(response.transaction_id),
+        Err(RpcError::Rejected(SubmitError::AlreadyAccepted)) => Ok(signed_tx.id().to_string()),
+        Err(e) => Err(submit_error(e)),
     }
+}
 
-    let signed_tx = signer.sign_no_payload(&private_key_array)
-        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+/// Decode a hex private key and derive the sender's P2PK address on `network`.
+fn graffiti_sender(private_key: &str, network: Network) -> Result<([u8; 32], String)> {
+    let private_bytes = hex::decode(private_key)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let private_key_array: [u8; 32] = private_bytes.try_into()
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), &private_key_array)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let (xonly_pubkey, _) = keypair.x_only_public_key();
+    let xonly_bytes: [u8; 32] = xonly_pubkey.serialize();
+
+    // Create address directly using kaspa-addresses API
+    use kaspa_addresses::{Address, Version};
+    let prefix = network.to_prefix();
+    let address = Address::new(prefix, Version::PubKey, &xonly_bytes);
+
+    Ok((private_key_array, address.to_string()))
+}
+
+pub async fn send_graffiti_anchored(
+    private_key: &str,
+    message: &str,
+    anchor_outpoint: &GetOutPoint,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<SendResult> {
+    send_graffiti_anchored_on(private_key, message, anchor_outpoint, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Send a graffiti that spends `anchor_outpoint`, proving the sender controlled
+/// that coin. Other UTXOs are added only if the anchor can't cover the fee.
+///
+/// Fails with `AnchorUnavailable` if the anchor is not an unspent, mature
+/// output of the sender's address.
+pub async fn send_graffiti_anchored_on(
+    private_key: &str,
+    message: &str,
+    anchor_outpoint: &GetOutPoint,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<SendResult> {
+    let payload = graffiti_payload(message, None, false)?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let base_fee = graffiti_signer(&[], &address, 0, &payload)?.estimate_mass() * fee_rate;
+    let (signed_tx, fee, change_amount) = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::Anchored(anchor_outpoint, base_fee + MIN_CHANGE),
+        fee_rate,
+        |selected| build_graffiti_transaction(selected, &address, &payload, &private_key_array, fee_rate),
+    ).await?;
 
-    // Calculate fee based on actual transaction mass
     let json_tx = serde_json::to_value(signed_tx.json())
         .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-    
-    // Get mass from transaction (calculate if not present)
-    let mass = json_tx.get("mass").and_then(|v| v.as_u64()).unwrap_or(0);
-    let fee = std::cmp::max(mass + fee_buffer, estimated_fee);
-    
-    // Recalculate change with actual fee
-    let actual_change = total_input.saturating_sub(amount).saturating_sub(fee);
-    if total_input < amount + fee {
-        return Err(KaspaGraffitiError::InsufficientBalance(total_input, amount + fee));
-    }
-    
-    // If change needs adjustment, recreate transaction with correct fee
-    let final_json_tx = if actual_change != change_amount {
-        // Need to recreate transaction with correct fee
-        let mut signer2 = KaspaTransactionSigner::new();
-        for utxo in &utxos_response.entries {
-            let script_pubkey_hex = &utxo.utxo_entry.script_public_key.script;
-            let script_pubkey: Vec<u8> = hex::decode(script_pubkey_hex)
-                .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
-            signer2.add_input(
-                &utxo.outpoint.transaction_id,
-                utxo.outpoint.index,
-                utxo.utxo_entry.amount,
-                &script_pubkey,
-            ).map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-        }
-        signer2.add_output(recipient, amount)
-            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-        if actual_change > 0 {
-            signer2.add_output(&sender_address_str, actual_change)
-                .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-        }
-        let signed_tx2 = signer2.sign_no_payload(&private_key_array)
-            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
-        serde_json::to_value(signed_tx2.json())
-            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?
-    } else {
-        json_tx
-    };
 
-    let submit_response = client.submit_transaction_json(&final_json_tx).await
-        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    let submit_response = client.submit_transaction_json(&json_tx, false).await
+        .map_err(submit_error)?;
 
-    Ok(TransferResult {
+    Ok(SendResult {
         txid: submit_response.transaction_id,
-        amount,
-        recipient: recipient.to_string(),
         fee,
+        change: change_amount,
+        address,
+        payload,
     })
 }
+
+/// The anchor first, followed by whatever mature `others` are needed to
+/// cover `target` plus the fee for every input.
+fn select_with_anchor(
+    anchor: GetUtxosByAddressesEntry,
+    others: &[GetUtxosByAddressesEntry],
+    target: u64,
+    fee_rate: u64,
+) -> Result<Vec<GetUtxosByAddressesEntry>> {
+    let required = target.saturating_add(input_mass().saturating_mul(fee_rate));
+    let anchor_amount = anchor.utxo_entry.amount;
+    let shortfall = required.saturating_sub(anchor_amount);
+
+    let mut selected = vec![anchor];
+    if shortfall > 0 {
+        let extra = select_spendable(others, shortfall, fee_rate, SelectionStrategy::LargestFirst)
+            .map_err(|e| insufficient_balance(e, anchor_amount))?;
+        selected.extend(extra);
+    }
+
+    Ok(selected)
+}
+
+pub async fn send_graffiti_timelocked(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    lock_time: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<TimelockedGraffiti> {
+    send_graffiti_timelocked_on(private_key, message, mimetype, lock_time, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Build and sign a graffiti that can't be accepted before `lock_time`, a
+/// DAA score or (at or above `LOCK_TIME_THRESHOLD`) a unix time in ms.
+///
+/// Nodes reject a transaction before its lock time, so nothing is broadcast:
+/// the caller must submit `transaction` with
+/// `RpcClient::submit_transaction_json` once the lock time has passed. The
+/// spent UTXOs stay unspent until then and may be used by other sends, which
+/// invalidates the reveal.
+pub async fn send_graffiti_timelocked_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    lock_time: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<TimelockedGraffiti> {
+    let payload = graffiti_payload(message, mimetype, false)?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let virtual_daa_score = client.get_virtual_daa_score().await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    check_lock_time(lock_time, virtual_daa_score, now_ms)?;
+
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), &private_key_array)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let base_fee = graffiti_signer(&[], &address, 0, &payload)?.estimate_mass() * fee_rate;
+    let (signed_tx, fee, change) = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::Covering(base_fee + MIN_CHANGE),
+        fee_rate,
+        |selected| build_graffiti_transaction_with(selected, &address, &payload, &[keypair], fee_rate, lock_time),
+    ).await?;
+
+    Ok(TimelockedGraffiti {
+        txid: signed_tx.id().to_string(),
+        lock_time,
+        fee,
+        change,
+        address,
+        transaction: signed_tx.json().clone(),
+    })
+}
+
+/// `lock_time` must still be ahead of the DAG (for a DAA score) or the clock
+/// (for a timestamp), otherwise the post isn't delayed at all.
+fn check_lock_time(lock_time: u64, virtual_daa_score: u64, now_ms: u64) -> Result<()> {
+    if lock_time < LOCK_TIME_THRESHOLD {
+        if lock_time <= virtual_daa_score {
+            return Err(KaspaGraffitiError::InvalidLockTime(format!(
+                "DAA score {} has already passed (virtual DAA score is {})", lock_time, virtual_daa_score
+            )));
+        }
+    } else if lock_time <= now_ms {
+        return Err(KaspaGraffitiError::InvalidLockTime(format!(
+            "time {} ms has already passed (now is {} ms)", lock_time, now_ms
+        )));
+    }
+    Ok(())
+}
+
+pub async fn send_graffiti_burn(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    burn_amount: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<BurnSendResult> {
+    send_graffiti_burn_on(private_key, message, mimetype, burn_amount, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Send a graffiti that also pays `burn_amount` sompi to the network's
+/// `burn_address`, as provable proof of cost. The rest comes back to the
+/// sender as change.
+pub async fn send_graffiti_burn_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    burn_amount: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<BurnSendResult> {
+    let payload = graffiti_payload(message, mimetype, false)?;
+    let burn_address = network.burn_address();
+    check_output_dust(burn_address, burn_amount)?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let base_fee = burn_signer(&[], &address, burn_address, burn_amount, 0, &payload)?.estimate_mass() * fee_rate;
+    let (signed_tx, fee, change) = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::Covering(burn_amount + base_fee + MIN_CHANGE),
+        fee_rate,
+        |selected| build_burn_graffiti_transaction(
+            selected,
+            &address,
+            burn_address,
+            burn_amount,
+            &payload,
+            &private_key_array,
+            fee_rate,
+        ),
+    ).await?;
+
+    let json_tx = serde_json::to_value(signed_tx.json())
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+    let response = client.submit_transaction_json(&json_tx, false).await
+        .map_err(submit_error)?;
+
+    Ok(BurnSendResult {
+        txid: response.transaction_id,
+        fee,
+        burned: burn_amount,
+        burn_address: burn_address.to_string(),
+        change,
+        address,
+        payload,
+    })
+}
+
+/// Build and sign a graffiti paying `burn_amount` to `burn_address` and the
+/// rest, less the fee, back to `address`. Returns the signed transaction,
+/// the fee and the change.
+fn build_burn_graffiti_transaction(
+    entries: &[GetUtxosByAddressesEntry],
+    address: &str,
+    burn_address: &str,
+    burn_amount: u64,
+    payload: &[u8],
+    private_key: &[u8; 32],
+    fee_rate: u64,
+) -> Result<(KaspaSignedTransaction, u64, u64)> {
+    if entries.is_empty() {
+        return Err(KaspaGraffitiError::EmptyTransaction);
+    }
+    let total_input: u64 = entries.iter().map(|e| e.utxo_entry.amount).sum();
+    if burn_amount >= total_input {
+        return Err(KaspaGraffitiError::InsufficientBalance(total_input, burn_amount + MIN_CHANGE));
+    }
+
+    let remaining = total_input - burn_amount;
+    let fee = burn_signer(entries, address, burn_address, burn_amount, remaining, payload)?.estimate_mass() * fee_rate;
+    let change = remaining.saturating_sub(fee);
+    if change < MIN_CHANGE {
+        return Err(KaspaGraffitiError::InsufficientBalance(total_input, burn_amount + fee + MIN_CHANGE));
+    }
+
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), private_key)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let signed_tx = burn_signer(entries, address, burn_address, burn_amount, change, payload)?
+        .sign_with_signers(&[keypair])
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    Ok((signed_tx, fee, change))
+}
+
+fn burn_signer(
+    entries: &[GetUtxosByAddressesEntry],
+    address: &str,
+    burn_address: &str,
+    burn_amount: u64,
+    change_amount: u64,
+    payload: &[u8],
+) -> Result<KaspaTransactionSigner> {
+    let mut signer = outputs_signer(entries, &[(burn_address, burn_amount), (address, change_amount)])?;
+    signer.set_payload(payload);
+    Ok(signer)
+}
+
+/// Largest mass the network relays as a standard transaction.
+const MAX_STANDARD_MASS: u64 = 100_000;
+
+pub async fn split_oversized_send(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<Vec<SendResult>> {
+    split_oversized_send_on(private_key, message, mimetype, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Send a graffiti spending every mature UTXO of the sender, splitting the
+/// inputs across several transactions when they don't fit in one.
+///
+/// Each extra transaction consolidates a subset of the inputs back to the
+/// sender. The last one spends those consolidated outputs while they are
+/// still unconfirmed and carries the payload. Results are in submission order,
+/// with the graffiti last.
+pub async fn split_oversized_send_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<Vec<SendResult>> {
+    let payload = graffiti_payload(message, mimetype, false)?;
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let plan = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::All,
+        fee_rate,
+        |entries| plan_split_send(entries, &address, &payload, &private_key_array, fee_rate),
+    ).await?;
+
+    let mut results = Vec::with_capacity(plan.len());
+    for (signed_tx, fee, change) in plan {
+        let json_tx = serde_json::to_value(signed_tx.json())
+            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+        // The graffiti spends consolidated outputs the node may not have yet
+        let submit_response = client.submit_transaction_json(&json_tx, !results.is_empty()).await
+            .map_err(submit_error)?;
+
+        results.push(SendResult {
+            txid: submit_response.transaction_id,
+            fee,
+            change,
+            address: address.clone(),
+            payload: hex::decode(&signed_tx.json().payload).unwrap_or_default(),
+        });
+    }
+
+    Ok(results)
+}
+
+/// Sign the consolidation transactions (if any) and the final graffiti
+/// transaction for `split_oversized_send`, each within `MAX_STANDARD_MASS`.
+fn plan_split_send(
+    entries: &[GetUtxosByAddressesEntry],
+    address: &str,
+    payload: &[u8],
+    private_key: &[u8; 32],
+    fee_rate: u64,
+) -> Result<Vec<(KaspaSignedTransaction, u64, u64)>> {
+    let final_mass = graffiti_signer(entries, address, 0, payload)?.estimate_mass();
+    if final_mass <= MAX_STANDARD_MASS {
+        return Ok(vec![build_graffiti_transaction(entries, address, payload, private_key, fee_rate)?]);
+    }
+
+    let base_mass = graffiti_signer(&[], address, 0, &[])?.estimate_mass();
+    let max_inputs = ((MAX_STANDARD_MASS - base_mass) / input_mass()) as usize;
+
+    let mut plan = Vec::new();
+    let mut consolidated = Vec::new();
+    for chunk in entries.chunks(max_inputs) {
+        let total_input: u64 = chunk.iter().map(|e| e.utxo_entry.amount).sum();
+        let fee = graffiti_signer(chunk, address, total_input, &[])?.estimate_mass() * fee_rate;
+        if total_input < fee + MIN_CHANGE {
+            return Err(KaspaGraffitiError::InsufficientBalance(total_input, fee + MIN_CHANGE));
+        }
+        let output = total_input - fee;
+
+        let signed_tx = graffiti_signer(chunk, address, output, &[])?
+            .sign(private_key)
+            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+        // Spend the consolidated output before it confirms
+        consolidated.push(GetUtxosByAddressesEntry {
+            address: address.to_string(),
+            outpoint: GetOutPoint {
+                transaction_id: signed_tx.id().to_string(),
+                index: 0,
+            },
+            utxo_entry: GetUtxoEntry {
+                amount: output,
+                script_public_key: chunk[0].utxo_entry.script_public_key.clone(),
+                block_daa_score: 0,
+                is_coinbase: false,
+            },
+            is_spent: false,
+        });
+        plan.push((signed_tx, fee, output));
+    }
+
+    plan.extend(plan_split_send(&consolidated, address, payload, private_key, fee_rate)?);
+    Ok(plan)
+}
+
+pub async fn send_graffiti_chunked(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<Vec<SendResult>> {
+    send_graffiti_chunked_on(private_key, message, mimetype, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Send a message too large for one payload as a chain of chunk transactions.
+///
+/// Each transaction spends the previous one's unconfirmed change, so they must
+/// be accepted in order. Readers rebuild the message with
+/// `PayloadEncoder::reassemble`. Results are in chunk order.
+pub async fn send_graffiti_chunked_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<Vec<SendResult>> {
+    let graffiti = GraffitiMessage::new(
+        message.to_string(),
+        Some(mimetype.unwrap_or("text/plain").to_string()),
+    );
+    let payloads = PayloadEncoder::encode_chunked(&graffiti, MAX_PAYLOAD_SIZE)
+        .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    // The first transaction funds the whole chain; later ones add one input each
+    let mut base_fee = (payloads.len() as u64 - 1) * input_mass() * fee_rate;
+    for payload in &payloads {
+        base_fee += graffiti_signer(&[], &address, 0, payload)?.estimate_mass() * fee_rate;
+    }
+    let plan = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::Covering(base_fee + MIN_CHANGE),
+        fee_rate,
+        |selected| plan_chunked_send(selected.to_vec(), &address, &payloads, &private_key_array, fee_rate),
+    ).await?;
+
+    let total = plan.len();
+    let mut results: Vec<SendResult> = Vec::with_capacity(total);
+    for (i, ((signed_tx, fee, change), payload)) in plan.into_iter().zip(payloads).enumerate() {
+        let json_tx = serde_json::to_value(signed_tx.json())
+            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+        // Every chunk after the first spends the previous, unconfirmed change
+        let submit_response = client.submit_transaction_json(&json_tx, i > 0).await
+            .map_err(|e| KaspaGraffitiError::ChunkFailed {
+                chunk: i + 1,
+                total,
+                sent: results.iter().map(|r| r.txid.clone()).collect(),
+                source: Box::new(submit_error(e)),
+            })?;
+
+        results.push(SendResult {
+            txid: submit_response.transaction_id,
+            fee,
+            change,
+            address: address.clone(),
+            payload,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Sign one transaction per chunk payload, each spending the previous change.
+fn plan_chunked_send(
+    selected: Vec<GetUtxosByAddressesEntry>,
+    address: &str,
+    payloads: &[Vec<u8>],
+    private_key: &[u8; 32],
+    fee_rate: u64,
+) -> Result<Vec<(KaspaSignedTransaction, u64, u64)>> {
+    let script_public_key = selected[0].utxo_entry.script_public_key.clone();
+
+    let mut plan = Vec::with_capacity(payloads.len());
+    let mut inputs = selected;
+    for payload in payloads {
+        let (signed_tx, fee, change) =
+            build_graffiti_transaction(&inputs, address, payload, private_key, fee_rate)?;
+
+        inputs = vec![GetUtxosByAddressesEntry {
+            address: address.to_string(),
+            outpoint: GetOutPoint {
+                transaction_id: signed_tx.id().to_string(),
+                index: 0,
+            },
+            utxo_entry: GetUtxoEntry {
+                amount: change,
+                script_public_key: script_public_key.clone(),
+                block_daa_score: 0,
+                is_coinbase: false,
+            },
+            is_spent: false,
+        }];
+        plan.push((signed_tx, fee, change));
+    }
+
+    Ok(plan)
+}
+
+/// Build the transaction payload for `message`, defaulting to text/plain.
+fn graffiti_payload(message: &str, mimetype: Option<&str>, raw: bool) -> Result<Vec<u8>> {
+    if raw {
+        if message.len() > MAX_PAYLOAD_SIZE {
+            return Err(KaspaGraffitiError::Encoding(
+                format!("Content too large: {} bytes (max: {})", message.len(), MAX_PAYLOAD_SIZE)
+            ));
+        }
+        return Ok(message.as_bytes().to_vec());
+    }
+
+    let graffiti = GraffitiMessage::new(
+        message.to_string(),
+        Some(mimetype.unwrap_or("text/plain").to_string()),
+    );
+    PayloadEncoder::encode(&graffiti)
+        .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))
+}
+
+/// Where a transaction carried its graffiti message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraffitiSource {
+    Payload,
+    OpReturn,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DecodedGraffiti {
+    pub message: GraffitiMessage,
+    pub source: GraffitiSource,
+    /// The timestamp is far in the future or before Kaspa's genesis.
+    pub suspicious_timestamp: bool,
+}
+
+/// The graffiti a transaction carries.
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TxGraffiti {
+    Message(DecodedGraffiti),
+    /// The payload is sealed to one recipient and needs their key to read.
+    Encrypted,
+}
+
+impl TxGraffiti {
+    pub fn message(&self) -> Option<&GraffitiMessage> {
+        match self {
+            TxGraffiti::Message(decoded) => Some(&decoded.message),
+            TxGraffiti::Encrypted => None,
+        }
+    }
+}
+
+/// Fetch a transaction and decode its graffiti message, as `tx_graffiti`.
+///
+/// Returns `None` if it carries none.
+pub async fn read_graffiti(txid: &str, rpc_url: Option<&str>) -> Result<Option<TxGraffiti>> {
+    let client = rpc_client(rpc_url)?;
+
+    let tx = client.get_transaction(txid).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    Ok(tx_graffiti(&tx.payload, &tx.output_scripts))
+}
+
+/// Decode a transaction's graffiti; every reader goes through here. A GFX
+/// payload is used if it decodes, and an encrypted one is reported as such.
+/// Otherwise, including when the payload is malformed, the first GFX message
+/// in an `OP_RETURN` output is used.
+fn tx_graffiti(payload_hex: &str, output_scripts: &[String]) -> Option<TxGraffiti> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let decoded = |message: GraffitiMessage, source| TxGraffiti::Message(DecodedGraffiti {
+        suspicious_timestamp: message.has_suspicious_timestamp(now, DEFAULT_MAX_CLOCK_SKEW),
+        message,
+        source,
+    });
+
+    match hex::decode(payload_hex).map(|payload| PayloadEncoder::decode(&payload)) {
+        Ok(Ok(Some(message))) => return Some(decoded(message, GraffitiSource::Payload)),
+        Ok(Err(GraffitiError::Encrypted)) => return Some(TxGraffiti::Encrypted),
+        Ok(Ok(None)) => {}
+        Ok(Err(e)) => log::debug!("Unreadable GFX payload, checking OP_RETURN outputs: {}", e),
+        Err(e) => log::debug!("Payload is not hex, checking OP_RETURN outputs: {}", e),
+    }
+
+    op_return_graffiti(output_scripts).map(|message| decoded(message, GraffitiSource::OpReturn))
+}
+
+/// The first GFX message in an `OP_RETURN` output among `output_scripts`,
+/// given as hex.
+fn op_return_graffiti(output_scripts: &[String]) -> Option<GraffitiMessage> {
+    output_scripts.iter().find_map(|script| {
+        let script = hex::decode(script).ok()?;
+        let data = ScriptData::from_op_return_script(&script)?;
+        PayloadEncoder::decode(&data.data).ok()?
+    })
+}
+
+/// The graffiti found in one of an address's transactions.
+#[derive(Debug, serde::Serialize)]
+pub struct AddressGraffiti {
+    pub txid: String,
+    pub block_time: Option<u64>,
+    #[serde(flatten)]
+    pub graffiti: TxGraffiti,
+}
+
+/// Decode the graffiti in `address`'s transactions, newest first, as
+/// `tx_graffiti`. Transactions without any are skipped.
+pub async fn read_graffiti_for_address(address: &str, rpc_url: Option<&str>) -> Result<Vec<AddressGraffiti>> {
+    let client = rpc_client(rpc_url)?;
+
+    let txs = client.get_transactions_by_address(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    Ok(txs.into_iter().filter_map(address_graffiti).collect())
+}
+
+fn address_graffiti(tx: ChainTx) -> Option<AddressGraffiti> {
+    Some(AddressGraffiti {
+        graffiti: tx_graffiti(&tx.payload, &tx.output_scripts)?,
+        txid: tx.transaction_id,
+        block_time: tx.block_time,
+    })
+}
+
+/// How far `scan_graffiti_history` got, saved after every page.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ScanCheckpoint {
+    pub address: String,
+    /// Transactions processed so far, counting from the newest.
+    pub offset: u64,
+    /// The address's transaction count when the scan started.
+    pub total: u64,
+}
+
+/// Paging, rate limiting and checkpointing for `scan_graffiti_history`.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub page_size: u32,
+    /// Pause between page requests, to stay under public endpoints' rate limits.
+    pub page_delay: Duration,
+    pub checkpoint: Option<PathBuf>,
+    /// Continue from the checkpoint file instead of the newest transaction.
+    pub resume: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            page_size: 100,
+            page_delay: Duration::from_millis(250),
+            checkpoint: None,
+            resume: false,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ScanSummary {
+    pub address: String,
+    /// Offset the scan started at, non-zero when resumed.
+    pub resumed_from: u64,
+    pub transactions_scanned: u64,
+    pub messages_found: u64,
+    /// False when `on_page` stopped the scan before the oldest transaction.
+    pub complete: bool,
+}
+
+/// Scan all of `address`'s history for graffiti a page at a time, newest
+/// first, handing each page's messages to `on_page` as they are decoded.
+/// The scan stops early if `on_page` returns `false`.
+///
+/// With `options.checkpoint`, progress is saved after each page and the file
+/// removed once the scan completes. With `options.resume` the scan continues
+/// from that checkpoint; a missing or corrupt one, or one for another address,
+/// starts from the newest transaction. Transactions received in the meantime
+/// push older ones to higher offsets, so the saved offset is moved on by how
+/// much the address's transaction count has grown.
+pub async fn scan_graffiti_history(
+    address: &str,
+    rpc_url: Option<&str>,
+    options: &ScanOptions,
+    mut on_page: impl FnMut(&[AddressGraffiti]) -> bool,
+) -> Result<ScanSummary> {
+    let client = rpc_client(rpc_url)?;
+
+    let total = client.get_transaction_count(address).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?
+        .total;
+
+    let checkpoint = match (&options.checkpoint, options.resume) {
+        (Some(path), true) => load_scan_checkpoint(path, address),
+        _ => None,
+    };
+    let mut offset = match checkpoint {
+        Some(checkpoint) => checkpoint.offset + total.saturating_sub(checkpoint.total),
+        None => 0,
+    };
+
+    let mut summary = ScanSummary {
+        address: address.to_string(),
+        resumed_from: offset,
+        transactions_scanned: 0,
+        messages_found: 0,
+        complete: false,
+    };
+
+    loop {
+        if summary.transactions_scanned > 0 {
+            tokio::time::sleep(options.page_delay).await;
+        }
+
+        let page = client.get_transactions_page(address, options.page_size, offset).await
+            .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+        let count = page.len() as u64;
+        let messages: Vec<AddressGraffiti> = page.into_iter().filter_map(address_graffiti).collect();
+
+        let keep_going = count == 0 || on_page(&messages);
+
+        offset += count;
+        summary.transactions_scanned += count;
+        summary.messages_found += messages.len() as u64;
+        if count < options.page_size as u64 {
+            summary.complete = true;
+            break;
+        }
+        if let Some(path) = &options.checkpoint {
+            save_scan_checkpoint(path, &ScanCheckpoint { address: address.to_string(), offset, total })?;
+        }
+        if !keep_going {
+            break;
+        }
+    }
+
+    if let (true, Some(path)) = (summary.complete, &options.checkpoint) {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+
+    Ok(summary)
+}
+
+fn load_scan_checkpoint(path: &Path, address: &str) -> Option<ScanCheckpoint> {
+    let json = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<ScanCheckpoint>(&json) {
+        Ok(checkpoint) if checkpoint.address == address => Some(checkpoint),
+        Ok(checkpoint) => {
+            log::warn!("Checkpoint {} is for {}, starting fresh", path.display(), checkpoint.address);
+            None
+        }
+        Err(e) => {
+            log::warn!("Ignoring corrupt checkpoint {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Written to a temporary file first, so an interrupted write can't leave a
+/// truncated checkpoint behind.
+fn save_scan_checkpoint(path: &Path, checkpoint: &ScanCheckpoint) -> Result<()> {
+    let json = serde_json::to_string_pretty(checkpoint)
+        .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Group `address`'s graffiti by content and mimetype, listing the txids
+/// that posted each, newest first.
+///
+/// The message kept for each group is its newest copy; timestamps and
+/// nonces differ between copies and are ignored. Encrypted graffiti can't
+/// be compared and is left out.
+pub async fn scan_graffiti_unique(address: &str, rpc_url: Option<&str>) -> Result<Vec<(GraffitiMessage, Vec<String>)>> {
+    let messages = read_graffiti_for_address(address, rpc_url).await?;
+    Ok(group_identical_graffiti(messages))
+}
+
+fn group_identical_graffiti(messages: Vec<AddressGraffiti>) -> Vec<(GraffitiMessage, Vec<String>)> {
+    let mut groups: Vec<(GraffitiMessage, Vec<String>)> = Vec::new();
+    let mut positions: HashMap<(String, Option<String>), usize> = HashMap::new();
+
+    for found in messages {
+        let TxGraffiti::Message(decoded) = found.graffiti else { continue };
+        let key = (decoded.message.content.clone(), decoded.message.mimetype.clone());
+        match positions.get(&key) {
+            Some(&i) => groups[i].1.push(found.txid),
+            None => {
+                positions.insert(key, groups.len());
+                groups.push((decoded.message, vec![found.txid]));
+            }
+        }
+    }
+
+    groups
+}
+
+/// How deep an accepted transaction is buried, measured two ways.
+///
+/// DAA score counts every block in the DAG, including red (merged but not
+/// selected) blocks, so it grows steadily with the block rate. Blue score only
+/// counts blue blocks along the selected chain and is the usual measure for
+/// reorg safety. Both are `None` until the transaction is accepted.
+#[derive(Debug, serde::Serialize)]
+pub struct ConfirmationInfo {
+    pub txid: String,
+    pub is_accepted: bool,
+    pub daa_depth: Option<u64>,
+    pub blue_score_depth: Option<u64>,
+}
+
+/// Depth of a block below the tip; zero if the tip hasn't caught up to it yet.
+pub fn confirmation_depth(tip_score: u64, block_score: u64) -> u64 {
+    tip_score.saturating_sub(block_score)
+}
+
+pub async fn get_confirmations(txid: &str, rpc_url: Option<&str>) -> Result<ConfirmationInfo> {
+    let client = rpc_client(rpc_url)?;
+
+    let tx = client.get_transaction(txid).await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    let mut info = ConfirmationInfo {
+        txid: tx.transaction_id.clone(),
+        is_accepted: tx.is_accepted,
+        daa_depth: None,
+        blue_score_depth: None,
+    };
+    if !tx.is_accepted {
+        return Ok(info);
+    }
+
+    if let Some(accepting_blue_score) = tx.accepting_block_blue_score {
+        let tip = client.get_virtual_selected_parent_blue_score().await
+            .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+        info.blue_score_depth = Some(confirmation_depth(tip, accepting_blue_score));
+    }
+
+    if let Some(block_hash) = tx.block_hash.first() {
+        let block = client.get_block(block_hash).await
+            .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+        let tip = client.get_virtual_daa_score().await
+            .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+        info.daa_depth = Some(confirmation_depth(tip, block.daa_score));
+    }
+
+    Ok(info)
+}
+
+/// Which of the node's fee-estimate buckets to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeLevel {
+    Priority,
+    Normal,
+    Low,
+}
+
+impl FeeLevel {
+    /// Parse `low`, `normal` or `priority`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "priority" => Some(FeeLevel::Priority),
+            "normal" => Some(FeeLevel::Normal),
+            "low" => Some(FeeLevel::Low),
+            _ => None,
+        }
+    }
+}
+
+/// Fee rate for a send, in sompi per gram of mass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeePriority {
+    Fixed(u64),
+    /// The node's estimate for `level`, or `default` when the node can't
+    /// provide one.
+    Auto { level: FeeLevel, default: u64 },
+}
+
+/// Resolve `fee` to a rate, along with the node's estimated confirmation time
+/// when the rate came from a live estimate.
+async fn resolve_fee_rate(client: &RpcClient, fee: FeePriority) -> (u64, Option<f64>) {
+    let (level, default) = match fee {
+        FeePriority::Fixed(rate) => return (rate, None),
+        FeePriority::Auto { level, default } => (level, default),
+    };
+
+    let estimate = match client.get_fee_estimate().await {
+        Ok(estimate) => estimate,
+        Err(e) => {
+            log::warn!("Fee estimate unavailable ({}), using fallback rate", e);
+            return (default, None);
+        }
+    };
+
+    match fee_bucket(&estimate, level) {
+        Some(bucket) => (bucket_rate(bucket), Some(bucket.estimated_seconds)),
+        None => (default, None),
+    }
+}
+
+fn fee_bucket(estimate: &GetFeeEstimateResponse, level: FeeLevel) -> Option<&GetFeeBucket> {
+    match level {
+        FeeLevel::Priority => Some(&estimate.priority_bucket),
+        FeeLevel::Normal => estimate.normal_buckets.first(),
+        FeeLevel::Low => estimate.low_buckets.first(),
+    }
+}
+
+/// Whole sompi per gram, rounded up so the send isn't under the estimate.
+fn bucket_rate(bucket: &GetFeeBucket) -> u64 {
+    (bucket.feerate.ceil() as u64).max(1)
+}
+
+#[derive(serde::Serialize)]
+pub struct FeeRate {
+    /// Sompi per gram of mass, as used by `FeePriority::Fixed`.
+    pub fee_rate: u64,
+    pub estimated_seconds: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct FeeSuggestion {
+    pub low: Option<FeeRate>,
+    pub normal: Option<FeeRate>,
+    pub priority: FeeRate,
+}
+
+/// The node's current fee rates for each priority level. A level is `None`
+/// when the node has no bucket for it.
+pub async fn suggest_fee_rate(rpc_url: Option<&str>) -> Result<FeeSuggestion> {
+    let client = rpc_client(rpc_url)?;
+    let estimate = client.get_fee_estimate().await
+        .map_err(|e| KaspaGraffitiError::Rpc(e.to_string()))?;
+
+    let rate = |level| fee_bucket(&estimate, level).map(|bucket| FeeRate {
+        fee_rate: bucket_rate(bucket),
+        estimated_seconds: bucket.estimated_seconds,
+    });
+
+    Ok(FeeSuggestion {
+        low: rate(FeeLevel::Low),
+        normal: rate(FeeLevel::Normal),
+        priority: FeeRate {
+            fee_rate: bucket_rate(&estimate.priority_bucket),
+            estimated_seconds: estimate.priority_bucket.estimated_seconds,
+        },
+    })
+}
+
+/// Smallest change output a graffiti transaction will create. Kept above
+/// `DUST_THRESHOLD`: the change is a graffiti's only output, so it can't be
+/// folded into the fee.
+const MIN_CHANGE: u64 = 1000;
+
+/// Fee rate used by `send_graffiti_priority` when the node can't provide an estimate.
+const PRIORITY_FALLBACK_FEE_RATE: u64 = 10;
+
+pub async fn send_graffiti_priority(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+) -> Result<PrioritySendResult> {
+    send_graffiti_priority_on(private_key, message, mimetype, rpc_url, Network::Testnet10, true).await
+}
+
+/// Send a graffiti at the node's priority fee rate for the fastest confirmation.
+///
+/// Falls back to `PRIORITY_FALLBACK_FEE_RATE` if the fee estimate is unavailable,
+/// in which case `estimated_seconds` is `None`.
+pub async fn send_graffiti_priority_on(
+    private_key: &str,
+    message: &str,
+    mimetype: Option<&str>,
+    rpc_url: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<PrioritySendResult> {
+    let client = rpc_client(rpc_url)?;
+
+    let (fee_rate, estimated_seconds) = resolve_fee_rate(&client, FeePriority::Auto {
+        level: FeeLevel::Priority,
+        default: PRIORITY_FALLBACK_FEE_RATE,
+    })
+    .await;
+
+    let sent = send_graffiti_on(private_key, message, mimetype, rpc_url, FeePriority::Fixed(fee_rate), false, false, None, network, check_network).await?;
+
+    Ok(PrioritySendResult {
+        txid: sent.txid,
+        fee: sent.fee,
+        fee_rate,
+        estimated_seconds,
+        change: sent.change,
+        address: sent.address,
+        payload: sent.payload,
+    })
+}
+
+/// Build and sign a graffiti transaction spending `entries` back to `address`.
+///
+/// The fee is `mass * fee_rate` where the mass is computed from the signed
+/// transaction. Returns the signed transaction, the fee and the change amount.
+fn build_graffiti_transaction(
+    entries: &[GetUtxosByAddressesEntry],
+    address: &str,
+    payload: &[u8],
+    private_key: &[u8; 32],
+    fee_rate: u64,
+) -> Result<(KaspaSignedTransaction, u64, u64)> {
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), private_key)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    build_graffiti_transaction_with(entries, address, payload, &[keypair], fee_rate, 0)
+}
+
+/// Like `build_graffiti_transaction`, with each input signed by whichever of
+/// `signers` owns it and the given `lock_time` (0 for none).
+fn build_graffiti_transaction_with<S: TxSigner>(
+    entries: &[GetUtxosByAddressesEntry],
+    address: &str,
+    payload: &[u8],
+    signers: &[S],
+    fee_rate: u64,
+    lock_time: u64,
+) -> Result<(KaspaSignedTransaction, u64, u64)> {
+    if entries.is_empty() {
+        return Err(KaspaGraffitiError::EmptyTransaction);
+    }
+    let total_input: u64 = entries.iter().map(|e| e.utxo_entry.amount).sum();
+
+    // Output values don't affect the serialized size, so the mass estimated
+    // with the full input as change is final.
+    let fee = graffiti_signer(entries, address, total_input, payload)?.estimate_mass() * fee_rate;
+    let change_amount = total_input.saturating_sub(fee);
+
+    if change_amount < MIN_CHANGE {
+        return Err(KaspaGraffitiError::InsufficientBalance(total_input, fee + MIN_CHANGE));
+    }
+
+    let mut signer = graffiti_signer(entries, address, change_amount, payload)?;
+    signer.set_lock_time(lock_time);
+    let signed_tx = signer.sign_with_signers(signers)
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    Ok((signed_tx, fee, change_amount))
+}
+
+fn graffiti_signer(
+    entries: &[GetUtxosByAddressesEntry],
+    address: &str,
+    change_amount: u64,
+    payload: &[u8],
+) -> Result<KaspaTransactionSigner> {
+    let mut signer = outputs_signer(entries, &[(address, change_amount)])?;
+    signer.set_payload(payload);
+    Ok(signer)
+}
+
+/// An unsigned transaction spending all of `entries` to `outputs`, in order.
+fn outputs_signer(
+    entries: &[GetUtxosByAddressesEntry],
+    outputs: &[(&str, u64)],
+) -> Result<KaspaTransactionSigner> {
+    let mut signer = KaspaTransactionSigner::new();
+
+    for utxo in entries {
+        let script_pubkey_hex = &utxo.utxo_entry.script_public_key.script;
+        let script_pubkey: Vec<u8> = hex::decode(script_pubkey_hex)
+            .map_err(|e| KaspaGraffitiError::Encoding(e.to_string()))?;
+
+        signer.add_input(
+            &utxo.outpoint.transaction_id,
+            utxo.outpoint.index,
+            utxo.utxo_entry.amount,
+            &script_pubkey,
+        ).map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+    }
+
+    for (address, amount) in outputs {
+        signer.add_output(address, *amount)
+            .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+    }
+
+    Ok(signer)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WalletInfo {
+    pub private_key: String,
+    pub public_key: String,
+    pub address: String,
+    pub network: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct BalanceInfo {
+    pub balance: u64,
+    pub address: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct ScanEstimate {
+    pub address: String,
+    pub transaction_count: u64,
+    pub approx_bytes: u64,
+    /// The node capped its count, so both figures are lower bounds.
+    pub limit_exceeded: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct SpendableBalanceInfo {
+    pub address: String,
+    pub spendable: u64,
+    pub pending: u64,
+}
+
+#[derive(serde::Serialize)]
+pub struct HDWalletInfo {
+    pub seed: String,
+    pub mnemonic: Option<String>,
+    pub address: String,
+    pub network: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct HDScanResult {
+    /// Used addresses only, external and change interleaved in scan order.
+    pub addresses: Vec<ScannedAddress>,
+    pub total_balance: u64,
+    pub highest_external_index: Option<u32>,
+    pub highest_change_index: Option<u32>,
+}
+
+#[derive(serde::Serialize)]
+pub struct HDBalanceInfo {
+    pub balance: u64,
+    /// Addresses holding funds, with what each holds.
+    pub addresses: Vec<ScannedAddress>,
+}
+
+#[derive(serde::Serialize)]
+pub struct UsedAddresses {
+    /// Receive addresses, then change addresses, each in index order.
+    pub addresses: Vec<DerivedAddressInfo>,
+    /// First receive index after the last used one, 0 if none were used.
+    pub next_index: u32,
+    pub next_change_index: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct ScannedAddress {
+    pub address: String,
+    pub index: u32,
+    pub is_change: bool,
+    pub balance: u64,
+}
+
+/// BIP44 chain of a derived address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    Receive,
+    Change,
+}
+
+impl Chain {
+    pub fn from_is_change(is_change: bool) -> Self {
+        if is_change { Chain::Change } else { Chain::Receive }
+    }
+
+    pub fn is_change(self) -> bool {
+        self == Chain::Change
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct DerivedAddressInfo {
+    pub address: String,
+    pub index: u32,
+    /// Same as `chain`, kept for existing consumers of the JSON.
+    pub is_change: bool,
+    pub chain: Chain,
+    pub private_key: String,
+    pub public_key: String,
+}
+
+/// Split `addresses` into receive and change addresses, keeping their order.
+pub fn partition_by_chain(addresses: Vec<DerivedAddressInfo>) -> (Vec<DerivedAddressInfo>, Vec<DerivedAddressInfo>) {
+    addresses.into_iter().partition(|a| a.chain == Chain::Receive)
+}
+
+#[derive(serde::Serialize)]
+pub struct TransferResult {
+    pub txid: String,
+    pub amount: u64,
+    pub recipient: String,
+    pub fee: u64,
+}
+
+/// Fee rate, in sompi per gram, of the `transfer` and `build_transfer`
+/// wrappers. Pass another through the `_on` variants.
+const TRANSFER_FEE_RATE: u64 = 1;
+
+pub async fn transfer(
+    private_key: &str,
+    recipient: &str,
+    amount: u64,
+    rpc_url: Option<&str>,
+) -> Result<TransferResult> {
+    transfer_on(private_key, recipient, amount, rpc_url, TRANSFER_FEE_RATE, None, Network::Testnet10, true).await
+}
+
+/// Send `amount` sompi to `recipient` at `fee_rate` sompi per gram, with any
+/// change going to `change_address`, else back to the sender.
+pub async fn transfer_on(
+    private_key: &str,
+    recipient: &str,
+    amount: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    change_address: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<TransferResult> {
+    let built = build_transfer_on(private_key, recipient, amount, rpc_url, fee_rate, change_address, network, check_network).await?;
+
+    let json_tx = serde_json::to_value(built.transaction.json())
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    let submit_response = rpc_client(rpc_url)?.submit_transaction_json(&json_tx, false).await
+        .map_err(submit_error)?;
+
+    Ok(TransferResult {
+        txid: submit_response.transaction_id,
+        amount,
+        recipient: recipient.to_string(),
+        fee: built.fee,
+    })
+}
+
+pub async fn build_transfer(
+    private_key: &str,
+    recipient: &str,
+    amount: u64,
+    rpc_url: Option<&str>,
+) -> Result<BuiltSend> {
+    build_transfer_on(private_key, recipient, amount, rpc_url, TRANSFER_FEE_RATE, None, Network::Testnet10, true).await
+}
+
+/// Select inputs and sign the payment `transfer_on` would send, without
+/// submitting it.
+pub async fn build_transfer_on(
+    private_key: &str,
+    recipient: &str,
+    amount: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    change_address: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<BuiltSend> {
+    validate_outputs(&[(recipient.to_string(), amount)], network)?;
+    if let Some(change_address) = change_address {
+        require_address(change_address, network)?;
+    }
+
+    let private_bytes = hex::decode(private_key)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    if private_bytes.len() != 32 {
+        return Err(KaspaGraffitiError::InvalidPrivateKey);
+    }
+    let private_key_array: [u8; 32] = private_bytes.try_into()
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), &private_key_array)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let (xonly_pubkey, _) = keypair.x_only_public_key();
+    let xonly_bytes: [u8; 32] = xonly_pubkey.serialize();
+
+    let prefix = network.to_prefix();
+    let sender_address = kaspa_addresses::Address::new(prefix, kaspa_addresses::Version::PubKey, &xonly_bytes);
+    let sender_address_str = sender_address.to_string();
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let (transaction, fee, change) = select_and_build(
+        &client,
+        vec![sender_address_str.clone()],
+        Spend::All,
+        fee_rate,
+        |spendable| {
+            let inputs = transfer_inputs(spendable, amount, fee_rate)?;
+            // Signed against the real scripts, so a P2SH recipient pays for
+            // its extra byte
+            build_multi_transfer(
+                &inputs,
+                &[(recipient.to_string(), amount)],
+                change_address.unwrap_or(&sender_address_str),
+                &private_key_array,
+                fee_rate,
+            )
+        },
+    ).await?;
+
+    Ok(BuiltSend {
+        transaction,
+        fee,
+        change,
+        address: sender_address_str,
+        payload: Vec::new(),
+    })
+}
+
+/// What sending `recipient` sompi from a UTXO snapshot would cost, computed
+/// without signing or touching the network so a UI can refresh it on every
+/// keystroke.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TransferBreakdown {
+    pub selected_inputs: Vec<GetUtxosByAddressesEntry>,
+    pub total_in: u64,
+    pub recipient: u64,
+    pub fee: u64,
+    /// Zero when the remainder is below the dust threshold and goes to the fee.
+    pub change: u64,
+    /// False when the snapshot can't cover the amount; `selected_inputs` is
+    /// then every UTXO and `fee` what spending them all would cost.
+    pub sufficient: bool,
+}
+
+/// Select inputs largest-first for a payment of `recipient_amount` to one
+/// P2PK address at `fee_rate` sompi per gram, with change back to a P2PK
+/// address. Every UTXO in `utxos` is taken as spendable, so filter immature
+/// coinbase outputs out with `is_mature` first.
+pub fn compute_transfer_breakdown(
+    utxos: &[GetUtxosByAddressesEntry],
+    recipient_amount: u64,
+    fee_rate: u64,
+) -> TransferBreakdown {
+    let fee = |inputs: usize, outputs: usize| p2pk_transfer_fee(inputs, outputs, fee_rate);
+    let insufficient = || TransferBreakdown {
+        selected_inputs: utxos.to_vec(),
+        total_in: utxos.iter().map(|e| e.utxo_entry.amount).sum(),
+        recipient: recipient_amount,
+        fee: fee(utxos.len(), 1),
+        change: 0,
+        sufficient: false,
+    };
+
+    let Some(target) = recipient_amount.checked_add(fee(0, 1)) else {
+        return insufficient();
+    };
+    let selected = match select_spendable(utxos, target, fee_rate, SelectionStrategy::LargestFirst) {
+        Ok(selected) => selected,
+        Err(CoinSelectionError::InsufficientFunds { .. }) => return insufficient(),
+    };
+
+    let total_in: u64 = selected.iter().map(|e| e.utxo_entry.amount).sum();
+    let fee_with_change = fee(selected.len(), 2);
+    let (change, fee) = match recipient_amount.checked_add(fee_with_change).and_then(|spent| total_in.checked_sub(spent)) {
+        Some(change) => fold_dust_change(change, fee_with_change, P2PK_SCRIPT_LEN),
+        // Enough without the change output, so the remainder is the fee
+        None => (0, total_in - recipient_amount),
+    };
+
+    TransferBreakdown {
+        selected_inputs: selected,
+        total_in,
+        recipient: recipient_amount,
+        fee,
+        change,
+        sufficient: true,
+    }
+}
+
+/// The inputs `compute_transfer_breakdown` picks from `spendable`, so a
+/// transfer spends exactly what its preview showed.
+fn transfer_inputs(spendable: &[GetUtxosByAddressesEntry], amount: u64, fee_rate: u64) -> Result<Vec<GetUtxosByAddressesEntry>> {
+    let breakdown = compute_transfer_breakdown(spendable, amount, fee_rate);
+    if !breakdown.sufficient {
+        return Err(KaspaGraffitiError::InsufficientBalance(breakdown.total_in, amount.saturating_add(breakdown.fee)));
+    }
+    Ok(breakdown.selected_inputs)
+}
+
+/// Fee at `fee_rate` for spending `inputs` P2PK outputs into `outputs` P2PK
+/// outputs, measured by the signer on a placeholder transaction.
+fn p2pk_transfer_fee(inputs: usize, outputs: usize, fee_rate: u64) -> u64 {
+    let placeholder = kaspa_addresses::Address::new(
+        kaspa_addresses::Prefix::Testnet,
+        kaspa_addresses::Version::PubKey,
+        &[0; 32],
+    ).to_string();
+
+    let mut draft = KaspaTransactionSigner::new();
+    for index in 0..inputs {
+        draft.add_input(&"00".repeat(32), index as u32, 0, &[])
+            .expect("placeholder outpoint is valid");
+    }
+    for _ in 0..outputs {
+        draft.add_output(&placeholder, 0).expect("placeholder address is valid");
+    }
+    draft.estimate_mass().saturating_mul(fee_rate)
+}
+
+/// Length of the script public key paying to `address`.
+fn output_script_len(address: &str) -> Result<usize> {
+    let address = kaspa_addresses::Address::try_from(address)
+        .map_err(|e| KaspaGraffitiError::InvalidAddress(e.to_string()))?;
+    Ok(kaspa_txscript::pay_to_address_script(&address).script().len())
+}
+
+fn check_output_dust(recipient: &str, amount: u64) -> Result<()> {
+    let script_len = output_script_len(recipient)?;
+    if is_dust(amount, script_len) {
+        return Err(KaspaGraffitiError::Transaction(format!(
+            "Output of {} sompi to {} is below the dust threshold of {} sompi",
+            amount, recipient, dust_threshold(script_len)
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the change and fee, moving change that would be dust at a
+/// `change_script_len`-byte script into the fee since the node won't relay
+/// a dust output.
+fn fold_dust_change(change: u64, fee: u64, change_script_len: usize) -> (u64, u64) {
+    if is_dust(change, change_script_len) {
+        (0, fee + change)
+    } else {
+        (change, fee)
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct SweepResult {
+    pub txid: String,
+    /// Amount received by `destination`: every input minus the fee.
+    pub amount: u64,
+    pub fee: u64,
+    pub destination: String,
+    pub inputs: usize,
+}
+
+pub async fn sweep(
+    private_key: &str,
+    destination: &str,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<SweepResult> {
+    sweep_on(private_key, destination, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Send every mature UTXO of the key's address to `destination` in one
+/// transaction with no change output.
+///
+/// Fails with `InsufficientBalance` if what's left after the fee would be
+/// dust.
+pub async fn sweep_on(
+    private_key: &str,
+    destination: &str,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<SweepResult> {
+    require_address(destination, network)?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let (signed_tx, amount, fee, inputs) = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::All,
+        fee_rate,
+        |entries| {
+            let (signed_tx, amount, fee) = build_sweep_transaction(entries, destination, &private_key_array, fee_rate)?;
+            Ok((signed_tx, amount, fee, entries.len()))
+        },
+    ).await?;
+
+    let json_tx = serde_json::to_value(signed_tx.json())
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    let submit_response = client.submit_transaction_json(&json_tx, false).await
+        .map_err(submit_error)?;
+
+    Ok(SweepResult {
+        txid: submit_response.transaction_id,
+        amount,
+        fee,
+        destination: destination.to_string(),
+        inputs,
+    })
+}
+
+/// Sign a transaction spending all of `entries` to a single `destination`
+/// output. Returns the signed transaction, the amount sent and the fee.
+fn build_sweep_transaction(
+    entries: &[GetUtxosByAddressesEntry],
+    destination: &str,
+    private_key: &[u8; 32],
+    fee_rate: u64,
+) -> Result<(KaspaSignedTransaction, u64, u64)> {
+    let total_input: u64 = entries.iter().map(|e| e.utxo_entry.amount).sum();
+
+    let fee = graffiti_signer(entries, destination, total_input, &[])?.estimate_mass() * fee_rate;
+    let amount = total_input.saturating_sub(fee);
+    let script_len = output_script_len(destination)?;
+    if is_dust(amount, script_len) {
+        return Err(KaspaGraffitiError::InsufficientBalance(total_input, fee + dust_threshold(script_len)));
+    }
+
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), private_key)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let signed_tx = graffiti_signer(entries, destination, amount, &[])?
+        .sign_with_signers(&[keypair])
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    Ok((signed_tx, amount, fee))
+}
+
+fn require_address(address: &str, network: Network) -> Result<()> {
+    match crate::wallet::validate_address(address, network) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(KaspaGraffitiError::InvalidAddress(format!("{} is not a {} address", address, network.name()))),
+        Err(e) => Err(KaspaGraffitiError::InvalidAddress(e.to_string())),
+    }
+}
+
+/// Checks every recipient before anything is signed, so a typo'd address
+/// or an unpayable amount fails with a clear error up front.
+fn validate_outputs(outputs: &[(String, u64)], network: Network) -> Result<()> {
+    for (recipient, amount) in outputs {
+        require_address(recipient, network)?;
+        if *amount == 0 {
+            return Err(KaspaGraffitiError::Transaction(format!("Amount to {} must be greater than zero", recipient)));
+        }
+        check_output_dust(recipient, *amount)?;
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct TransferOutput {
+    pub recipient: String,
+    pub amount: u64,
+    /// Output index in the transaction, for building the outpoint.
+    pub index: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct MultiTransferResult {
+    pub txid: String,
+    pub outputs: Vec<TransferOutput>,
+    pub fee: u64,
+    /// Zero when the leftover would have been dust at the change address and
+    /// went to the fee.
+    pub change: u64,
+    pub address: String,
+}
+
+pub async fn transfer_multi(
+    private_key: &str,
+    outputs: Vec<(String, u64)>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+) -> Result<MultiTransferResult> {
+    transfer_multi_on(private_key, outputs, rpc_url, fee_rate, Network::Testnet10, true).await
+}
+
+/// Pay every `(address, amount)` in `outputs` from one transaction, with the
+/// change going back to the sender after the recipients.
+pub async fn transfer_multi_on(
+    private_key: &str,
+    outputs: Vec<(String, u64)>,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    network: Network,
+    check_network: bool,
+) -> Result<MultiTransferResult> {
+    if outputs.is_empty() {
+        return Err(KaspaGraffitiError::Transaction("No recipients given".to_string()));
+    }
+    validate_outputs(&outputs, network)?;
+
+    let (private_key_array, address) = graffiti_sender(private_key, network)?;
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let (signed_tx, fee, change) = select_and_build(
+        &client,
+        vec![address.clone()],
+        Spend::Covering(transfer_target(&outputs, fee_rate)?),
+        fee_rate,
+        |selected| build_multi_transfer(selected, &outputs, &address, &private_key_array, fee_rate),
+    ).await?;
+
+    let json_tx = serde_json::to_value(signed_tx.json())
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    let submit_response = client.submit_transaction_json(&json_tx, false).await
+        .map_err(submit_error)?;
+
+    Ok(MultiTransferResult {
+        txid: submit_response.transaction_id,
+        outputs: outputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (recipient, amount))| TransferOutput { recipient, amount, index: index as u32 })
+            .collect(),
+        fee,
+        change,
+        address,
+    })
+}
+
+/// What a payment to `outputs` needs its inputs to cover: the recipients and
+/// the fee for everything but the inputs. The change output is left out, as
+/// it's dropped when there isn't enough for it.
+fn transfer_target(outputs: &[(String, u64)], fee_rate: u64) -> Result<u64> {
+    let sent: u64 = outputs.iter().map(|(_, amount)| amount).sum();
+    let base_outputs: Vec<(&str, u64)> = outputs.iter().map(|(a, v)| (a.as_str(), *v)).collect();
+    Ok(sent + outputs_signer(&[], &base_outputs)?.estimate_mass() * fee_rate)
+}
+
+/// Sign a transaction spending all of `entries` to `outputs` plus a change
+/// output to `change_address`. Returns the signed transaction, the fee and
+/// the change, which is 0 when it would have been dust.
+fn build_multi_transfer(
+    entries: &[GetUtxosByAddressesEntry],
+    outputs: &[(String, u64)],
+    change_address: &str,
+    private_key: &[u8; 32],
+    fee_rate: u64,
+) -> Result<(KaspaSignedTransaction, u64, u64)> {
+    let (mut signer, fee, change) = plan_multi_transfer(entries, outputs, change_address, fee_rate)?;
+
+    let keypair = secp256k1::KeyPair::from_seckey_slice(secp(), private_key)
+        .map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let signed_tx = signer
+        .sign_with_signers(&[keypair])
+        .map_err(|e| KaspaGraffitiError::Transaction(e.to_string()))?;
+
+    Ok((signed_tx, fee, change))
+}
+
+/// The unsigned transaction `build_multi_transfer` signs, with its fee and
+/// change.
+fn plan_multi_transfer(
+    entries: &[GetUtxosByAddressesEntry],
+    outputs: &[(String, u64)],
+    change_address: &str,
+    fee_rate: u64,
+) -> Result<(KaspaTransactionSigner, u64, u64)> {
+    let total_input: u64 = entries.iter().map(|e| e.utxo_entry.amount).sum();
+    let sent: u64 = outputs.iter().map(|(_, amount)| amount).sum();
+    let mut tx_outputs: Vec<(&str, u64)> = outputs.iter().map(|(a, v)| (a.as_str(), *v)).collect();
+
+    let fee_without_change = outputs_signer(entries, &tx_outputs)?.estimate_mass() * fee_rate;
+    if total_input < sent + fee_without_change {
+        return Err(KaspaGraffitiError::InsufficientBalance(total_input, sent + fee_without_change));
+    }
+
+    tx_outputs.push((change_address, total_input));
+    let fee_with_change = outputs_signer(entries, &tx_outputs)?.estimate_mass() * fee_rate;
+    let (change, fee) = match total_input.checked_sub(sent + fee_with_change) {
+        Some(change) => fold_dust_change(change, fee_with_change, output_script_len(change_address)?),
+        // Enough without the change output, so the remainder is the fee
+        None => (0, total_input - sent),
+    };
+    tx_outputs.pop();
+    if change > 0 {
+        tx_outputs.push((change_address, change));
+    }
+
+    Ok((outputs_signer(entries, &tx_outputs)?, fee, change))
+}
+
+/// A payment built on a machine without the key, for `sign_offline`.
+#[derive(serde::Serialize)]
+pub struct UnsignedTransfer {
+    pub fee: u64,
+    pub change: u64,
+    pub transaction: UnsignedTx,
+}
+
+/// Build the payment `transfer_on` would make from `sender`, unsigned, so
+/// the key can stay on an offline machine. Only the sender's address is
+/// needed; `sender` must be a P2PK address for the offline key to sign it.
+pub async fn build_unsigned_transfer_on(
+    sender: &str,
+    recipient: &str,
+    amount: u64,
+    rpc_url: Option<&str>,
+    fee_rate: u64,
+    change_address: Option<&str>,
+    network: Network,
+    check_network: bool,
+) -> Result<UnsignedTransfer> {
+    require_address(sender, network)?;
+    validate_outputs(&[(recipient.to_string(), amount)], network)?;
+    if let Some(change_address) = change_address {
+        require_address(change_address, network)?;
+    }
+
+    let client = rpc_client(rpc_url)?;
+    require_node_network(&client, NodeNetwork::Is(network), check_network).await?;
+
+    let (signer, fee, change) = select_and_build(
+        &client,
+        vec![sender.to_string()],
+        Spend::All,
+        fee_rate,
+        |spendable| {
+            let inputs = transfer_inputs(spendable, amount, fee_rate)?;
+            plan_multi_transfer(&inputs, &[(recipient.to_string(), amount)], change_address.unwrap_or(sender), fee_rate)
+        },
+    ).await?;
+
+    Ok(UnsignedTransfer { fee, change, transaction: signer.build() })
+}
+
+/// A transaction signed by `sign_offline`, ready to submit.
+#[derive(serde::Serialize)]
+pub struct OfflineSignedTx {
+    pub txid: String,
+    pub fee: u64,
+    pub tx_hex: String,
+    pub transaction: JsonTransaction,
+}
+
+/// Sign an `UnsignedTx` JSON file without touching the network. Every
+/// input must be spendable by `private_key`.
+pub fn sign_offline(private_key: &str, unsigned_json: &str) -> Result<OfflineSignedTx> {
+    let keypair = KeyPair::from_hex(private_key).map_err(|_| KaspaGraffitiError::InvalidPrivateKey)?;
+    let unsigned: UnsignedTx = serde_json::from_str(unsigned_json)
+        .map_err(|e| KaspaGraffitiError::Transaction(format!("Invalid unsigned transaction: {}", e)))?;
+
+    let total_in: u64 = unsigned.inputs.iter().map(|input| input.amount).sum();
+    let total_out: u64 = unsigned.outputs.iter().map(|output| output.amount).sum();
+    let fee = total_in.checked_sub(total_out).ok_or_else(|| {
+        KaspaGraffitiError::Transaction(format!("Outputs ({} sompi) exceed inputs ({} sompi)", total_out, total_in))
+    })?;
+
+    let signed = KaspaTransactionSigner::from_unsigned(&unsigned)
+        .and_then(|mut signer| signer.sign_with(&keypair))
+        .map_err(KaspaGraffitiError::Transaction)?;
+
+    Ok(OfflineSignedTx {
+        txid: signed.id().to_string(),
+        fee,
+        tx_hex: signed.hex().to_string(),
+        transaction: signed.json().clone(),
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct SelfTestStep {
+    pub name: &'static str,
+    pub passed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SelfTestReport {
+    pub steps: Vec<SelfTestStep>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.passed)
+    }
+}
+
+const SELFTEST_STEPS: [&str; 5] = ["keygen", "hd-derive", "sign", "decode", "verify"];
+
+/// Exercise key generation, HD derivation, signing and payload decoding
+/// without touching the network, to check the crypto works on this build.
+///
+/// Each step builds on the previous one, so steps after a failure are
+/// reported as failed without running.
+pub fn selftest() -> SelfTestReport {
+    let mut steps = Vec::new();
+    run_selftest(&mut steps);
+
+    for name in SELFTEST_STEPS.iter().skip(steps.len()) {
+        steps.push(SelfTestStep {
+            name,
+            passed: false,
+            error: Some("skipped after an earlier failure".to_string()),
+        });
+    }
+
+    SelfTestReport { steps }
+}
+
+fn run_selftest(steps: &mut Vec<SelfTestStep>) -> Option<()> {
+    let message = "kaspa-graffiti selftest";
+
+    let keypair = KeyPair::new();
+    let keygen = KeyPair::from_hex(&keypair.to_hex())
+        .map_err(|e| e.to_string())
+        .and_then(|parsed| match parsed.public_key_hex() == keypair.public_key_hex() {
+            true => Ok(()),
+            false => Err("key did not round-trip through hex".to_string()),
+        });
+    selftest_step(steps, "keygen", keygen)?;
+
+    // BIP32 test vector 1, m/0'
+    let seed: Vec<u8> = (0u8..16).collect();
+    let derived = crate::wallet::ExtendedKey::from_seed(&seed)
+        .and_then(|master| master.derive_path("m/0'"))
+        .map_err(|e| e.to_string())
+        .and_then(|child| match child.keypair().to_hex().as_str() {
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea" => Ok(child.keypair().clone()),
+            other => Err(format!("derived unexpected key {}", other)),
+        });
+    let keypair = selftest_step(steps, "hd-derive", derived)?;
+
+    // A synthetic P2PK UTXO of the derived key: OP_DATA_32 <x-only pubkey> OP_CHECKSIG
+    let address = crate::wallet::generate_address(keypair.public_key(), Network::Testnet10);
+    let mut script = vec![0x20];
+    script.extend_from_slice(&keypair.public_key_bytes()[1..]);
+    script.push(0xac);
+    let entries = [GetUtxosByAddressesEntry {
+        address: address.clone(),
+        outpoint: GetOutPoint { transaction_id: "00".repeat(32), index: 0 },
+        utxo_entry: GetUtxoEntry {
+            amount: 100_000_000,
+            script_public_key: GetScriptPublicKey { version: 0, script: hex::encode(script) },
+            block_daa_score: 0,
+            is_coinbase: false,
+        },
+        is_spent: false,
+    }];
+
+    let signed = graffiti_payload(message, None, false)
+        .and_then(|payload| {
+            let (signed_tx, _, change) = build_graffiti_transaction(&entries, &address, &payload, &keypair.to_bytes(), 1)?;
+            Ok((payload, signed_tx, change))
+        })
+        .map_err(|e| e.to_string());
+    let (payload, signed_tx, change) = selftest_step(steps, "sign", signed)?;
+
+    let decoded = hex::decode(&signed_tx.json().payload)
+        .map_err(|e| e.to_string())
+        .and_then(|payload| PayloadEncoder::decode(&payload).map_err(|e| e.to_string()))
+        .and_then(|decoded| match decoded {
+            Some(decoded) if decoded.content == message => Ok(()),
+            Some(decoded) => Err(format!("decoded {:?}", decoded.content)),
+            None => Err("payload is not a graffiti message".to_string()),
+        });
+    selftest_step(steps, "decode", decoded)?;
+
+    let verified = graffiti_signer(&entries, &address, change, &payload)
+        .map_err(|e| e.to_string())
+        .and_then(|signer| signer.verify(&signed_tx));
+    selftest_step(steps, "verify", verified)
+}
+
+/// Record the outcome of a selftest step, returning its value if it passed.
+fn selftest_step<T>(steps: &mut Vec<SelfTestStep>, name: &'static str, result: std::result::Result<T, String>) -> Option<T> {
+    let (passed, error, value) = match result {
+        Ok(value) => (true, None, Some(value)),
+        Err(e) => (false, Some(e), None),
+    };
+    steps.push(SelfTestStep { name, passed, error });
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::mock::{MockResponse, MockRpc};
+    use crate::wallet::DUST_THRESHOLD;
+    use crate::rpc::client::{GetBalanceByAddressResponse, GetUtxosByAddressesResponse, SubmitTransactionResponse};
+
+    const TEST_KEY: &str = "1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac";
+    const TESTNET_10_INFO: &str = r#"{"networkName": "kaspa-testnet-10", "virtualDaaScore": "5000"}"#;
+
+    fn test_key() -> [u8; 32] {
+        hex::decode(TEST_KEY).unwrap().try_into().unwrap()
+    }
+
+    fn test_address() -> String {
+        let keypair = KeyPair::from_hex(TEST_KEY).unwrap();
+        crate::wallet::generate_address(keypair.public_key(), Network::Testnet10)
+    }
+
+    fn test_entry(txid_byte: u8, amount: u64) -> GetUtxosByAddressesEntry {
+        let keypair = KeyPair::from_hex(TEST_KEY).unwrap();
+        // P2PK script: OP_DATA_32 <x-only pubkey> OP_CHECKSIG
+        let mut script = vec![0x20];
+        script.extend_from_slice(&keypair.public_key_bytes()[1..]);
+        script.push(0xac);
+
+        GetUtxosByAddressesEntry {
+            address: test_address(),
+            outpoint: GetOutPoint {
+                transaction_id: hex::encode([txid_byte; 32]),
+                index: 0,
+            },
+            utxo_entry: GetUtxoEntry {
+                amount,
+                script_public_key: GetScriptPublicKey {
+                    version: 0,
+                    script: hex::encode(script),
+                },
+                block_daa_score: 0,
+                is_coinbase: false,
+            },
+            is_spent: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_change_from_mempool() {
+        let address = test_address();
+        let other = crate::wallet::generate_address(KeyPair::new().public_key(), Network::Testnet10);
+
+        let mock = MockRpc::start().await;
+        let mempool = serde_json::json!([
+            {
+                "transactionId": hex::encode([0x01; 32]),
+                "outputs": [
+                    { "amount": "250000000", "scriptPublicKeyAddress": other },
+                    { "amount": "749998000", "scriptPublicKeyAddress": address }
+                ]
+            },
+            {
+                "transactionId": hex::encode([0x02; 32]),
+                "outputs": [
+                    { "amount": 5000, "scriptPublicKeyAddress": other }
+                ]
+            }
+        ]);
+        mock.on("GET", &format!("/addresses/{}/mempool", address), 200, &mempool.to_string());
+        mock.on("GET", &format!("/addresses/{}/utxos", address), 200, "[]");
+
+        let pending = pending_change(&address, Some(mock.url())).await.unwrap();
+        assert_eq!(pending, 749_998_000);
+
+        let balance = get_spendable_balance(&address, Some(mock.url())).await.unwrap();
+        assert_eq!(balance.spendable, 0);
+        assert_eq!(balance.pending, 749_998_000);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_scan_size() {
+        let address = test_address();
+        let mock = MockRpc::start().await;
+        mock.on("GET", &format!("/addresses/{}/transactions-count", address), 200, r#"{"total": 240, "limit_exceeded": false}"#);
+
+        let estimate = estimate_scan_size(&address, Some(mock.url())).await.unwrap();
+        assert_eq!(estimate.transaction_count, 240);
+        assert_eq!(estimate.approx_bytes, 240 * APPROX_TX_JSON_BYTES);
+        assert!(!estimate.limit_exceeded);
+        // Only the count was requested, no transaction bodies
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    /// `entry` as the REST server returns it from `/addresses/utxos`.
+    fn utxo_json(entry: &GetUtxosByAddressesEntry) -> serde_json::Value {
+        serde_json::json!({
+            "address": entry.address,
+            "outpoint": {
+                "transactionId": entry.outpoint.transaction_id,
+                "index": entry.outpoint.index
+            },
+            "utxoEntry": {
+                "amount": entry.utxo_entry.amount.to_string(),
+                "scriptPublicKey": { "scriptPublicKey": entry.utxo_entry.script_public_key.script },
+                "blockDaaScore": entry.utxo_entry.block_daa_score.to_string(),
+                "isCoinbase": entry.utxo_entry.is_coinbase
+            },
+            "isSpent": entry.is_spent
+        })
+    }
+
+    fn mock_utxo_entries(entries: &[GetUtxosByAddressesEntry]) -> String {
+        serde_json::Value::from(entries.iter().map(utxo_json).collect::<Vec<_>>()).to_string()
+    }
+
+    /// An `/addresses/active` response listing `addresses` as used.
+    fn mock_activity(addresses: &[&str]) -> String {
+        let entries: Vec<_> = addresses.iter().map(|a| serde_json::json!({ "address": a, "active": true })).collect();
+        serde_json::Value::from(entries).to_string()
+    }
+
+    /// A testnet-10 node at DAA score 5000 holding `utxos`; submit routes are
+    /// left to the test.
+    async fn funded_mock(utxos: &[GetUtxosByAddressesEntry]) -> MockRpc {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, TESTNET_10_INFO);
+        mock.on("GET", "/info/blockdag", 200, r#"{"virtualDaaScore": "5000"}"#);
+        mock.on("POST", "/addresses/utxos", 200, &mock_utxo_entries(utxos));
+        mock
+    }
+
+    fn entry_at(address: &str, txid_byte: u8, amount: u64, spent: bool) -> GetUtxosByAddressesEntry {
+        let mut entry = test_entry(txid_byte, amount);
+        entry.address = address.to_string();
+        entry.is_spent = spent;
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_priority_send_uses_priority_bucket() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        let estimate = serde_json::json!({
+            "priorityBucket": { "feerate": 7.0, "estimatedSeconds": 0.5 },
+            "normalBuckets": [{ "feerate": 2.0, "estimatedSeconds": 10.0 }],
+            "lowBuckets": [{ "feerate": 1.0, "estimatedSeconds": 60.0 }]
+        });
+        mock.on("GET", "/info/fee-estimate", 200, &estimate.to_string());
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        let result = send_graffiti_priority(TEST_KEY, "urgent", None, Some(mock.url())).await.unwrap();
+
+        let mass = graffiti_signer(&[test_entry(0x11, 100_000_000)], &test_address(), 0, &result.payload)
+            .unwrap()
+            .estimate_mass();
+        assert_eq!(result.fee_rate, 7);
+        assert_eq!(result.fee, mass * 7);
+        assert_eq!(result.estimated_seconds, Some(0.5));
+        assert_eq!(result.change, 100_000_000 - mass * 7);
+    }
+
+    #[tokio::test]
+    async fn test_send_result_payload_matches_submitted_tx() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        let result = send_graffiti(TEST_KEY, "on the record", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await.unwrap();
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let body: serde_json::Value = serde_json::from_str(&submitted[0].body).unwrap();
+        assert_eq!(body["transaction"]["payload"], result.payload_hex());
+
+        let decoded = PayloadEncoder::decode(&result.payload).unwrap().unwrap();
+        assert_eq!(decoded.content, "on the record");
+        assert_eq!(decoded.mimetype.as_deref(), Some("text/plain"));
+
+        let raw = send_graffiti(TEST_KEY, "on the record", None, Some(mock.url()), FeePriority::Fixed(1), true, false).await.unwrap();
+        assert_eq!(raw.payload, b"on the record");
+        assert!(PayloadEncoder::decode(&raw.payload).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_round_trip() {
+        let utxo = test_entry(0x11, 50_000_000);
+        let fee_rate = 3;
+
+        let mock = funded_mock(&[utxo.clone()]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "cd".repeat(32)));
+
+        let result = send_graffiti(TEST_KEY, "round trip", Some("text/markdown"), Some(mock.url()), FeePriority::Fixed(fee_rate), false, false)
+            .await
+            .unwrap();
+        assert_eq!(result.txid, "cd".repeat(32));
+        assert_eq!(result.address, test_address());
+
+        // The UTXO lookup asked for the sender's own address
+        let lookup = mock.requests_to("POST", "/addresses/utxos");
+        let lookup: serde_json::Value = serde_json::from_str(&lookup[0].body).unwrap();
+        assert_eq!(lookup["addresses"], serde_json::json!([test_address()]));
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        assert_eq!(submitted.len(), 1);
+        let tx = &serde_json::from_str::<serde_json::Value>(&submitted[0].body).unwrap()["transaction"];
+
+        // Payload decodes back to the original message
+        let payload = hex::decode(tx["payload"].as_str().unwrap()).unwrap();
+        let decoded = PayloadEncoder::decode(&payload).unwrap().unwrap();
+        assert_eq!(decoded.content, "round trip");
+        assert_eq!(decoded.mimetype.as_deref(), Some("text/markdown"));
+
+        // Single signed input spending the mock UTXO
+        let inputs = tx["inputs"].as_array().unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0]["previousOutpoint"]["transactionId"], utxo.outpoint.transaction_id);
+        assert_eq!(inputs[0]["previousOutpoint"]["index"], utxo.outpoint.index);
+        assert_eq!(inputs[0]["signatureScript"].as_str().unwrap().len(), 66 * 2);
+
+        // Change returns to the sender; fee is mass * fee_rate and nothing else leaks
+        let outputs = tx["outputs"].as_array().unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0]["scriptPublicKey"]["scriptPublicKey"], utxo.utxo_entry.script_public_key.script);
+        assert_eq!(outputs[0]["amount"], result.change);
+        assert_eq!(result.fee, tx["mass"].as_u64().unwrap() * fee_rate);
+        assert_eq!(result.change + result.fee, utxo.utxo_entry.amount);
+    }
+
+    #[tokio::test]
+    async fn test_change_goes_to_change_address() {
+        let utxo = test_entry(0x11, 50_000_000);
+        let change_key = KeyPair::from_hex(&"11".repeat(32)).unwrap();
+        let change_address = crate::wallet::generate_address(change_key.public_key(), Network::Testnet10);
+        let change_script = hex::encode(
+            kaspa_txscript::pay_to_address_script(&change_address.as_str().try_into().unwrap()).script(),
+        );
+
+        let mock = funded_mock(&[utxo.clone()]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "cd".repeat(32)));
+
+        let result = send_graffiti_on(TEST_KEY, "elsewhere", None, Some(mock.url()), FeePriority::Fixed(1), false, false, Some(&change_address), Network::Testnet10, true)
+            .await
+            .unwrap();
+        assert_eq!(result.address, test_address());
+        transfer_on(TEST_KEY, &test_address(), 10_000_000, Some(mock.url()), 1, Some(&change_address), Network::Testnet10, true)
+            .await
+            .unwrap();
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let graffiti = &serde_json::from_str::<serde_json::Value>(&submitted[0].body).unwrap()["transaction"];
+        let outputs = graffiti["outputs"].as_array().unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0]["scriptPublicKey"]["scriptPublicKey"], change_script);
+        assert_eq!(outputs[0]["amount"], result.change);
+
+        let payment = &serde_json::from_str::<serde_json::Value>(&submitted[1].body).unwrap()["transaction"];
+        let outputs = payment["outputs"].as_array().unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0]["scriptPublicKey"]["scriptPublicKey"], utxo.utxo_entry.script_public_key.script);
+        assert_eq!(outputs[0]["amount"], 10_000_000);
+        assert_eq!(outputs[1]["scriptPublicKey"]["scriptPublicKey"], change_script);
+
+        // Checked like a recipient, before the node is contacted
+        let mainnet = crate::wallet::generate_address(change_key.public_key(), Network::Mainnet);
+        let Err(err) = transfer_on(TEST_KEY, &test_address(), 10_000_000, Some("http://127.0.0.1:1"), 1, Some(&mainnet), Network::Testnet10, true).await else {
+            panic!("sent change to a mainnet address");
+        };
+        assert!(matches!(err, KaspaGraffitiError::InvalidAddress(_)), "{}", err);
+        let Err(err) = send_graffiti_on(TEST_KEY, "gm", None, Some("http://127.0.0.1:1"), FeePriority::Fixed(1), false, false, Some("kaspatest:nope"), Network::Testnet10, true).await else {
+            panic!("sent change to an invalid address");
+        };
+        assert!(matches!(err, KaspaGraffitiError::InvalidAddress(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_build_without_submitting() {
+        let utxo = test_entry(0x11, 50_000_000);
+
+        // No submit route: any submission would fail the build
+        let mock = funded_mock(&[utxo.clone()]).await;
+
+        let built = build_graffiti(TEST_KEY, "not yet", None, Some(mock.url()), FeePriority::Fixed(1), false).await.unwrap();
+        assert_eq!(built.address, test_address());
+        assert_eq!(built.fee + built.change, utxo.utxo_entry.amount);
+        assert_eq!(PayloadEncoder::decode(&built.payload).unwrap().unwrap().content, "not yet");
+
+        let dry_run = built.dry_run();
+        assert_eq!(dry_run.txid, built.transaction.id());
+        assert_eq!(dry_run.transaction.payload, hex::encode(&built.payload));
+        let parsed = JsonTransaction::from_hex(&dry_run.tx_hex).unwrap();
+        assert_eq!(parsed.payload, dry_run.transaction.payload);
+        assert_eq!(parsed.inputs[0].signature_script, dry_run.transaction.inputs[0].signature_script);
+
+        let built = build_transfer(TEST_KEY, &test_address(), 10_000_000, Some(mock.url())).await.unwrap();
+        assert!(built.payload.is_empty());
+        assert_eq!(built.transaction.json().outputs.len(), 2);
+        assert_eq!(10_000_000 + built.fee + built.change, utxo.utxo_entry.amount);
+        let pricier = build_transfer_on(TEST_KEY, &test_address(), 10_000_000, Some(mock.url()), 3, None, Network::Testnet10, true).await.unwrap();
+        assert_eq!(pricier.fee, 3 * built.fee);
+
+        assert!(mock.requests_to("POST", "/transactions").is_empty());
+        assert_eq!(mock.requests_to("POST", "/addresses/utxos").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_offline_signing_round_trip() {
+        let utxos = [test_entry(0x11, 30_000_000), test_entry(0x22, 20_000_000)];
+
+        let mock = funded_mock(&utxos).await;
+
+        // Online: only the address is known
+        let unsigned = build_unsigned_transfer_on(&test_address(), &test_address(), 40_000_000, Some(mock.url()), 1, None, Network::Testnet10, true)
+            .await
+            .unwrap();
+        assert_eq!(unsigned.transaction.inputs.len(), 2);
+        let file = serde_json::to_string(&unsigned.transaction).unwrap();
+
+        // Offline: signing is deterministic and matches signing online
+        let signed = sign_offline(TEST_KEY, &file).unwrap();
+        let again = sign_offline(TEST_KEY, &file).unwrap();
+        assert_eq!(signed.txid, again.txid);
+        assert_eq!(signed.tx_hex, again.tx_hex);
+        assert_eq!(signed.fee, unsigned.fee);
+        let online = build_transfer(TEST_KEY, &test_address(), 40_000_000, Some(mock.url())).await.unwrap();
+        assert_eq!(signed.txid, online.transaction.id());
+        assert_eq!(signed.tx_hex, online.transaction.hex());
+
+        // A key that owns none of the inputs can't sign
+        let other = KeyPair::from_hex(&"11".repeat(32)).unwrap().to_hex();
+        assert!(matches!(sign_offline(&other, &file), Err(KaspaGraffitiError::Transaction(_))));
+        assert!(matches!(sign_offline(TEST_KEY, "{}"), Err(KaspaGraffitiError::Transaction(_))));
+        assert!(mock.requests_to("POST", "/transactions").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_skips_spent_and_immature_utxos() {
+        let spendable = test_entry(0x11, 10_000_000);
+        let mut spent = test_entry(0x22, 500_000_000);
+        spent.is_spent = true;
+        // Mined at 4500, so spendable from DAA score 5500
+        let mut immature = test_entry(0x33, 400_000_000);
+        immature.utxo_entry.is_coinbase = true;
+        immature.utxo_entry.block_daa_score = 4_500;
+
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, TESTNET_10_INFO);
+        mock.on("GET", "/info/blockdag", 200, r#"{"virtualDaaScore": "5000"}"#);
+        mock.on_sequence("POST", "/addresses/utxos", vec![
+            MockResponse::new(200, &mock_utxo_entries(&[spent.clone(), immature.clone(), spendable.clone()])),
+            // With only the spent and immature outputs there is nothing to spend
+            MockResponse::new(200, &mock_utxo_entries(&[spent, immature])),
+        ]);
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "cd".repeat(32)));
+
+        let result = send_graffiti(TEST_KEY, "mixed", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await.unwrap();
+        assert_eq!(result.change + result.fee, spendable.utxo_entry.amount);
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let tx = &serde_json::from_str::<serde_json::Value>(&submitted[0].body).unwrap()["transaction"];
+        let inputs = tx["inputs"].as_array().unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0]["previousOutpoint"]["transactionId"], spendable.outpoint.transaction_id);
+
+        let err = send_graffiti(TEST_KEY, "mixed", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await.unwrap_err();
+        assert!(matches!(err, KaspaGraffitiError::InsufficientBalance(0, _)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_timelocked() {
+        let mock = funded_mock(&[test_entry(0x11, 50_000_000)]).await;
+
+        let result = send_graffiti_timelocked(TEST_KEY, "later", None, 8000, Some(mock.url()), 1)
+            .await
+            .unwrap();
+        assert_eq!(result.lock_time, 8000);
+        assert_eq!(result.transaction.lock_time, 8000);
+        assert_eq!(result.change + result.fee, 50_000_000);
+        let payload = hex::decode(&result.transaction.payload).unwrap();
+        assert_eq!(PayloadEncoder::decode(&payload).unwrap().unwrap().content, "later");
+        // Held back for the caller to broadcast
+        assert!(mock.requests_to("POST", "/transactions").is_empty());
+
+        let Err(err) = send_graffiti_timelocked(TEST_KEY, "too late", None, 5000, Some(mock.url()), 1).await else {
+            panic!("lock time in the past was accepted");
+        };
+        assert!(matches!(err, KaspaGraffitiError::InvalidLockTime(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_burn() {
+        let mock = funded_mock(&[test_entry(0x11, 50_000_000)]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "cd".repeat(32)));
+
+        let result = send_graffiti_burn(TEST_KEY, "burnt", None, 100_000, Some(mock.url()), 1)
+            .await
+            .unwrap();
+        assert_eq!(result.burn_address, Network::Testnet10.burn_address());
+        assert_eq!(result.burned + result.change + result.fee, 50_000_000);
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let tx = &serde_json::from_str::<serde_json::Value>(&submitted[0].body).unwrap()["transaction"];
+        let outputs = tx["outputs"].as_array().unwrap();
+        assert_eq!(outputs.len(), 2);
+        let burn = kaspa_addresses::Address::try_from(Network::Testnet10.burn_address()).unwrap();
+        let burn_script = hex::encode(kaspa_txscript::pay_to_address_script(&burn).script());
+        assert_eq!(outputs[0]["scriptPublicKey"]["scriptPublicKey"], burn_script);
+        assert_eq!(outputs[0]["amount"], 100_000);
+        assert_eq!(outputs[1]["amount"], result.change);
+
+        // Dust burns are refused before the node is contacted
+        let Err(err) = send_graffiti_burn(TEST_KEY, "burnt", None, 1, Some("http://127.0.0.1:1"), 1).await else {
+            panic!("burned a dust amount");
+        };
+        assert!(err.to_string().contains("below the dust threshold"), "{}", err);
+    }
+
+    #[test]
+    fn test_build_burn_graffiti_rejects_burning_inputs() {
+        let entries = [test_entry(0x11, 100_000)];
+        let burn_address = Network::Testnet10.burn_address();
+        let build = |burn| build_burn_graffiti_transaction(&entries, &test_address(), burn_address, burn, b"gfx", &test_key(), 1);
+
+        assert!(matches!(build(100_000), Err(KaspaGraffitiError::InsufficientBalance(100_000, _))));
+        assert!(matches!(build(99_500), Err(KaspaGraffitiError::InsufficientBalance(100_000, _))));
+        let (signed, fee, change) = build(50_000).unwrap();
+        assert_eq!(signed.json().outputs[0].amount, 50_000);
+        assert_eq!(50_000 + fee + change, 100_000);
+    }
+
+    #[test]
+    fn test_check_lock_time() {
+        let now_ms = 1_700_000_000_000;
+        assert!(check_lock_time(5001, 5000, now_ms).is_ok());
+        assert!(check_lock_time(5000, 5000, now_ms).is_err());
+        assert!(check_lock_time(now_ms + 60_000, 5000, now_ms).is_ok());
+        assert!(check_lock_time(now_ms - 60_000, 5000, now_ms).is_err());
+    }
+
+    #[test]
+    fn test_selftest_passes() {
+        let report = selftest();
+        let names: Vec<_> = report.steps.iter().map(|step| step.name).collect();
+        assert_eq!(names, SELFTEST_STEPS);
+        for step in &report.steps {
+            assert!(step.passed, "{}: {:?}", step.name, step.error);
+        }
+        assert!(report.passed());
+    }
+
+    #[test]
+    fn test_fold_dust_change() {
+        assert_eq!(fold_dust_change(DUST_THRESHOLD, 2000, P2PK_SCRIPT_LEN), (DUST_THRESHOLD, 2000));
+        assert_eq!(fold_dust_change(DUST_THRESHOLD - 1, 2000, P2PK_SCRIPT_LEN), (0, 2000 + DUST_THRESHOLD - 1));
+        assert_eq!(fold_dust_change(0, 2000, P2PK_SCRIPT_LEN), (0, 2000));
+
+        // A P2SH change script is a byte longer, so its threshold is higher
+        let p2sh = kaspa_addresses::Address::new(kaspa_addresses::Prefix::Testnet, kaspa_addresses::Version::ScriptHash, &[7; 32]);
+        let p2sh_len = output_script_len(&p2sh.to_string()).unwrap();
+        assert!(dust_threshold(p2sh_len) > DUST_THRESHOLD);
+        assert_eq!(fold_dust_change(DUST_THRESHOLD, 2000, p2sh_len), (0, 2000 + DUST_THRESHOLD));
+    }
+
+    #[test]
+    fn test_build_sweep_transaction() {
+        let entries = [test_entry(0x11, 300_000), test_entry(0x22, 200_000)];
+        let destination = KeyPair::from_hex(&"42".repeat(32)).unwrap();
+        let destination = crate::wallet::generate_address(destination.public_key(), Network::Testnet10);
+
+        let (signed, amount, fee) = build_sweep_transaction(&entries, &destination, &test_key(), 2).unwrap();
+        let tx = signed.json();
+        assert_eq!(tx.inputs.len(), 2);
+        // Everything goes to the destination, with no change output
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.outputs[0].amount, amount);
+        assert_eq!(amount + fee, 500_000);
+        assert_eq!(fee, tx.mass * 2);
+
+        // Nothing worth sending once the fee is paid
+        let Err(err) = build_sweep_transaction(&[test_entry(0x11, 2000)], &destination, &test_key(), 2) else {
+            panic!("swept a dust-sized balance");
+        };
+        assert!(matches!(err, KaspaGraffitiError::InsufficientBalance(..)), "{}", err);
+    }
+
+    fn test_recipients(count: u8) -> Vec<(String, u64)> {
+        (1..=count)
+            .map(|i| {
+                let keypair = KeyPair::from_hex(&format!("{:02x}", 0x40 + i).repeat(32)).unwrap();
+                (crate::wallet::generate_address(keypair.public_key(), Network::Testnet10), 10_000 * i as u64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_multi_transfer_two_recipients() {
+        let entries = [test_entry(0x11, 300_000)];
+        let recipients = test_recipients(2);
+
+        let (signed, fee, change) = build_multi_transfer(&entries, &recipients, &test_address(), &test_key(), 2).unwrap();
+        let tx = signed.json();
+        assert_eq!(tx.outputs.len(), 3);
+        assert_eq!(tx.outputs[0].amount, 10_000);
+        assert_eq!(tx.outputs[1].amount, 20_000);
+        assert_eq!(tx.outputs[2].amount, change);
+        assert_eq!(fee, tx.mass * 2);
+        assert_eq!(30_000 + fee + change, 300_000);
+    }
+
+    #[test]
+    fn test_build_multi_transfer_three_recipients() {
+        let entries = [test_entry(0x11, 40_000), test_entry(0x22, 40_000)];
+        let recipients = test_recipients(3);
+
+        let (signed, fee, change) = build_multi_transfer(&entries, &recipients, &test_address(), &test_key(), 1).unwrap();
+        let tx = signed.json();
+        assert_eq!(tx.inputs.len(), 2);
+        assert_eq!(tx.outputs.len(), 4);
+        assert_eq!(tx.outputs.iter().take(3).map(|o| o.amount).sum::<u64>(), 60_000);
+        assert_eq!(60_000 + fee + change, 80_000);
+
+        // Leftover below the dust threshold goes to the fee
+        let entries = [test_entry(0x11, 40_000), test_entry(0x22, 20_000 + fee + 100)];
+        let (signed, dust_fee, change) = build_multi_transfer(&entries, &recipients, &test_address(), &test_key(), 1).unwrap();
+        assert_eq!(signed.json().outputs.len(), 3);
+        assert_eq!(change, 0);
+        assert_eq!(dust_fee, fee + 100);
+    }
+
+    /// A node that only knows one UTXO, standing in for a gRPC connection.
+    struct FakeNode;
+
+    impl RpcTransport for FakeNode {
+        async fn get_balance_by_address(&self, _address: &str) -> std::result::Result<GetBalanceByAddressResponse, RpcError> {
+            Ok(GetBalanceByAddressResponse { balance: 50_000 })
+        }
+
+        async fn get_utxos_by_addresses(&self, _addresses: Vec<String>) -> std::result::Result<GetUtxosByAddressesResponse, RpcError> {
+            Ok(GetUtxosByAddressesResponse { entries: vec![test_entry(0x11, 50_000)] })
+        }
+
+        async fn submit_transaction(&self, _tx: &KaspaSignedTransaction, _allow_orphan: bool) -> std::result::Result<SubmitTransactionResponse, RpcError> {
+            Err(SubmitError::FeeTooLow.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commands_over_other_transport() {
+        let balance = get_balance_with(&FakeNode, &test_address()).await.unwrap();
+        assert_eq!(balance.balance, 50_000);
+
+        let utxos = get_utxos_with(&FakeNode, &test_address()).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, hex::encode([0x11; 32]));
+
+        let (signed, _, _) = build_multi_transfer(&[test_entry(0x11, 50_000)], &test_recipients(1), &test_address(), &test_key(), 1).unwrap();
+        let Err(err) = submit_with(&FakeNode, &signed).await else {
+            panic!("fake node accepted a transaction");
+        };
+        assert!(matches!(err, KaspaGraffitiError::Rejected(SubmitError::FeeTooLow)), "{}", err);
+    }
+
+    #[test]
+    fn test_transfer_breakdown_with_change() {
+        let entries = [test_entry(0x11, 100_000), test_entry(0x22, 300_000)];
+        let recipient = [(test_recipients(1)[0].0.clone(), 150_000)];
+
+        let breakdown = compute_transfer_breakdown(&entries, 150_000, 2);
+        assert!(breakdown.sufficient);
+        assert_eq!(breakdown.selected_inputs.len(), 1);
+        assert_eq!(breakdown.total_in, 300_000);
+        assert_eq!(breakdown.recipient + breakdown.fee + breakdown.change, 300_000);
+
+        // Same figures as the transaction transfer signs
+        let (_, fee, change) = build_multi_transfer(&breakdown.selected_inputs, &recipient, &test_address(), &test_key(), 2).unwrap();
+        assert_eq!((breakdown.fee, breakdown.change), (fee, change));
+    }
+
+    #[test]
+    fn test_transfer_breakdown_exact_funds() {
+        let entries = [test_entry(0x11, 300_000)];
+        let fee_without_change = outputs_signer(&entries, &[(&test_address(), 0)]).unwrap().estimate_mass();
+
+        let breakdown = compute_transfer_breakdown(&entries, 300_000 - fee_without_change, 1);
+        assert!(breakdown.sufficient);
+        assert_eq!((breakdown.fee, breakdown.change), (fee_without_change, 0));
+
+        let (signed, fee, _) = build_multi_transfer(&entries, &[(test_recipients(1)[0].0.clone(), breakdown.recipient)], &test_address(), &test_key(), 1).unwrap();
+        assert_eq!(signed.json().outputs.len(), 1);
+        assert_eq!(fee, breakdown.fee);
+    }
+
+    #[test]
+    fn test_transfer_breakdown_insufficient() {
+        let entries = [test_entry(0x11, 100_000), test_entry(0x22, 300_000)];
+
+        let breakdown = compute_transfer_breakdown(&entries, 400_000, 1);
+        assert!(!breakdown.sufficient);
+        assert_eq!(breakdown.selected_inputs.len(), 2);
+        assert_eq!(breakdown.total_in, 400_000);
+        assert_eq!(breakdown.fee, outputs_signer(&entries, &[(&test_address(), 0)]).unwrap().estimate_mass());
+        assert_eq!(breakdown.change, 0);
+
+        // An amount near u64::MAX can't pay its fee, rather than overflowing
+        assert!(!compute_transfer_breakdown(&entries, u64::MAX, 1).sufficient);
+    }
+
+    #[test]
+    fn test_build_multi_transfer_over_budget() {
+        let entries = [test_entry(0x11, 50_000)];
+        let Err(err) = build_multi_transfer(&entries, &test_recipients(3), &test_address(), &test_key(), 1) else {
+            panic!("sent more than the inputs hold");
+        };
+        assert!(matches!(err, KaspaGraffitiError::InsufficientBalance(50_000, _)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_multi_rejects_dust_output() {
+        let mut recipients = test_recipients(2);
+        recipients[1].1 = DUST_THRESHOLD - 1;
+
+        // Rejected before the node is contacted
+        let Err(err) = transfer_multi(TEST_KEY, recipients, Some("http://127.0.0.1:1"), 1).await else {
+            panic!("sent a dust output");
+        };
+        assert!(err.to_string().contains("below the dust threshold"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_rejects_address_of_other_network() {
+        let keypair = KeyPair::from_hex(TEST_KEY).unwrap();
+        let mainnet = crate::wallet::generate_address(keypair.public_key(), Network::Mainnet);
+
+        // Rejected before the node is contacted
+        let Err(err) = transfer(TEST_KEY, &mainnet, 100_000, Some("http://127.0.0.1:1")).await else {
+            panic!("sent to a mainnet address on testnet");
+        };
+        assert!(matches!(err, KaspaGraffitiError::InvalidAddress(_)), "{}", err);
+        assert!(err.to_string().contains("is not a testnet-10 address"), "{}", err);
+
+        let Err(err) = transfer(TEST_KEY, &test_address(), 0, Some("http://127.0.0.1:1")).await else {
+            panic!("sent a zero amount");
+        };
+        assert!(err.to_string().contains("must be greater than zero"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_wrong_network_node() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, r#"{"networkName": "kaspa-mainnet"}"#);
+        mock.on("POST", "/addresses/utxos", 200, &mock_utxo_entries(&[test_entry(0x11, 100_000_000)]));
+
+        let Err(err) = send_graffiti(TEST_KEY, "wrong node", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await else {
+            panic!("send to a mainnet node succeeded");
+        };
+        assert!(matches!(err, KaspaGraffitiError::NetworkMismatch { .. }), "{:?}", err);
+        assert_eq!(err.to_string(), "Network mismatch: address is testnet-10 but the node is on mainnet");
+        // Caught before any wallet data was requested
+        assert!(mock.requests_to("POST", "/addresses/utxos").is_empty());
+
+        let Err(err) = transfer(TEST_KEY, &test_address(), 100_000, Some(mock.url())).await else {
+            panic!("transfer on a mainnet node succeeded");
+        };
+        assert!(matches!(err, KaspaGraffitiError::NetworkMismatch { .. }), "{:?}", err);
+
+        let Err(err) = transfer_multi_on(TEST_KEY, vec![(test_address(), 100_000)], Some(mock.url()), 1, Network::Testnet11, true).await else {
+            panic!("batch on a mainnet node succeeded");
+        };
+        assert_eq!(err.to_string(), "Network mismatch: address is testnet-11 but the node is on mainnet");
+        assert!(mock.requests_to("POST", "/addresses/utxos").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_balance_rejects_wrong_network_node() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, r#"{"networkName": "kaspa-mainnet"}"#);
+        mock.on("GET", &format!("/addresses/{}/balance", test_address()), 200, r#"{"address": "x", "balance": "5"}"#);
+
+        let Err(err) = get_balance(&test_address(), Some(mock.url())).await else {
+            panic!("testnet balance from a mainnet node");
+        };
+        assert_eq!(err.to_string(), "Network mismatch: address is testnet but the node is on mainnet");
+        assert!(mock.requests_to("GET", &format!("/addresses/{}/balance", test_address())).is_empty());
+
+        let mainnet = crate::wallet::generate_address(KeyPair::from_hex(TEST_KEY).unwrap().public_key(), Network::Mainnet);
+        mock.on("GET", &format!("/addresses/{}/balance", mainnet), 200, &format!(r#"{{"address": "{}", "balance": "5"}}"#, mainnet));
+        assert_eq!(get_balance(&mainnet, Some(mock.url())).await.unwrap().balance, 5);
+
+        // Either testnet node serves kaspatest: addresses
+        let testnet_11 = MockRpc::start().await;
+        testnet_11.on("GET", "/info/network", 200, r#"{"networkName": "kaspa-testnet-11"}"#);
+        testnet_11.on("GET", &format!("/addresses/{}/balance", test_address()), 200, r#"{"address": "x", "balance": "7"}"#);
+        assert_eq!(get_balance(&test_address(), Some(testnet_11.url())).await.unwrap().balance, 7);
+    }
+
+    #[tokio::test]
+    async fn test_send_auto_fee_uses_estimate_or_default() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        let estimate = serde_json::json!({
+            "priorityBucket": { "feerate": 9.0, "estimatedSeconds": 0.5 },
+            "normalBuckets": [{ "feerate": 2.4, "estimatedSeconds": 10.0 }],
+            "lowBuckets": []
+        });
+        mock.on("GET", "/info/fee-estimate", 200, &estimate.to_string());
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        // Compressed payload size varies with the timestamp, so use the one sent
+        let mass = |payload: &[u8]| graffiti_signer(&[test_entry(0x11, 100_000_000)], &test_address(), 0, payload)
+            .unwrap()
+            .estimate_mass();
+
+        let normal = FeePriority::Auto { level: FeeLevel::Normal, default: 5 };
+        let result = send_graffiti(TEST_KEY, "auto", None, Some(mock.url()), normal, false, false).await.unwrap();
+        assert_eq!(result.fee, mass(&result.payload) * 3);
+
+        // No low bucket in the estimate, so the default applies
+        let low = FeePriority::Auto { level: FeeLevel::Low, default: 5 };
+        let result = send_graffiti(TEST_KEY, "auto", None, Some(mock.url()), low, false, false).await.unwrap();
+        assert_eq!(result.fee, mass(&result.payload) * 5);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_fee_rate_without_estimate_endpoint() {
+        let mock = MockRpc::start().await;
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+
+        let auto = FeePriority::Auto { level: FeeLevel::Priority, default: 4 };
+        assert_eq!(resolve_fee_rate(&client, auto).await, (4, None));
+        assert_eq!(resolve_fee_rate(&client, FeePriority::Fixed(2)).await, (2, None));
+        assert_eq!(mock.requests_to("GET", "/info/fee-estimate").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_long_message() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        // Random hex only compresses to about half, leaving a >255 byte payload
+        let noise: Vec<u8> = (0..300).map(|_| rand::random()).collect();
+        let message = hex::encode(noise);
+        let result = send_graffiti(TEST_KEY, &message, None, Some(mock.url()), FeePriority::Fixed(1), false, false).await.unwrap();
+        assert!(result.payload.len() > 255);
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let body: serde_json::Value = serde_json::from_str(&submitted[0].body).unwrap();
+        let on_chain = hex::decode(body["transaction"]["payload"].as_str().unwrap()).unwrap();
+        assert_eq!(PayloadEncoder::decode(&on_chain).unwrap().unwrap().content, message);
+    }
+
+    #[tokio::test]
+    async fn test_derived_address_chain() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+
+        let receive = derive_address_from_seed(seed, 0, false).await.unwrap();
+        let change = derive_address_from_seed(seed, 0, true).await.unwrap();
+        assert_eq!((receive.chain, receive.is_change), (Chain::Receive, false));
+        assert_eq!((change.chain, change.is_change), (Chain::Change, true));
+
+        let json = serde_json::to_value(&change).unwrap();
+        assert_eq!(json["chain"], "change");
+        assert_eq!(json["is_change"], true);
+
+        let mut mixed = derive_many_addresses(seed, 3, false).await.unwrap();
+        mixed.extend(derive_many_addresses(seed, 2, true).await.unwrap());
+        assert!(mixed[..3].iter().all(|a| a.chain == Chain::Receive));
+        assert!(mixed[3..].iter().all(|a| a.chain == Chain::Change));
+
+        let (receive, change) = partition_by_chain(mixed);
+        assert_eq!(receive.iter().map(|a| a.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(change.iter().map(|a| a.index).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_derive_many_matches_single_derivation() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        for is_change in [false, true] {
+            let many = derive_many_addresses(seed, 50, is_change).await.unwrap();
+            assert_eq!(many.len(), 50);
+            for (i, derived) in many.iter().enumerate() {
+                let single = derive_address_from_seed(seed, i as u32, is_change).await.unwrap();
+                assert_eq!(derived.index, i as u32);
+                assert_eq!((&derived.address, &derived.private_key), (&single.address, &single.private_key));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_derive_address_at_custom_path() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let network = Network::Testnet10;
+
+        let custom = derive_address_from_seed_on(seed, 0, false, Some("m/44'/111111'/3'/0/7"), DerivationParams::default(), network).await.unwrap();
+        let account_3 = crate::wallet::ExtendedKey::from_seed(&hex::decode(seed).unwrap())
+            .and_then(|master| master.derive_child(44 + HARDENED_OFFSET))
+            .and_then(|purpose| purpose.derive_child(111111 + HARDENED_OFFSET))
+            .and_then(|coin| coin.derive_child(3 + HARDENED_OFFSET))
+            .unwrap();
+        let expected = account_3.derive_leaf(DerivationParams::default(), false, 7).unwrap();
+        assert_eq!(custom.private_key, expected.keypair().to_hex());
+        assert_eq!((custom.index, custom.chain), (7, Chain::Receive));
+
+        // The account 0 path is the default
+        let default = derive_address_from_seed(seed, 7, false).await.unwrap();
+        let explicit = derive_address_from_seed_on(seed, 0, false, Some("m/44'/111111'/0'/0/7"), DerivationParams::default(), network).await.unwrap();
+        assert_eq!(explicit.address, default.address);
+        assert_ne!(custom.address, default.address);
+
+        let change = derive_address_from_seed_on(seed, 0, false, Some("m/44'/111111'/3'/1/2"), DerivationParams::default(), network).await.unwrap();
+        assert_eq!((change.index, change.chain), (2, Chain::Change));
+        let padded = derive_address_from_seed_on(seed, 0, false, Some("m/44h/111111h/3h/01/2"), DerivationParams::default(), network).await.unwrap();
+        assert_eq!((padded.address, padded.chain), (change.address, Chain::Change));
+
+        let Err(err) = derive_address_from_seed_on(seed, 0, false, Some("m/44'/x"), DerivationParams::default(), network).await else {
+            panic!("malformed path was accepted");
+        };
+        assert!(err.to_string().contains("Invalid derivation path"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_accounts_derive_separate_address_sets() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let derive = |account| derive_many_addresses_on(seed, 5, false, DerivationParams::for_account(account), Network::Testnet10);
+
+        let account_0: Vec<String> = derive(0).await.unwrap().into_iter().map(|a| a.address).collect();
+        let account_1: Vec<String> = derive(1).await.unwrap().into_iter().map(|a| a.address).collect();
+        assert_eq!(account_0, derive_many_addresses(seed, 5, false).await.unwrap().into_iter().map(|a| a.address).collect::<Vec<_>>());
+        assert_eq!(account_1, derive(1).await.unwrap().into_iter().map(|a| a.address).collect::<Vec<_>>());
+        assert!(account_1.iter().all(|address| !account_0.contains(address)));
+
+        for (index, address) in account_1.iter().enumerate() {
+            let path = format!("m/44'/111111'/1'/0/{}", index);
+            let by_path = derive_address_from_seed_on(seed, 0, false, Some(&path), DerivationParams::default(), Network::Testnet10).await.unwrap();
+            assert_eq!(&by_path.address, address);
+        }
+        let single = derive_address_from_seed_on(seed, 3, false, None, DerivationParams::for_account(1), Network::Testnet10).await.unwrap();
+        assert_eq!(single.address, account_1[3]);
+
+        let hardened = DerivationParams { hardened_leaves: true, ..DerivationParams::for_account(1) };
+        let leaves = derive_many_addresses_on(seed, 5, true, hardened, Network::Testnet10).await.unwrap();
+        for leaf in &leaves {
+            let path = format!("m/44'/111111'/1'/1'/{}'", leaf.index);
+            let by_path = derive_address_from_seed_on(seed, 0, false, Some(&path), DerivationParams::default(), Network::Testnet10).await.unwrap();
+            assert_eq!(by_path.address, leaf.address);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_xpub_addresses_match_seed() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let xpub = account_xpub(seed).await.unwrap();
+        assert!(xpub.starts_with("tpub"));
+
+        let watched = derive_many_addresses_from_xpub(&xpub, 20).await.unwrap();
+        let full = derive_many_addresses(seed, 20, false).await.unwrap();
+        assert_eq!(watched.len(), 20);
+        for (watch, derived) in watched.iter().zip(&full) {
+            assert_eq!(watch.index, derived.index);
+            assert_eq!((&watch.address, &watch.public_key), (&derived.address, &derived.public_key));
+        }
+
+        let mainnet = account_xpub_on(seed, DerivationParams::default(), Network::Mainnet).await.unwrap();
+        let watched = derive_many_addresses_from_xpub(&mainnet, 1).await.unwrap();
+        assert!(watched[0].address.starts_with("kaspa:"), "{}", watched[0].address);
+    }
+
+    #[tokio::test]
+    async fn test_verify_backup() {
+        let dir = std::env::temp_dir().join(format!("kaspa-backup-{}", hex::encode(rand::random::<[u8; 8]>())));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wallet.json");
+        let path = path.to_str().unwrap();
+
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let backup = export_backup(seed, path, Network::Testnet10).await.unwrap();
+        assert_eq!(backup.addresses.len(), BACKUP_SAMPLE_SIZE as usize);
+
+        let verification = verify_backup(path).await.unwrap();
+        assert!(verification.is_valid);
+        assert!(verification.checksum_valid);
+        assert_eq!(verification.addresses_checked, BACKUP_SAMPLE_SIZE);
+
+        // Swap in an address from a different seed
+        let other = derive_address_from_seed("0f0e0d0c0b0a09080706050403020100", 2, false).await.unwrap();
+        let mut tampered: WalletBackup = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        tampered.addresses[2] = other.address.clone();
+        std::fs::write(path, serde_json::to_string(&tampered).unwrap()).unwrap();
+
+        let verification = verify_backup(path).await.unwrap();
+        assert!(!verification.is_valid);
+        assert!(!verification.checksum_valid);
+        assert_eq!(verification.mismatches, vec![AddressMismatch {
+            index: 2,
+            expected: backup.addresses[2].clone(),
+            found: other.address,
+        }]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_hd_wallet_gap_limit() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let external = derive_many_addresses(seed, 4, false).await.unwrap();
+        let change = derive_many_addresses(seed, 2, true).await.unwrap();
+
+        let utxos = [
+            entry_at(&external[0].address, 0x01, 1_000, false),
+            entry_at(&external[0].address, 0x01, 2_000, false),
+            entry_at(&change[1].address, 0x01, 7_000, false),
+        ];
+
+        let mock = funded_mock(&utxos).await;
+        // External index 3 has been emptied, which still counts as used
+        mock.on("POST", "/addresses/active", 200, &mock_activity(&[&external[0].address, &external[3].address, &change[1].address]));
+
+        let result = scan_hd_wallet(seed, Some(mock.url()), Some(5)).await.unwrap();
+        assert_eq!(result.highest_external_index, Some(3));
+        assert_eq!(result.highest_change_index, Some(1));
+        assert_eq!(result.total_balance, 10_000);
+
+        let used: Vec<_> = result.addresses.iter().map(|a| (a.is_change, a.index, a.balance)).collect();
+        assert_eq!(used, vec![(false, 0, 3_000), (false, 3, 0), (true, 1, 7_000)]);
+
+        // Indices 0-4 on both chains, then 5-9 to close the gap
+        let requests = mock.requests_to("POST", "/addresses/active");
+        assert_eq!(requests.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(first["addresses"].as_array().unwrap().len(), 10);
+        // Balances are only looked up for the used addresses
+        let lookups = mock.requests_to("POST", "/addresses/utxos");
+        assert_eq!(lookups.len(), 1);
+        let body: serde_json::Value = serde_json::from_str(&lookups[0].body).unwrap();
+        assert_eq!(body["addresses"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_scan_hd_wallet_empty() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, TESTNET_10_INFO);
+        mock.on("POST", "/addresses/active", 200, "[]");
+
+        let result = scan_hd_wallet("000102030405060708090a0b0c0d0e0f", Some(mock.url()), None).await.unwrap();
+        assert!(result.addresses.is_empty());
+        assert_eq!(result.highest_external_index, None);
+        assert!(mock.requests_to("POST", "/addresses/utxos").is_empty());
+
+        let requests = mock.requests_to("POST", "/addresses/active");
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["addresses"].as_array().unwrap().len(), 2 * DEFAULT_GAP_LIMIT as usize);
+    }
+
+    #[tokio::test]
+    async fn test_get_balances_keeps_input_order() {
+        let mock = MockRpc::start().await;
+        let body = r#"[
+            {"address": "kaspatest:qa", "balance": "100"},
+            {"address": "kaspatest:qb", "balance": "0"},
+            {"address": "kaspatest:qc", "balance": "300"}
+        ]"#;
+        mock.on("POST", "/addresses/balances", 200, body);
+
+        let balances = get_balances(&["kaspatest:qc", "kaspatest:qa", "kaspatest:qb"], Some(mock.url())).await.unwrap();
+        let found: Vec<_> = balances.iter().map(|b| (b.address.as_str(), b.balance)).collect();
+        assert_eq!(found, vec![("kaspatest:qc", 300), ("kaspatest:qa", 100), ("kaspatest:qb", 0)]);
+        assert_eq!(mock.requests_to("POST", "/addresses/balances").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_hd_balance() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let external = derive_many_addresses(seed, 2, false).await.unwrap();
+        let change = derive_many_addresses(seed, 1, true).await.unwrap();
+
+        let utxos = [
+            entry_at(&external[0].address, 0x03, 4_000, true),
+            entry_at(&external[1].address, 0x03, 1_500, false),
+            entry_at(&external[1].address, 0x03, 2_500, false),
+            entry_at(&change[0].address, 0x03, 600, false),
+        ];
+
+        let mock = funded_mock(&utxos).await;
+        mock.on("POST", "/addresses/active", 200, &mock_activity(&[&external[0].address, &external[1].address, &change[0].address]));
+
+        let balance = get_hd_balance(seed, 5, Some(mock.url())).await.unwrap();
+        assert_eq!(balance.balance, 4_600);
+        // Spent-only history isn't listed
+        let held: Vec<_> = balance.addresses.iter().map(|a| (a.address.as_str(), a.balance)).collect();
+        assert_eq!(held, vec![(external[1].address.as_str(), 4_000), (change[0].address.as_str(), 600)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_hd_balance_empty_wallet() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, TESTNET_10_INFO);
+        mock.on("POST", "/addresses/active", 200, "[]");
+
+        let balance = get_hd_balance("000102030405060708090a0b0c0d0e0f", DEFAULT_GAP_LIMIT, Some(mock.url())).await.unwrap();
+        assert_eq!(balance.balance, 0);
+        assert!(balance.addresses.is_empty());
+        assert_eq!(mock.requests_to("POST", "/addresses/active").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_used_addresses() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let external = derive_many_addresses(seed, 3, false).await.unwrap();
+        let change = derive_many_addresses(seed, 1, true).await.unwrap();
+
+        let utxos = [
+            entry_at(&change[0].address, 0x02, 1_000, true),
+            entry_at(&external[2].address, 0x02, 1_000, true),
+        ];
+
+        let mock = funded_mock(&utxos).await;
+        mock.on("POST", "/addresses/active", 200, &mock_activity(&[&change[0].address, &external[2].address]));
+
+        let used = scan_used_addresses(seed, 5, Some(mock.url())).await.unwrap();
+        let found: Vec<_> = used.addresses.iter().map(|a| (a.chain, a.index, a.address.as_str())).collect();
+        assert_eq!(found, vec![
+            (Chain::Receive, 2, external[2].address.as_str()),
+            (Chain::Change, 0, change[0].address.as_str()),
+        ]);
+        assert_eq!(used.addresses[0].private_key, external[2].private_key);
+        assert_eq!(used.next_index, 3);
+        assert_eq!(used.next_change_index, 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_used_addresses_other_account() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let params = DerivationParams::for_account(1);
+        let account = derive_many_addresses_on(seed, 2, false, params, Network::Testnet10).await.unwrap();
+
+        let mock = funded_mock(&[entry_at(&account[1].address, 0x04, 1_000, false)]).await;
+        mock.on("POST", "/addresses/active", 200, &mock_activity(&[&account[1].address]));
+
+        let used = scan_used_addresses_on(seed, 5, Some(mock.url()), params, Network::Testnet10, true).await.unwrap();
+        assert_eq!(used.addresses.len(), 1);
+        assert_eq!(used.addresses[0].address, account[1].address);
+        assert_eq!(used.addresses[0].private_key, account[1].private_key);
+        assert_eq!(used.next_index, 2);
+
+        // Account 0 has none of them
+        let default = scan_used_addresses(seed, 5, Some(mock.url())).await.unwrap();
+        assert!(default.addresses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_used_addresses_unused_wallet() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, TESTNET_10_INFO);
+        mock.on("POST", "/addresses/active", 200, "[]");
+
+        let used = scan_used_addresses("000102030405060708090a0b0c0d0e0f", 3, Some(mock.url())).await.unwrap();
+        assert!(used.addresses.is_empty());
+        assert_eq!((used.next_index, used.next_change_index), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_hd_spends_across_addresses() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let external = derive_many_addresses(seed, 3, false).await.unwrap();
+        let change = derive_many_addresses(seed, 1, true).await.unwrap();
+
+        let utxo = |address: &str, public_key_hex: &str, txid_byte: u8| {
+            let mut entry = entry_at(address, txid_byte, 2_000, false);
+            entry.utxo_entry.script_public_key.script = format!("20{}ac", &public_key_hex[2..]);
+            entry
+        };
+        // Neither UTXO covers the fee and minimum change alone
+        let utxos = [
+            utxo(&external[0].address, &external[0].public_key, 0x01),
+            utxo(&external[2].address, &external[2].public_key, 0x02),
+        ];
+
+        let mock = funded_mock(&utxos).await;
+        mock.on("POST", "/addresses/active", 200, &mock_activity(&[&external[0].address, &external[2].address]));
+        mock.on_sequence("POST", "/transactions", vec![
+            MockResponse::new(200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32))),
+            MockResponse::new(400, r#"{"error": "transaction ab was already accepted by the consensus"}"#),
+        ]);
+
+        let result = send_graffiti_hd(seed, "from many", None, Some(mock.url()), 1).await.unwrap();
+
+        let mut inputs_from = result.inputs_from.clone();
+        inputs_from.sort();
+        let mut expected = vec![external[0].address.clone(), external[2].address.clone()];
+        expected.sort();
+        assert_eq!(inputs_from, expected);
+        assert_eq!(result.change_address, change[0].address);
+        assert_eq!(result.change + result.fee, 4_000);
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let body: serde_json::Value = serde_json::from_str(&submitted[0].body).unwrap();
+        let inputs = body["transaction"]["inputs"].as_array().unwrap();
+        assert_eq!(inputs.len(), 2);
+        assert!(inputs.iter().all(|i| i["signatureScript"].as_str().unwrap().len() == 66 * 2));
+        // The scan and the send share one client and one network check
+        assert_eq!(mock.requests_to("GET", "/info/network").len(), 1);
+
+        // Resubmitting a transaction the node already has is not a failure
+        let resent = send_graffiti_hd(seed, "from many", None, Some(mock.url()), 1).await.unwrap();
+        assert_eq!(resent.txid.len(), 64);
+        assert_ne!(resent.txid, result.txid);
+    }
+
+    #[tokio::test]
+    async fn test_anchored_send_spends_anchor() {
+        let mut anchor = test_entry(0x33, 20_000);
+        anchor.outpoint.index = 2;
+
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000), anchor.clone()]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        // Largest-first selection alone would never pick the small anchor
+        let result = send_graffiti_anchored(TEST_KEY, "I own this", &anchor.outpoint, Some(mock.url()), 1)
+            .await
+            .unwrap();
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        let body: serde_json::Value = serde_json::from_str(&submitted[0].body).unwrap();
+        let inputs = body["transaction"]["inputs"].as_array().unwrap();
+        assert_eq!(inputs[0]["previousOutpoint"]["transactionId"], anchor.outpoint.transaction_id);
+        assert_eq!(inputs[0]["previousOutpoint"]["index"], 2);
+        // 20_000 sompi covers the fee on its own
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(result.change, 20_000 - result.fee);
+
+        let missing = GetOutPoint { transaction_id: hex::encode([0x44; 32]), index: 0 };
+        let err = send_graffiti_anchored(TEST_KEY, "I own this", &missing, Some(mock.url()), 1).await;
+        assert!(matches!(err, Err(KaspaGraffitiError::AnchorUnavailable(_))));
+    }
+
+    #[test]
+    fn test_select_with_anchor_adds_inputs_for_fee() {
+        let anchor = test_entry(0x33, 1_500);
+        let others = vec![test_entry(0x11, 50_000), test_entry(0x22, 100_000)];
+
+        let target = graffiti_signer(&[], &test_address(), 0, b"payload").unwrap().estimate_mass() + MIN_CHANGE;
+        let selected = select_with_anchor(anchor, &others, target, 1).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].outpoint.transaction_id, hex::encode([0x33; 32]));
+        assert_eq!(selected[1].utxo_entry.amount, 100_000);
+    }
+
+    #[test]
+    fn test_plan_split_send() {
+        let entries: Vec<_> = (0..200).map(|i| test_entry(i as u8, 50_000)).collect();
+        let payload = graffiti_payload("split me", None, false).unwrap();
+
+        let plan = plan_split_send(&entries, &test_address(), &payload, &test_key(), 1).unwrap();
+        assert_eq!(plan.len(), 4);
+
+        for (tx, fee, _) in &plan {
+            assert!(tx.json().mass <= MAX_STANDARD_MASS);
+            assert_eq!(*fee, tx.json().mass);
+        }
+
+        let (last, _, _) = plan.last().unwrap();
+        assert_eq!(hex::decode(&last.json().payload).unwrap(), payload);
+        let spent: Vec<&str> = last.json().inputs.iter()
+            .map(|i| i.previous_outpoint.transaction_id.as_str())
+            .collect();
+        let consolidated: Vec<&str> = plan[..3].iter().map(|(tx, _, _)| tx.id()).collect();
+        assert_eq!(spent, consolidated);
+
+        // Everything but the fees ends up in the final change output
+        let total_fees: u64 = plan.iter().map(|(_, fee, _)| fee).sum();
+        assert_eq!(plan[3].2, 200 * 50_000 - total_fees);
+
+        // A handful of inputs still goes out as a single transaction
+        let plan = plan_split_send(&entries[..5], &test_address(), &payload, &test_key(), 1).unwrap();
+        assert_eq!(plan.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_split_oversized_send_submits_in_order() {
+        let entries: Vec<_> = (0..200).map(|i| test_entry(i as u8, 50_000)).collect();
+        let mock = funded_mock(&entries).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        let results = split_oversized_send(TEST_KEY, "split me", None, Some(mock.url()), 1)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results[..3].iter().all(|r| r.payload.is_empty()));
+        assert_eq!(PayloadEncoder::decode(&results[3].payload).unwrap().unwrap().content, "split me");
+
+        let submitted = mock.requests_to("POST", "/transactions");
+        assert_eq!(submitted.len(), 4);
+        let last: serde_json::Value = serde_json::from_str(&submitted[3].body).unwrap();
+        assert_eq!(last["transaction"]["inputs"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_rejections() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        mock.on_sequence("POST", "/transactions", vec![
+            MockResponse::new(400, r#"{"error": "transaction ab has 500 fees which is under the required amount of 2000"}"#),
+            MockResponse::new(400, r#"{"error": "transaction ab was already accepted by the consensus"}"#),
+        ]);
+
+        let Err(err) = send_graffiti(TEST_KEY, "gm", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await else {
+            panic!("expected a fee rejection");
+        };
+        assert!(matches!(err, KaspaGraffitiError::Rejected(SubmitError::FeeTooLow)));
+
+        // Resubmitting a transaction the node already has is not a failure
+        let result = send_graffiti(TEST_KEY, "gm", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await.unwrap();
+        assert_eq!(result.txid.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_allow_orphan() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        send_graffiti(TEST_KEY, "default", None, Some(mock.url()), FeePriority::Fixed(1), false, false).await.unwrap();
+        send_graffiti(TEST_KEY, "chained", None, Some(mock.url()), FeePriority::Fixed(1), false, true).await.unwrap();
+
+        let bodies: Vec<serde_json::Value> = mock.requests_to("POST", "/transactions").iter()
+            .map(|r| serde_json::from_str(&r.body).unwrap())
+            .collect();
+        assert_eq!(bodies[0]["allowOrphan"], false);
+        assert_eq!(bodies[1]["allowOrphan"], true);
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_chunked_chains_change() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        let message = "chunk ".repeat(300);
+        let results = send_graffiti_chunked(TEST_KEY, &message, None, Some(mock.url()), 1)
+            .await
+            .unwrap();
+        assert!(results.len() > 1);
+
+        // Each transaction is funded solely by the previous change
+        for pair in results.windows(2) {
+            assert_eq!(pair[0].change, pair[1].change + pair[1].fee);
+        }
+        let submitted = mock.requests_to("POST", "/transactions");
+        assert_eq!(submitted.len(), results.len());
+        for request in &submitted[1..] {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let inputs = body["transaction"]["inputs"].as_array().unwrap();
+            assert_eq!(inputs.len(), 1);
+            assert_eq!(inputs[0]["previousOutpoint"]["index"], 0);
+        }
+
+        let chunks: Vec<_> = results.iter()
+            .map(|r| PayloadEncoder::decode(&r.payload).unwrap().unwrap())
+            .collect();
+        assert_eq!(PayloadEncoder::reassemble(&chunks).unwrap().content, message);
+    }
+
+    #[tokio::test]
+    async fn test_send_graffiti_chunked_reports_sent_chunks_on_rejection() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        let txid = format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32));
+        mock.on_sequence("POST", "/transactions", vec![
+            MockResponse::new(200, &txid),
+            MockResponse::new(400, r#"{"error": "transaction cd has 500 fees which is under the required amount of 2000"}"#),
+        ]);
+
+        let message = "chunk ".repeat(300);
+        let err = send_graffiti_chunked(TEST_KEY, &message, None, Some(mock.url()), 1)
+            .await
+            .unwrap_err();
+        match err {
+            KaspaGraffitiError::ChunkFailed { chunk, total, sent, source } => {
+                assert_eq!(chunk, 2);
+                assert!(total >= 2);
+                assert_eq!(sent, vec!["ab".repeat(32)]);
+                assert!(matches!(*source, KaspaGraffitiError::Rejected(SubmitError::FeeTooLow)));
+            }
+            other => panic!("expected ChunkFailed, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suggest_fee_rate() {
+        let mock = MockRpc::start().await;
+        let estimate = serde_json::json!({
+            "priorityBucket": { "feerate": 3.2, "estimatedSeconds": 0.5 },
+            "normalBuckets": [{ "feerate": 2.0, "estimatedSeconds": 10.0 }, { "feerate": 1.5, "estimatedSeconds": 20.0 }],
+            "lowBuckets": []
+        });
+        mock.on("GET", "/info/fee-estimate", 200, &estimate.to_string());
+
+        let suggestion = suggest_fee_rate(Some(mock.url())).await.unwrap();
+        assert_eq!(suggestion.priority.fee_rate, 4);
+        let normal = suggestion.normal.unwrap();
+        assert_eq!((normal.fee_rate, normal.estimated_seconds), (2, 10.0));
+        assert!(suggestion.low.is_none());
+
+        assert_eq!(FeeLevel::from_name("priority"), Some(FeeLevel::Priority));
+        assert_eq!(FeeLevel::from_name("urgent"), None);
+    }
+
+    #[tokio::test]
+    async fn test_priority_send_falls_back_without_estimate() {
+        let mock = funded_mock(&[test_entry(0x11, 100_000_000)]).await;
+        mock.on("POST", "/transactions", 200, &format!("{{\"transactionId\": \"{}\"}}", "ab".repeat(32)));
+
+        let result = send_graffiti_priority(TEST_KEY, "urgent", None, Some(mock.url())).await.unwrap();
+        assert_eq!(result.fee_rate, PRIORITY_FALLBACK_FEE_RATE);
+        assert!(result.estimated_seconds.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_graffiti() {
+        let txid = "cd".repeat(32);
+        let message = PayloadEncoder::text_to_graffiti("gm kaspa".to_string());
+        let payload = PayloadEncoder::encode(&message).unwrap();
+
+        let mock = MockRpc::start().await;
+        let tx = serde_json::json!({
+            "transaction_id": txid,
+            "payload": hex::encode(&payload),
+            "block_hash": ["ef".repeat(32)],
+            "block_time": 1700000000000u64,
+            "is_accepted": true
+        });
+        mock.on("GET", &format!("/transactions/{}", txid), 200, &tx.to_string());
+
+        let Some(TxGraffiti::Message(decoded)) = read_graffiti(&txid, Some(mock.url())).await.unwrap() else {
+            panic!("no message decoded");
+        };
+        assert_eq!(decoded.message.content, "gm kaspa");
+        assert_eq!(decoded.message.mimetype.as_deref(), Some("text/plain"));
+        assert_eq!(decoded.source, GraffitiSource::Payload);
+        assert!(!decoded.suspicious_timestamp);
+
+        let raw_txid = "ce".repeat(32);
+        let raw = serde_json::json!({ "transaction_id": raw_txid, "payload": hex::encode(b"plain bytes") });
+        mock.on("GET", &format!("/transactions/{}", raw_txid), 200, &raw.to_string());
+        assert!(read_graffiti(&raw_txid, Some(mock.url())).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_graffiti_from_op_return() {
+        let txid = "cf".repeat(32);
+        let payload = PayloadEncoder::encode(&PayloadEncoder::text_to_graffiti("in an output".to_string())).unwrap();
+        let op_return = ScriptData::new_op_return(payload).to_script_vec();
+        let p2pk = kaspa_txscript::pay_to_address_script(&test_address().as_str().try_into().unwrap());
+
+        let mock = MockRpc::start().await;
+        let tx = serde_json::json!({
+            "transaction_id": txid,
+            "payload": "",
+            "outputs": [
+                { "script_public_key": hex::encode(p2pk.script()) },
+                { "script_public_key": hex::encode(&op_return) }
+            ]
+        });
+        mock.on("GET", &format!("/transactions/{}", txid), 200, &tx.to_string());
+
+        let Some(TxGraffiti::Message(decoded)) = read_graffiti(&txid, Some(mock.url())).await.unwrap() else {
+            panic!("no message decoded");
+        };
+        assert_eq!(decoded.message.content, "in an output");
+        assert_eq!(decoded.source, GraffitiSource::OpReturn);
+    }
+
+    #[test]
+    fn test_tx_graffiti_policy() {
+        let message = PayloadEncoder::text_to_graffiti("in an output".to_string());
+        let op_return = hex::encode(ScriptData::new_op_return(PayloadEncoder::encode(&message).unwrap()).to_script_vec());
+
+        // A malformed GFX payload falls back to the OP_RETURN output
+        let mut malformed = PayloadEncoder::encode(&PayloadEncoder::text_to_graffiti("flag".to_string())).unwrap();
+        malformed[4] = 9;
+        let found = tx_graffiti(&hex::encode(&malformed), &[op_return.clone()]).unwrap();
+        assert_eq!(found.message().unwrap().content, "in an output");
+        assert!(tx_graffiti(&hex::encode(&malformed), &[]).is_none());
+
+        let recipient = KeyPair::from_hex(TEST_KEY).unwrap();
+        let sealed = PayloadEncoder::encode_encrypted(&message, &recipient.xonly_pubkey()).unwrap();
+        assert!(matches!(tx_graffiti(&hex::encode(&sealed), &[op_return]), Some(TxGraffiti::Encrypted)));
+    }
+
+    #[tokio::test]
+    async fn test_scan_graffiti_history_resumes_from_checkpoint() {
+        let address = test_address();
+        let txs_path = format!("/addresses/{}/full-transactions", address);
+        let payload = |text: &str| hex::encode(PayloadEncoder::encode(&PayloadEncoder::text_to_graffiti(text.to_string())).unwrap());
+        let page = |range: std::ops::RangeInclusive<u8>| {
+            let txs: Vec<serde_json::Value> = range
+                .map(|i| serde_json::json!({ "transaction_id": format!("{:02x}", i).repeat(32), "payload": payload(&format!("message {}", i)) }))
+                .collect();
+            MockResponse::new(200, &serde_json::Value::from(txs).to_string())
+        };
+
+        let mock = MockRpc::start().await;
+        mock.on("GET", &format!("/addresses/{}/transactions-count", address), 200, r#"{"total": 5}"#);
+        mock.on_sequence("GET", &txs_path, vec![page(1..=2), page(3..=4), page(5..=5)]);
+
+        let checkpoint = std::env::temp_dir().join(format!("scan-{}.json", std::process::id()));
+        let options = ScanOptions {
+            page_size: 2,
+            page_delay: Duration::ZERO,
+            checkpoint: Some(checkpoint.clone()),
+            resume: true,
+        };
+
+        // Interrupted after the first page
+        let mut first = Vec::new();
+        let summary = scan_graffiti_history(&address, Some(mock.url()), &options, |messages| {
+            first.extend(messages.iter().map(|m| m.graffiti.message().unwrap().content.clone()));
+            false
+        })
+        .await
+        .unwrap();
+        assert!(!summary.complete);
+        assert_eq!(first, vec!["message 1", "message 2"]);
+
+        let mut rest = Vec::new();
+        let summary = scan_graffiti_history(&address, Some(mock.url()), &options, |messages| {
+            rest.extend(messages.iter().map(|m| m.graffiti.message().unwrap().content.clone()));
+            true
+        })
+        .await
+        .unwrap();
+        assert!(summary.complete);
+        assert_eq!(summary.resumed_from, 2);
+        assert_eq!(summary.transactions_scanned, 3);
+        assert_eq!(rest, vec!["message 3", "message 4", "message 5"]);
+        let offsets: Vec<String> = mock.requests_to("GET", &txs_path).iter().map(|r| r.path.clone()).collect();
+        assert!(offsets[1].ends_with("offset=2") && offsets[2].ends_with("offset=4"), "{:?}", offsets);
+        assert!(!checkpoint.exists());
+
+        // A corrupt checkpoint starts over
+        std::fs::write(&checkpoint, "not json").unwrap();
+        let mock = MockRpc::start().await;
+        mock.on("GET", &format!("/addresses/{}/transactions-count", address), 200, r#"{"total": 1}"#);
+        mock.on_sequence("GET", &txs_path, vec![page(1..=1)]);
+        let summary = scan_graffiti_history(&address, Some(mock.url()), &options, |_| true).await.unwrap();
+        assert_eq!(summary.resumed_from, 0);
+        assert!(!checkpoint.exists());
+    }
+
+    #[tokio::test]
+    async fn test_read_graffiti_for_address() {
+        let address = test_address();
+        let payload = |text: &str| hex::encode(PayloadEncoder::encode(&PayloadEncoder::text_to_graffiti(text.to_string())).unwrap());
+        let txs = serde_json::json!([
+            { "transaction_id": "01".repeat(32), "payload": payload("newest"), "block_time": 1700000002000u64, "is_accepted": true },
+            { "transaction_id": "02".repeat(32), "payload": "", "block_time": 1700000001500u64, "is_accepted": true },
+            { "transaction_id": "03".repeat(32), "payload": hex::encode(b"GFX not really"), "is_accepted": false },
+            { "transaction_id": "04".repeat(32), "payload": "zz", "is_accepted": true },
+            { "transaction_id": "05".repeat(32), "payload": payload("oldest"), "block_time": 1700000000000u64, "is_accepted": true }
+        ]);
+
+        let mock = MockRpc::start().await;
+        mock.on("GET", &format!("/addresses/{}/full-transactions", address), 200, &txs.to_string());
+
+        let found = read_graffiti_for_address(&address, Some(mock.url())).await.unwrap();
+        let summary: Vec<(&str, &str, Option<u64>)> = found.iter()
+            .map(|g| (g.txid.as_str(), g.graffiti.message().unwrap().content.as_str(), g.block_time))
+            .collect();
+        let (newest, oldest) = ("01".repeat(32), "05".repeat(32));
+        assert_eq!(summary, vec![
+            (newest.as_str(), "newest", Some(1700000002000)),
+            (oldest.as_str(), "oldest", Some(1700000000000)),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_scan_graffiti_unique() {
+        let address = test_address();
+        let payload = |text: &str| hex::encode(PayloadEncoder::encode(&PayloadEncoder::text_to_graffiti(text.to_string())).unwrap());
+        let txs = serde_json::json!([
+            { "transaction_id": "01".repeat(32), "payload": payload("gm"), "is_accepted": true },
+            { "transaction_id": "02".repeat(32), "payload": payload("something else"), "is_accepted": true },
+            { "transaction_id": "03".repeat(32), "payload": payload("gm"), "is_accepted": true }
+        ]);
+
+        let mock = MockRpc::start().await;
+        mock.on("GET", &format!("/addresses/{}/full-transactions", address), 200, &txs.to_string());
+
+        let groups = scan_graffiti_unique(&address, Some(mock.url())).await.unwrap();
+        let summary: Vec<(&str, Vec<String>)> = groups.iter()
+            .map(|(message, txids)| (message.content.as_str(), txids.clone()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("gm", vec!["01".repeat(32), "03".repeat(32)]),
+            ("something else", vec!["02".repeat(32)]),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_mnemonic_wallet_commands() {
+        let generated = generate_mnemonic_wallet_on(12, Network::Testnet10).await.unwrap();
+        let phrase = generated.mnemonic.clone().unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 12);
+
+        let loaded = load_mnemonic_wallet(&phrase, "").await.unwrap();
+        assert_eq!(loaded.seed, generated.seed);
+        assert_eq!(loaded.address, generated.address);
+
+        // A passphrase yields a different wallet from the same words
+        let protected = load_mnemonic_wallet(&phrase, "hunter2").await.unwrap();
+        assert_ne!(protected.address, generated.address);
+
+        let bad = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        let err = load_mnemonic_wallet(bad, "").await.err().unwrap();
+        assert!(err.to_string().contains("Invalid mnemonic"));
+    }
+
+    #[test]
+    fn test_graffiti_payload_limits() {
+        let encoded = graffiti_payload("hello", Some("text/markdown"), false).unwrap();
+        let decoded = PayloadEncoder::decode(&encoded).unwrap().unwrap();
+        assert_eq!(decoded.mimetype.as_deref(), Some("text/markdown"));
+
+        // 120 raw bytes used to be rejected; the encoded size is what counts now
+        assert!(graffiti_payload(&"a".repeat(120), None, false).is_ok());
+        // Repetitive text compresses well under the limit, random hex doesn't
+        assert!(graffiti_payload(&"a".repeat(MAX_PAYLOAD_SIZE), None, false).is_ok());
+        let noise: Vec<u8> = (0..MAX_PAYLOAD_SIZE).map(|_| rand::random()).collect();
+        assert!(graffiti_payload(&hex::encode(noise), None, false).is_err());
+        assert!(graffiti_payload(&"a".repeat(MAX_PAYLOAD_SIZE), None, true).is_ok());
+        assert!(graffiti_payload(&"a".repeat(MAX_PAYLOAD_SIZE + 1), None, true).is_err());
+        assert!(graffiti_payload("hi", Some("application/zip"), false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_utxos_confirmations_and_network() {
+        let mut old = test_entry(0x11, 1_000);
+        old.utxo_entry.block_daa_score = 4_000;
+        let mut new = test_entry(0x22, 2_000);
+        new.utxo_entry.block_daa_score = 4_999;
+
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/blockdag", 200, r#"{"networkName": "kaspa-testnet-10", "virtualDaaScore": "5000", "tipHashes": []}"#);
+        mock.on("GET", &format!("/addresses/{}/utxos", test_address()), 200, &mock_utxo_entries(&[old, new]));
+
+        let utxos = get_utxos(&test_address(), Some(mock.url())).await.unwrap();
+        let confirmations: Vec<_> = utxos.iter().map(|u| u.confirmations).collect();
+        assert_eq!(confirmations, vec![Some(1_000), Some(1)]);
+
+        let mainnet = crate::wallet::generate_address(KeyPair::from_hex(TEST_KEY).unwrap().public_key(), Network::Mainnet);
+        let err = get_utxos(&mainnet, Some(mock.url())).await.unwrap_err();
+        assert_eq!(err.to_string(), "Network mismatch: address is mainnet but the node is on testnet-10");
+        // The network came with the DAG info
+        assert!(mock.requests_to("GET", "/info/network").is_empty());
+
+        let err = get_utxos_on(&mainnet, Some(mock.url()), Network::Testnet10, true).await.unwrap_err();
+        assert!(matches!(err, KaspaGraffitiError::InvalidAddress(_)), "{:?}", err);
+        // Skipping the check leaves the node to answer for itself
+        mock.on("GET", &format!("/addresses/{}/utxos", mainnet), 200, "[]");
+        assert!(get_utxos_on(&mainnet, Some(mock.url()), Network::Mainnet, false).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_utxo_info_to_signer_input() {
+        let entry = test_entry(0x42, 75_000);
+        let utxo = UtxoInfo {
+            txid: entry.outpoint.transaction_id.clone(),
+            vout: 1,
+            amount: 75_000,
+            script_pubkey: entry.utxo_entry.script_public_key.script.clone(),
+            confirmations: None,
+        };
+
+        let input = SignerInput::try_from(&utxo).unwrap();
+        assert_eq!(input.txid, utxo.txid);
+        assert_eq!(input.vout, 1);
+        assert_eq!(input.amount, 75_000);
+        assert_eq!(hex::encode(&input.script_pubkey), utxo.script_pubkey);
+
+        let mut signer = KaspaTransactionSigner::new();
+        signer.add_signer_input(&input).unwrap();
+
+        let bad = UtxoInfo { script_pubkey: "zz".to_string(), ..utxo };
+        assert!(matches!(SignerInput::try_from(&bad), Err(KaspaGraffitiError::Encoding(_))));
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_depths() {
+        let txid = "a1".repeat(32);
+        let block_hash = "b2".repeat(32);
+
+        let mock = MockRpc::start().await;
+        let tx = serde_json::json!({
+            "transaction_id": txid,
+            "payload": "",
+            "block_hash": [block_hash],
+            "is_accepted": true,
+            "accepting_block_blue_score": 9_000
+        });
+        let block = serde_json::json!({
+            "header": { "hash": block_hash, "daaScore": "10050", "blueScore": "8990" }
+        });
+        mock.on("GET", &format!("/transactions/{}", txid), 200, &tx.to_string());
+        mock.on("GET", &format!("/blocks/{}", block_hash), 200, &block.to_string());
+        mock.on("GET", "/info/network", 200, TESTNET_10_INFO);
+        mock.on("GET", "/info/blockdag", 200, r#"{"virtualDaaScore": "10250"}"#);
+        mock.on("GET", "/info/virtual-chain-blue-score", 200, r#"{"blueScore": 9030}"#);
+
+        let info = get_confirmations(&txid, Some(mock.url())).await.unwrap();
+        assert!(info.is_accepted);
+        assert_eq!(info.daa_depth, Some(200));
+        assert_eq!(info.blue_score_depth, Some(30));
+
+        let pending_txid = "a2".repeat(32);
+        let pending = serde_json::json!({ "transaction_id": pending_txid, "is_accepted": false });
+        mock.on("GET", &format!("/transactions/{}", pending_txid), 200, &pending.to_string());
+        let info = get_confirmations(&pending_txid, Some(mock.url())).await.unwrap();
+        assert!(!info.is_accepted);
+        assert_eq!(info.daa_depth, None);
+        assert_eq!(info.blue_score_depth, None);
+
+        assert_eq!(confirmation_depth(100, 150), 0);
+    }
+
+    #[tokio::test]
+    async fn test_hd_seed_lengths() {
+        assert!(load_hd_wallet(&"00".repeat(16)).await.is_ok());
+        assert!(load_hd_wallet(&"00".repeat(64)).await.is_ok());
+
+        let err = load_hd_wallet(&"00".repeat(8)).await.err().unwrap();
+        assert!(err.to_string().contains("Invalid seed length"));
+        assert!(load_hd_wallet(&"00".repeat(65)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_network_selection() {
+        let mainnet = load_wallet_on(TEST_KEY, Network::Mainnet).await.unwrap();
+        assert!(mainnet.address.starts_with("kaspa:"));
+        assert_eq!(mainnet.network, "mainnet");
+
+        let testnet = load_wallet(TEST_KEY).await.unwrap();
+        assert!(testnet.address.starts_with("kaspatest:"));
+        assert_eq!(testnet.network, "testnet-10");
+    }
+
+    #[tokio::test]
+    async fn test_hd_wallet_mnemonic() {
+        let generated = generate_hd_wallet().await.unwrap();
+        let mnemonic = generated.mnemonic.clone().unwrap();
+        assert_eq!(mnemonic.split_whitespace().count(), 24);
+        assert_eq!(generated.seed.len(), 128);
+
+        // Loading by phrase or by the derived seed yields the same wallet
+        let from_phrase = load_hd_wallet(&mnemonic).await.unwrap();
+        assert_eq!(from_phrase.seed, generated.seed);
+        assert_eq!(from_phrase.address, generated.address);
+
+        let from_seed = load_hd_wallet(&generated.seed).await.unwrap();
+        assert_eq!(from_seed.address, generated.address);
+        assert!(from_seed.mnemonic.is_none());
+
+        assert!(load_hd_wallet("not a valid phrase").await.is_err());
+    }
+
+    #[test]
+    fn test_check_rpc_network() {
+        assert!(check_rpc_network(crate::rpc::PUBLIC_TESTNET10_RPC, Network::Testnet10).is_none());
+        assert!(check_rpc_network("http://127.0.0.1:8000", Network::Mainnet).is_none());
+        assert!(check_rpc_network(crate::rpc::PUBLIC_TESTNET10_RPC, Network::Mainnet).is_some());
+        assert!(check_rpc_network("https://api.kaspa.org/", Network::Testnet10).is_some());
+    }
+
+    #[test]
+    fn test_graffiti_fee_scales_with_fee_rate() {
+        let entries = vec![test_entry(0xaa, 100_000_000)];
+        let address = test_address();
+
+        let (tx1, fee1, change1) =
+            build_graffiti_transaction(&entries, &address, b"hello", &test_key(), 1).unwrap();
+        let (_, fee3, change3) =
+            build_graffiti_transaction(&entries, &address, b"hello", &test_key(), 3).unwrap();
+
+        assert_eq!(fee1, tx1.json().mass);
+        assert_eq!(fee3, fee1 * 3);
+        assert_eq!(change1, 100_000_000 - fee1);
+        assert_eq!(change3, 100_000_000 - fee3);
+    }
+
+    #[test]
+    fn test_graffiti_fee_insufficient_balance() {
+        let entries = vec![test_entry(0xaa, 2_000)];
+        let result =
+            build_graffiti_transaction(&entries, &test_address(), b"hello", &test_key(), 1);
+        assert!(matches!(result, Err(KaspaGraffitiError::InsufficientBalance(2_000, _))));
+
+        let result = build_graffiti_transaction(&[], &test_address(), b"hello", &test_key(), 1);
+        assert!(matches!(result, Err(KaspaGraffitiError::EmptyTransaction)));
+    }
+}
@@ -0,0 +1,212 @@
+//! Defaults for `kaspa-graffiti-cli`, read from a TOML config file.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use kaspa_graffiti::wallet::Network;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::cli::Options;
+
+/// Fee rate in sompi/gram when neither the command line nor the config sets one.
+const DEFAULT_FEE_RATE: u64 = 1;
+
+/// Written by `config-init`.
+pub const TEMPLATE: &str = r#"# kaspa-graffiti-cli defaults. Command-line flags and arguments take precedence.
+
+# Network when --network isn't given: mainnet, testnet-10, testnet-11 or simnet
+# network = "testnet-10"
+
+# RPC endpoint when --rpc isn't given. Only used on the network above, so
+# --network still picks that network's public endpoint.
+# rpc = "https://api-tn10.kaspa.org"
+
+# Fee rate in sompi/gram when a command's fee_rate argument is left out
+# fee_rate = 1
+"#;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Cannot read config file {path}: {source}")]
+    Read { path: String, source: std::io::Error },
+    #[error("Invalid config file {path}: {message}")]
+    Parse { path: String, message: String },
+    #[error("Unknown network in config file: {0}")]
+    InvalidNetwork(String),
+    #[error("Cannot write config file {path}: {source}")]
+    Write { path: String, source: std::io::Error },
+    #[error("No config file path: set HOME or pass --config")]
+    NoPath,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub rpc: Option<String>,
+    pub network: Option<String>,
+    pub fee_rate: Option<u64>,
+}
+
+/// The flags with the config filled in underneath them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub rpc_url: Option<String>,
+    pub network: Network,
+    pub fee_rate: u64,
+}
+
+/// `~/.config/kaspa-graffiti/config.toml`
+pub fn default_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").filter(|home| !home.is_empty())?;
+    Some(PathBuf::from(home).join(".config").join("kaspa-graffiti").join("config.toml"))
+}
+
+/// The `--config` path, else the default one.
+pub fn path_for(options: &Options) -> Result<PathBuf, ConfigError> {
+    match &options.config {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => default_path().ok_or(ConfigError::NoPath),
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let display = path.display().to_string();
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: display.clone(), source })?;
+        Self::parse(&text).map_err(|e| ConfigError::Parse { path: display, message: e.to_string() })
+    }
+
+    /// The file named by `--config`, which must exist, else the default
+    /// file if there is one.
+    pub fn for_options(options: &Options) -> Result<Self, ConfigError> {
+        match &options.config {
+            Some(path) => Self::load(Path::new(path)),
+            None => match default_path() {
+                Some(path) if path.exists() => Self::load(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// Fill in what the flags left unset. The config's `rpc` belongs to its
+    /// network, so it is dropped when `--network` picks another one.
+    pub fn merge(&self, options: &Options) -> Result<Settings, ConfigError> {
+        let config_network = match self.network.as_deref() {
+            Some(name) => Some(
+                Network::from_name(name).map_err(|_| ConfigError::InvalidNetwork(name.to_string()))?,
+            ),
+            None => None,
+        };
+        let network = options.network.or(config_network).unwrap_or(Network::Testnet10);
+        let config_rpc = self
+            .rpc
+            .clone()
+            .filter(|_| config_network.unwrap_or(Network::Testnet10) == network);
+
+        Ok(Settings {
+            rpc_url: options.rpc_url.clone().or(config_rpc),
+            network,
+            fee_rate: self.fee_rate.unwrap_or(DEFAULT_FEE_RATE),
+        })
+    }
+}
+
+/// Write `TEMPLATE` to `path`, creating its directory. An existing file is
+/// left alone.
+pub fn write_template(path: &Path) -> Result<(), ConfigError> {
+    let error = |source| ConfigError::Write { path: path.display().to_string(), source };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(error)?;
+    }
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path).map_err(error)?;
+    file.write_all(TEMPLATE.as_bytes()).map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{parse_args, ParsedCommand};
+
+    fn options(args: &[&str]) -> Options {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        match parse_args(&args).unwrap() {
+            ParsedCommand::Run { options, .. } => options,
+            other => panic!("expected a command, got {:?}", other),
+        }
+    }
+
+    const CONFIG: &str = r#"
+network = "mainnet"
+rpc = "http://my-node:16110"
+fee_rate = 5
+"#;
+
+    #[test]
+    fn test_flags_override_config() {
+        let config = Config::parse(CONFIG).unwrap();
+
+        let settings = config.merge(&options(&["balance", "addr"])).unwrap();
+        assert_eq!(settings.network, Network::Mainnet);
+        assert_eq!(settings.rpc_url.as_deref(), Some("http://my-node:16110"));
+        assert_eq!(settings.fee_rate, 5);
+
+        let settings = config.merge(&options(&["balance", "addr", "--rpc", "http://other"])).unwrap();
+        assert_eq!(settings.rpc_url.as_deref(), Some("http://other"));
+        assert_eq!(settings.network, Network::Mainnet);
+
+        // The config's mainnet node isn't used for testnet
+        let settings = config.merge(&options(&["balance", "addr", "--network", "testnet-10"])).unwrap();
+        assert_eq!(settings.network, Network::Testnet10);
+        assert_eq!(settings.rpc_url, None);
+
+        let settings = config.merge(&options(&["balance", "addr", "--network", "mainnet"])).unwrap();
+        assert_eq!(settings.rpc_url.as_deref(), Some("http://my-node:16110"));
+    }
+
+    #[test]
+    fn test_empty_config_keeps_defaults() {
+        let settings = Config::default().merge(&options(&["fee"])).unwrap();
+        assert_eq!(
+            settings,
+            Settings { rpc_url: None, network: Network::Testnet10, fee_rate: DEFAULT_FEE_RATE }
+        );
+
+        // A node set without a network is a testnet-10 node
+        let config = Config::parse(r#"rpc = "http://tn10:16210""#).unwrap();
+        let settings = config.merge(&options(&["fee"])).unwrap();
+        assert_eq!(settings.rpc_url.as_deref(), Some("http://tn10:16210"));
+
+        // The commented-out template changes nothing
+        assert_eq!(Config::parse(TEMPLATE).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(Config::parse("fee = 5").is_err());
+        assert!(Config::parse(r#"fee_rate = "high""#).is_err());
+
+        let config = Config::parse(r#"network = "devnet""#).unwrap();
+        let Err(err) = config.merge(&options(&["fee"])) else { panic!("devnet accepted") };
+        assert!(matches!(err, ConfigError::InvalidNetwork(name) if name == "devnet"));
+    }
+
+    #[test]
+    fn test_write_template() {
+        let dir = std::env::temp_dir().join(format!("kaspa-graffiti-config-{}", std::process::id()));
+        let path = dir.join("nested").join("config.toml");
+
+        write_template(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+        // Never overwrites
+        assert!(matches!(write_template(&path), Err(ConfigError::Write { .. })));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
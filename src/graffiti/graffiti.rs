@@ -1,22 +1,205 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use secp256k1::{PublicKey, SecretKey, XOnlyPublicKey};
 use serde::{Deserialize, Serialize};
 use serde_json;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
 use thiserror::Error;
 
+use crate::wallet::secp;
+
 #[derive(Error, Debug)]
 pub enum GraffitiError {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
     #[error("Base64 error: {0}")]
     Base64(String),
+    #[error("Invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
     #[error("Content too large: {0} bytes (max: {1})")]
     ContentTooLarge(usize, usize),
     #[error("Invalid mimetype: {0}")]
     InvalidMimeType(String),
+    #[error("Mimetype not allowed: {0}")]
+    MimeTypeNotAllowed(String),
+    #[error("Unsupported message version: {0}")]
+    InvalidVersion(u8),
+    #[error("Missing chunk {0} of {1}")]
+    MissingChunk(u16, u16),
+    #[error("Duplicate chunk {0}")]
+    DuplicateChunk(u16),
+    #[error("Invalid chunks: {0}")]
+    InvalidChunks(String),
+    #[error("Reassembled content doesn't match the group's content hash")]
+    ChecksumMismatch,
+    #[error("Compression error: {0}")]
+    Compression(String),
+    #[error("Payload is encrypted; decrypt it with the recipient's key")]
+    Encrypted,
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("Encryption failed: {0}")]
+    Encryption(String),
+    #[error("Decryption failed: {0}")]
+    Decryption(String),
 }
 
-const MAX_PAYLOAD_SIZE: usize = 500;
+/// Default cap on the full encoded payload, header included.
+pub const MAX_PAYLOAD_SIZE: usize = 500;
 const MAGIC_BYTES: &[u8] = b"GFX";
+/// Header format: magic, version, compression flag, u16 LE length of the
+/// stored body. Version 2 payloads had no compression flag, version 1
+/// payloads had no version byte and a single length byte.
+const PAYLOAD_VERSION: u8 = 3;
+const LEGACY_PAYLOAD_VERSION: u8 = 2;
+const HEADER_LEN: usize = MAGIC_BYTES.len() + 1 + 1 + 2;
+/// Marks a payload sealed to one recipient. Layout after the version:
+/// ephemeral compressed pubkey, nonce, u16 LE length and the ciphertext of a
+/// complete plain payload.
+const ENCRYPTED_PAYLOAD_VERSION: u8 = 4;
+const EPHEMERAL_KEY_LEN: usize = 33;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const ENCRYPTED_HEADER_LEN: usize = MAGIC_BYTES.len() + 1 + EPHEMERAL_KEY_LEN + NONCE_LEN + 2;
+/// Bound on decompressed JSON, so a tiny payload can't expand without limit.
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024;
+/// Kaspa mainnet genesis, in Unix seconds. No genuine message is older.
+pub const KASPA_GENESIS_TIMESTAMP: u64 = 1_637_609_671;
+/// How far ahead of the local clock `PayloadEncoder::decode_checked` lets a
+/// timestamp be by default, in seconds.
+pub const DEFAULT_MAX_CLOCK_SKEW: u64 = 10 * 60;
+/// Mimetypes `GraffitiMessage::validate` accepts. `type/*` allows every
+/// subtype of `type`.
+pub const DEFAULT_MIME_ALLOWLIST: &[&str] = &[
+    "text/*",
+    "image/*",
+    "application/json",
+    "application/octet-stream",
+];
+
+/// How the JSON body of a payload is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+    Zstd,
+}
+
+impl Compression {
+    fn flag(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    fn from_flag(flag: u8) -> Result<Self, GraffitiError> {
+        match flag {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Deflate),
+            2 => Ok(Compression::Zstd),
+            other => Err(GraffitiError::Compression(format!(
+                "unknown scheme {}",
+                other
+            ))),
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, GraffitiError> {
+        let err = |e: std::io::Error| GraffitiError::Compression(e.to_string());
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data).map_err(err)?;
+                encoder.finish().map_err(err)
+            }
+            Compression::Zstd => zstd::bulk::compress(data, 19).map_err(err),
+        }
+    }
+
+    fn decompress(self, data: &[u8]) -> Result<Vec<u8>, GraffitiError> {
+        let err = |e: std::io::Error| GraffitiError::Compression(e.to_string());
+        let reader: Box<dyn Read + '_> = match self {
+            Compression::None => return Ok(data.to_vec()),
+            Compression::Deflate => Box::new(flate2::read::DeflateDecoder::new(data)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(data).map_err(err)?),
+        };
+
+        let mut out = Vec::new();
+        reader
+            .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+            .read_to_end(&mut out)
+            .map_err(err)?;
+        if out.len() > MAX_DECOMPRESSED_SIZE {
+            return Err(GraffitiError::Compression(format!(
+                "decompressed body exceeds {} bytes",
+                MAX_DECOMPRESSED_SIZE
+            )));
+        }
+        Ok(out)
+    }
+}
+
+/// Content type of a graffiti message. Unknown types are kept verbatim.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MimeType {
+    TextPlain,
+    TextMarkdown,
+    ImagePng,
+    ImageJpeg,
+    ImageGif,
+    Other(String),
+}
+
+impl MimeType {
+    pub fn is_image(&self) -> bool {
+        matches!(
+            self,
+            MimeType::ImagePng | MimeType::ImageJpeg | MimeType::ImageGif
+        )
+    }
+}
+
+impl std::str::FromStr for MimeType {
+    type Err = std::convert::Infallible;
+
+    /// Matching ignores case and parameters such as `; charset=utf-8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let essence = s
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        Ok(match essence.as_str() {
+            "text/plain" => MimeType::TextPlain,
+            "text/markdown" => MimeType::TextMarkdown,
+            "image/png" => MimeType::ImagePng,
+            "image/jpeg" | "image/jpg" => MimeType::ImageJpeg,
+            "image/gif" => MimeType::ImageGif,
+            _ => MimeType::Other(s.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for MimeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            MimeType::TextPlain => "text/plain",
+            MimeType::TextMarkdown => "text/markdown",
+            MimeType::ImagePng => "image/png",
+            MimeType::ImageJpeg => "image/jpeg",
+            MimeType::ImageGif => "image/gif",
+            MimeType::Other(other) => other,
+        };
+        f.write_str(s)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraffitiMessage {
@@ -25,6 +208,18 @@ pub struct GraffitiMessage {
     pub content: String,
     pub mimetype: Option<String>,
     pub nonce: u32,
+    /// Position of this message in a chunked group, from 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_total: Option<u16>,
+    /// Shared by every chunk of one message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// Hex Blake2b-256 of the whole content, carried by every chunk so a
+    /// lost or altered chunk is caught on reassembly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
 }
 
 impl GraffitiMessage {
@@ -38,6 +233,10 @@ impl GraffitiMessage {
             content,
             mimetype,
             nonce: 0,
+            chunk_index: None,
+            chunk_total: None,
+            group_id: None,
+            content_hash: None,
         }
     }
 
@@ -46,47 +245,397 @@ impl GraffitiMessage {
         self
     }
 
+    /// Whether `timestamp` is more than `max_skew` seconds after `now`, or
+    /// before Kaspa existed.
+    pub fn has_suspicious_timestamp(&self, now: u64, max_skew: u64) -> bool {
+        self.timestamp < KASPA_GENESIS_TIMESTAMP || self.timestamp > now.saturating_add(max_skew)
+    }
+
+    /// Typed view of `mimetype`. Messages without one are plain text.
+    pub fn mime(&self) -> MimeType {
+        match &self.mimetype {
+            Some(mimetype) => mimetype.parse().unwrap(),
+            None => MimeType::TextPlain,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), GraffitiError> {
+        self.validate_with_allowlist(DEFAULT_MIME_ALLOWLIST)
+    }
+
+    /// Like `validate`, accepting the mimetypes in `allowlist` instead of
+    /// `DEFAULT_MIME_ALLOWLIST`.
+    pub fn validate_with_allowlist(&self, allowlist: &[&str]) -> Result<(), GraffitiError> {
         if self.version != 1 {
-            return Err(GraffitiError::InvalidMimeType(
-                "Invalid version".to_string(),
-            ));
+            return Err(GraffitiError::InvalidVersion(self.version));
         }
 
         if let Some(ref mimetype) = self.mimetype {
-            if !mimetype.starts_with("text/") && !mimetype.starts_with("image/") {
-                return Err(GraffitiError::InvalidMimeType(mimetype.clone()));
+            let (type_, subtype) = parse_mime_essence(mimetype)
+                .ok_or_else(|| GraffitiError::InvalidMimeType(mimetype.clone()))?;
+            let allowed = allowlist.iter().any(|entry| {
+                entry.split_once('/').map_or(false, |(t, s)| {
+                    (t == "*" || t.eq_ignore_ascii_case(&type_))
+                        && (s == "*" || s.eq_ignore_ascii_case(&subtype))
+                })
+            });
+            if !allowed {
+                return Err(GraffitiError::MimeTypeNotAllowed(mimetype.clone()));
             }
         }
 
+        // Known image types carry base64 data, which only holds for whole messages
+        let mime = self.mime();
+        if mime.is_image() && self.chunk_total.is_none() {
+            BASE64
+                .decode(&self.content)
+                .map_err(|e| GraffitiError::Base64(format!("{} content: {}", mime, e)))?;
+        }
+
         Ok(())
     }
 }
 
+/// Lowercased type and subtype of a `type/subtype; name=value` mimetype,
+/// or `None` if it isn't one. Names are RFC 2045 tokens; parameter values
+/// may also be quoted.
+fn parse_mime_essence(mimetype: &str) -> Option<(String, String)> {
+    fn is_token(s: &str) -> bool {
+        !s.is_empty()
+            && s.bytes()
+                .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&b))
+    }
+
+    let mut parts = mimetype.split(';');
+    let (type_, subtype) = parts.next()?.trim().split_once('/')?;
+    if !is_token(type_) || !is_token(subtype) {
+        return None;
+    }
+    for param in parts {
+        let (name, value) = param.trim().split_once('=')?;
+        let quoted = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+        if !is_token(name) || !(is_token(value) || quoted) {
+            return None;
+        }
+    }
+    Some((type_.to_ascii_lowercase(), subtype.to_ascii_lowercase()))
+}
+
+/// Length of `c` once escaped in a JSON string.
+fn json_escaped_len(c: char) -> usize {
+    match c {
+        '"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+        c if (c as u32) < 0x20 => 6,
+        c => c.len_utf8(),
+    }
+}
+
+fn is_encrypted(data: &[u8]) -> bool {
+    data.len() > MAGIC_BYTES.len()
+        && data.starts_with(MAGIC_BYTES)
+        && data[MAGIC_BYTES.len()] == ENCRYPTED_PAYLOAD_VERSION
+}
+
+/// Blake2b-256 of chunked content, as hex.
+fn content_hash(content: &str) -> String {
+    use blake2::{digest::consts::U32, Blake2b};
+    hex::encode(Blake2b::<U32>::digest(content.as_bytes()))
+}
+
+/// ChaCha20 key from the ECDH point. Only x is used, so the parity of the
+/// recipient's x-only key doesn't matter.
+fn shared_key(point: &PublicKey, secret: &SecretKey) -> [u8; 32] {
+    let shared = secp256k1::ecdh::shared_secret_point(point, secret);
+    let mut hasher = Sha256::new();
+    hasher.update(b"GFX encrypted message");
+    hasher.update(&shared[..32]);
+    hasher.finalize().into()
+}
+
+/// A decoded message with the result of its timestamp check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckedMessage {
+    pub message: GraffitiMessage,
+    pub suspicious_timestamp: bool,
+}
+
 pub struct PayloadEncoder;
 
 impl PayloadEncoder {
     pub fn encode(message: &GraffitiMessage) -> Result<Vec<u8>, GraffitiError> {
+        Self::encode_with_limit(message, MAX_PAYLOAD_SIZE)
+    }
+
+    /// Encode `message`, rejecting payloads larger than `max_size` bytes
+    /// including the header.
+    ///
+    /// The JSON is stored compressed when deflate or zstd makes it smaller,
+    /// whichever is smallest, and as-is otherwise.
+    pub fn encode_with_limit(
+        message: &GraffitiMessage,
+        max_size: usize,
+    ) -> Result<Vec<u8>, GraffitiError> {
+        message.validate()?;
+
+        let json = serde_json::to_vec(message)?;
+        let mut best = (Compression::None, json.clone());
+        for compression in [Compression::Deflate, Compression::Zstd] {
+            let body = compression.compress(&json)?;
+            if body.len() < best.1.len() {
+                best = (compression, body);
+            }
+        }
+
+        Self::frame(best.0, &best.1, max_size)
+    }
+
+    /// Encode `message` with a fixed `compression`, even if it doesn't shrink.
+    pub fn encode_compressed(
+        message: &GraffitiMessage,
+        compression: Compression,
+        max_size: usize,
+    ) -> Result<Vec<u8>, GraffitiError> {
         message.validate()?;
 
-        let json = serde_json::to_string(message)?;
-        let payload_bytes = json.as_bytes();
+        let json = serde_json::to_vec(message)?;
+        Self::frame(compression, &compression.compress(&json)?, max_size)
+    }
 
-        if payload_bytes.len() > MAX_PAYLOAD_SIZE {
+    fn frame(
+        compression: Compression,
+        body: &[u8],
+        max_size: usize,
+    ) -> Result<Vec<u8>, GraffitiError> {
+        let max_size = max_size.min(HEADER_LEN + u16::MAX as usize);
+        if HEADER_LEN + body.len() > max_size {
             return Err(GraffitiError::ContentTooLarge(
-                payload_bytes.len(),
-                MAX_PAYLOAD_SIZE,
+                HEADER_LEN + body.len(),
+                max_size,
             ));
         }
 
-        let mut result = Vec::with_capacity(MAGIC_BYTES.len() + 1 + payload_bytes.len());
+        let mut result = Vec::with_capacity(HEADER_LEN + body.len());
         result.extend_from_slice(MAGIC_BYTES);
-        result.push(payload_bytes.len() as u8);
-        result.extend_from_slice(payload_bytes);
+        result.push(PAYLOAD_VERSION);
+        result.push(compression.flag());
+        result.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        result.extend_from_slice(body);
 
         Ok(result)
     }
 
+    /// Split `message` into chunk messages that each encode to at most
+    /// `max_size` bytes, for sending as a sequence of transactions.
+    pub fn encode_chunked(
+        message: &GraffitiMessage,
+        max_size: usize,
+    ) -> Result<Vec<Vec<u8>>, GraffitiError> {
+        message.validate()?;
+
+        // An empty chunk with the widest possible index fields
+        let mut template = message.clone();
+        template.content = String::new();
+        template.chunk_index = Some(u16::MAX);
+        template.chunk_total = Some(u16::MAX);
+        template.group_id = Some(hex::encode(rand::random::<[u8; 8]>()));
+        template.content_hash = Some(content_hash(&message.content));
+
+        let overhead = HEADER_LEN + serde_json::to_string(&template)?.len();
+        if overhead >= max_size {
+            return Err(GraffitiError::ContentTooLarge(overhead, max_size));
+        }
+        let room = max_size - overhead;
+
+        // Split on char boundaries, measuring each char as it appears in JSON
+        let mut parts = Vec::new();
+        let mut current = String::new();
+        let mut current_len = 0;
+        for c in message.content.chars() {
+            let len = json_escaped_len(c);
+            if current_len + len > room && !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            current.push(c);
+            current_len += len;
+        }
+        if !current.is_empty() || parts.is_empty() {
+            parts.push(current);
+        }
+
+        let total = u16::try_from(parts.len()).map_err(|_| {
+            GraffitiError::ContentTooLarge(message.content.len(), room * u16::MAX as usize)
+        })?;
+
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, content)| {
+                let mut chunk = template.clone();
+                chunk.content = content;
+                chunk.chunk_index = Some(index as u16);
+                chunk.chunk_total = Some(total);
+                Self::encode_with_limit(&chunk, max_size)
+            })
+            .collect()
+    }
+
+    /// Rebuild the original message from all chunks of one group, in any order.
+    ///
+    /// Chunks carrying a content hash are checked against it, which catches a
+    /// dropped chunk even when the remaining indices look complete.
+    pub fn reassemble(messages: &[GraffitiMessage]) -> Result<GraffitiMessage, GraffitiError> {
+        let first = messages
+            .first()
+            .ok_or_else(|| GraffitiError::InvalidChunks("no chunks".to_string()))?;
+        let (group_id, total) = match (&first.group_id, first.chunk_total) {
+            (Some(group_id), Some(total)) => (group_id, total),
+            _ => {
+                return Err(GraffitiError::InvalidChunks(
+                    "not a chunked message".to_string(),
+                ))
+            }
+        };
+
+        let mut slots: Vec<Option<&GraffitiMessage>> = vec![None; total as usize];
+        for message in messages {
+            if message.group_id.as_ref() != Some(group_id)
+                || message.chunk_total != Some(total)
+                || message.content_hash != first.content_hash
+            {
+                return Err(GraffitiError::InvalidChunks(
+                    "chunks from different groups".to_string(),
+                ));
+            }
+            let index = message
+                .chunk_index
+                .filter(|i| *i < total)
+                .ok_or_else(|| GraffitiError::InvalidChunks("bad chunk index".to_string()))?;
+            if slots[index as usize].replace(message).is_some() {
+                return Err(GraffitiError::DuplicateChunk(index));
+            }
+        }
+
+        let mut content = String::new();
+        for (index, slot) in slots.iter().enumerate() {
+            let chunk = slot.ok_or(GraffitiError::MissingChunk(index as u16, total))?;
+            content.push_str(&chunk.content);
+        }
+
+        if let Some(expected) = &first.content_hash {
+            if content_hash(&content) != *expected {
+                return Err(GraffitiError::ChecksumMismatch);
+            }
+        }
+
+        let head = slots[0].unwrap();
+        let message = GraffitiMessage {
+            version: head.version,
+            timestamp: head.timestamp,
+            content,
+            mimetype: head.mimetype.clone(),
+            nonce: head.nonce,
+            chunk_index: None,
+            chunk_total: None,
+            group_id: None,
+            content_hash: None,
+        };
+        message.validate()?;
+
+        Ok(message)
+    }
+
+    /// Encode `message` sealed to the holder of x-only `recipient_pubkey`,
+    /// using ECDH with a fresh ephemeral key and ChaCha20-Poly1305.
+    pub fn encode_encrypted(
+        message: &GraffitiMessage,
+        recipient_pubkey: &[u8; 32],
+    ) -> Result<Vec<u8>, GraffitiError> {
+        let recipient = XOnlyPublicKey::from_slice(recipient_pubkey)
+            .map_err(|e| GraffitiError::InvalidKey(e.to_string()))?;
+        let recipient = PublicKey::from_x_only_public_key(recipient, secp256k1::Parity::Even);
+
+        let plaintext = Self::encode_with_limit(message, usize::MAX)?;
+        let size = ENCRYPTED_HEADER_LEN + plaintext.len() + TAG_LEN;
+        if size > MAX_PAYLOAD_SIZE {
+            return Err(GraffitiError::ContentTooLarge(size, MAX_PAYLOAD_SIZE));
+        }
+
+        let ephemeral = loop {
+            if let Ok(key) = SecretKey::from_slice(&rand::random::<[u8; 32]>()) {
+                break key;
+            }
+        };
+        let nonce: [u8; NONCE_LEN] = rand::random();
+
+        let mut result = Vec::with_capacity(size);
+        result.extend_from_slice(MAGIC_BYTES);
+        result.push(ENCRYPTED_PAYLOAD_VERSION);
+        result.extend_from_slice(&PublicKey::from_secret_key(secp(), &ephemeral).serialize());
+        result.extend_from_slice(&nonce);
+
+        let cipher = ChaCha20Poly1305::new(&shared_key(&recipient, &ephemeral).into());
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &result,
+                },
+            )
+            .map_err(|e| GraffitiError::Encryption(e.to_string()))?;
+
+        result.extend_from_slice(&(ciphertext.len() as u16).to_le_bytes());
+        result.extend_from_slice(&ciphertext);
+
+        Ok(result)
+    }
+
+    /// Decode a payload, decrypting it with `recipient_privkey` if it was
+    /// made by `encode_encrypted`. Plain payloads decode as with `decode`.
+    pub fn decode_encrypted(
+        data: &[u8],
+        recipient_privkey: &[u8; 32],
+    ) -> Result<Option<GraffitiMessage>, GraffitiError> {
+        if !is_encrypted(data) {
+            return Self::decode(data);
+        }
+        if data.len() < ENCRYPTED_HEADER_LEN {
+            return Ok(None);
+        }
+
+        let secret = SecretKey::from_slice(recipient_privkey)
+            .map_err(|e| GraffitiError::InvalidKey(e.to_string()))?;
+
+        let key_start = MAGIC_BYTES.len() + 1;
+        let nonce_start = key_start + EPHEMERAL_KEY_LEN;
+        let len_start = nonce_start + NONCE_LEN;
+        let ephemeral = PublicKey::from_slice(&data[key_start..nonce_start])
+            .map_err(|e| GraffitiError::InvalidKey(e.to_string()))?;
+        let len = u16::from_le_bytes([data[len_start], data[len_start + 1]]) as usize;
+        if data.len() < ENCRYPTED_HEADER_LEN + len {
+            return Ok(None);
+        }
+
+        let cipher = ChaCha20Poly1305::new(&shared_key(&ephemeral, &secret).into());
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&data[nonce_start..len_start]),
+                Payload {
+                    msg: &data[ENCRYPTED_HEADER_LEN..ENCRYPTED_HEADER_LEN + len],
+                    aad: &data[..len_start],
+                },
+            )
+            .map_err(|_| GraffitiError::Decryption("wrong key or corrupted payload".to_string()))?;
+
+        match Self::decode(&plaintext)? {
+            Some(message) => Ok(Some(message)),
+            None => Err(GraffitiError::Decryption(
+                "not a graffiti payload".to_string(),
+            )),
+        }
+    }
+
     pub fn decode(data: &[u8]) -> Result<Option<GraffitiMessage>, GraffitiError> {
         if data.len() < MAGIC_BYTES.len() + 1 {
             return Ok(None);
@@ -96,22 +645,82 @@ impl PayloadEncoder {
             return Ok(None);
         }
 
-        let payload_len = data[MAGIC_BYTES.len()] as usize;
-        let payload_start = MAGIC_BYTES.len() + 1;
+        if is_encrypted(data) {
+            return Err(GraffitiError::Encrypted);
+        }
+
+        let at = MAGIC_BYTES.len();
+        let (compression, payload_start, payload_len) = match data[at] {
+            PAYLOAD_VERSION => {
+                if data.len() < HEADER_LEN {
+                    return Ok(None);
+                }
+                let compression = Compression::from_flag(data[at + 1])?;
+                let len = u16::from_le_bytes([data[at + 2], data[at + 3]]) as usize;
+                (compression, HEADER_LEN, len)
+            }
+            LEGACY_PAYLOAD_VERSION => {
+                if data.len() < at + 3 {
+                    return Ok(None);
+                }
+                let len = u16::from_le_bytes([data[at + 1], data[at + 2]]) as usize;
+                (Compression::None, at + 3, len)
+            }
+            // Version 1: single length byte straight after the magic
+            len => (Compression::None, at + 1, len as usize),
+        };
 
         if data.len() < payload_start + payload_len {
             return Ok(None);
         }
 
-        let payload = &data[payload_start..payload_start + payload_len];
-        let json_str =
-            std::str::from_utf8(payload).map_err(|e| GraffitiError::Base64(e.to_string()))?;
+        let payload = compression.decompress(&data[payload_start..payload_start + payload_len])?;
+        let json_str = std::str::from_utf8(&payload)?;
 
         let message: GraffitiMessage = serde_json::from_str(json_str)?;
 
         Ok(Some(message))
     }
 
+    /// Like `decode`, also flagging a timestamp more than `max_skew` seconds
+    /// ahead of the local clock or before Kaspa's genesis. Such messages are
+    /// still returned.
+    pub fn decode_checked(
+        data: &[u8],
+        max_skew: u64,
+    ) -> Result<Option<CheckedMessage>, GraffitiError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Ok(Self::decode(data)?.map(|message| CheckedMessage {
+            suspicious_timestamp: message.has_suspicious_timestamp(now, max_skew),
+            message,
+        }))
+    }
+
+    /// Decode any payload: a GFX message if it is one, else the bytes as a
+    /// `text/plain` message if they are UTF-8, else as base64
+    /// `application/octet-stream`. Only GFX messages have a real timestamp;
+    /// the others get 0. `None` for an empty payload.
+    pub fn decode_any(data: &[u8]) -> Result<Option<GraffitiMessage>, GraffitiError> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        if let Some(message) = Self::decode(data)? {
+            return Ok(Some(message));
+        }
+
+        let (content, mimetype) = match std::str::from_utf8(data) {
+            Ok(text) => (text.to_string(), "text/plain"),
+            Err(_) => (BASE64.encode(data), "application/octet-stream"),
+        };
+        Ok(Some(GraffitiMessage {
+            timestamp: 0,
+            ..GraffitiMessage::new(content, Some(mimetype.to_string()))
+        }))
+    }
+
     pub fn encode_base64(message: &GraffitiMessage) -> Result<String, GraffitiError> {
         let bytes = Self::encode(message)?;
         Ok(BASE64.encode(&bytes))
@@ -185,4 +794,461 @@ mod tests {
         let decoded = PayloadEncoder::decode(&encoded).unwrap().unwrap();
         assert_eq!(decoded.nonce, 12345);
     }
+
+    #[test]
+    fn test_long_message_header() {
+        let message = PayloadEncoder::text_to_graffiti("x".repeat(400));
+        let encoded =
+            PayloadEncoder::encode_compressed(&message, Compression::None, MAX_PAYLOAD_SIZE)
+                .unwrap();
+        assert!(encoded.len() > 255);
+        assert_eq!(encoded[3], PAYLOAD_VERSION);
+        assert_eq!(encoded[4], 0);
+        assert_eq!(
+            u16::from_le_bytes([encoded[5], encoded[6]]) as usize,
+            encoded.len() - HEADER_LEN
+        );
+
+        let decoded = PayloadEncoder::decode(&encoded).unwrap().unwrap();
+        assert_eq!(decoded.content, message.content);
+    }
+
+    #[test]
+    fn test_decode_legacy_header() {
+        let message = PayloadEncoder::text_to_graffiti("old format".to_string());
+        let json = serde_json::to_vec(&message).unwrap();
+        let mut legacy = MAGIC_BYTES.to_vec();
+        legacy.push(json.len() as u8);
+        legacy.extend_from_slice(&json);
+
+        let decoded = PayloadEncoder::decode(&legacy).unwrap().unwrap();
+        assert_eq!(decoded.content, "old format");
+    }
+
+    #[test]
+    fn test_decode_version_2_header() {
+        let message = PayloadEncoder::text_to_graffiti("uncompressed".to_string());
+        let json = serde_json::to_vec(&message).unwrap();
+        let mut legacy = MAGIC_BYTES.to_vec();
+        legacy.push(LEGACY_PAYLOAD_VERSION);
+        legacy.extend_from_slice(&(json.len() as u16).to_le_bytes());
+        legacy.extend_from_slice(&json);
+
+        let decoded = PayloadEncoder::decode(&legacy).unwrap().unwrap();
+        assert_eq!(decoded.content, "uncompressed");
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8() {
+        let mut payload = MAGIC_BYTES.to_vec();
+        payload.push(LEGACY_PAYLOAD_VERSION);
+        payload.extend_from_slice(&2u16.to_le_bytes());
+        payload.extend_from_slice(&[0xff, 0xfe]);
+
+        assert!(matches!(
+            PayloadEncoder::decode(&payload),
+            Err(GraffitiError::Utf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_compression_roundtrip() {
+        let message = PayloadEncoder::text_to_graffiti("compress me ".repeat(20));
+        for compression in [Compression::None, Compression::Deflate, Compression::Zstd] {
+            let encoded =
+                PayloadEncoder::encode_compressed(&message, compression, MAX_PAYLOAD_SIZE).unwrap();
+            assert_eq!(encoded[4], compression.flag());
+
+            let decoded = PayloadEncoder::decode(&encoded).unwrap().unwrap();
+            assert_eq!(decoded.content, message.content, "{:?}", compression);
+        }
+    }
+
+    #[test]
+    fn test_compressible_text_shrinks() {
+        let message = PayloadEncoder::text_to_graffiti("to the moon! ".repeat(100));
+        let json_len = serde_json::to_vec(&message).unwrap().len();
+        assert!(HEADER_LEN + json_len > MAX_PAYLOAD_SIZE);
+
+        let encoded = PayloadEncoder::encode(&message).unwrap();
+        assert!(encoded.len() < json_len / 4);
+        assert_ne!(encoded[4], Compression::None.flag());
+        assert_eq!(
+            PayloadEncoder::decode(&encoded).unwrap().unwrap().content,
+            message.content
+        );
+    }
+
+    #[test]
+    fn test_decode_unknown_compression() {
+        let message = PayloadEncoder::text_to_graffiti("flag".to_string());
+        let mut encoded = PayloadEncoder::encode(&message).unwrap();
+        encoded[4] = 9;
+        assert!(matches!(
+            PayloadEncoder::decode(&encoded),
+            Err(GraffitiError::Compression(_))
+        ));
+    }
+
+    #[test]
+    fn test_payload_limit() {
+        let message = PayloadEncoder::text_to_graffiti("y".repeat(200));
+        let encoded = PayloadEncoder::encode(&message).unwrap();
+
+        assert!(PayloadEncoder::encode_with_limit(&message, encoded.len()).is_ok());
+        assert!(matches!(
+            PayloadEncoder::encode_with_limit(&message, encoded.len() - 1),
+            Err(GraffitiError::ContentTooLarge(_, _))
+        ));
+
+        // Random hex doesn't compress below the limit
+        let noise: Vec<u8> = (0..MAX_PAYLOAD_SIZE).map(|_| rand::random()).collect();
+        let too_long = PayloadEncoder::text_to_graffiti(hex::encode(noise));
+        assert!(PayloadEncoder::encode(&too_long).is_err());
+    }
+
+    #[test]
+    fn test_mime_type_parsing() {
+        let cases = [
+            ("text/plain", MimeType::TextPlain),
+            ("Text/Plain; charset=utf-8", MimeType::TextPlain),
+            ("text/markdown", MimeType::TextMarkdown),
+            ("image/png", MimeType::ImagePng),
+            ("image/jpg", MimeType::ImageJpeg),
+            ("IMAGE/JPEG", MimeType::ImageJpeg),
+            ("image/gif", MimeType::ImageGif),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<MimeType>().unwrap(), expected, "{}", s);
+        }
+        assert_eq!(MimeType::ImageJpeg.to_string(), "image/jpeg");
+
+        let other: MimeType = "image/webp".parse().unwrap();
+        assert_eq!(other, MimeType::Other("image/webp".to_string()));
+        assert_eq!(other.to_string(), "image/webp");
+        assert_eq!(other.to_string().parse::<MimeType>().unwrap(), other);
+    }
+
+    #[test]
+    fn test_message_mime_accessor() {
+        let mut message = PayloadEncoder::text_to_graffiti("hi".to_string());
+        assert_eq!(message.mime(), MimeType::TextPlain);
+
+        message.mimetype = None;
+        assert_eq!(message.mime(), MimeType::TextPlain);
+
+        message.mimetype = Some("image/png".to_string());
+        assert!(matches!(message.validate(), Err(GraffitiError::Base64(_))));
+        message.content = BASE64.encode(b"\x89PNG");
+        assert!(message.validate().is_ok());
+
+        // The wire format still carries the mimetype as a string
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["mimetype"], "image/png");
+    }
+
+    #[test]
+    fn test_mimetype_allowlist() {
+        let mut message = PayloadEncoder::text_to_graffiti("{}".to_string());
+        for mimetype in [
+            "text/plain",
+            "Text/HTML; charset=utf-8",
+            "text/plain; charset=\"utf-8\"",
+            "application/json",
+            "application/octet-stream",
+        ] {
+            message.mimetype = Some(mimetype.to_string());
+            assert!(message.validate().is_ok(), "{}", mimetype);
+        }
+
+        for mimetype in ["application/pdf", "video/mp4", "textual/plain"] {
+            message.mimetype = Some(mimetype.to_string());
+            assert!(
+                matches!(
+                    message.validate(),
+                    Err(GraffitiError::MimeTypeNotAllowed(_))
+                ),
+                "{}",
+                mimetype
+            );
+        }
+
+        for mimetype in [
+            "",
+            "text",
+            "text/",
+            "/plain",
+            "text/plain/extra",
+            "text /plain",
+            "text/pl ain",
+            "text/plain; charset",
+            "text/plain; =utf-8",
+            "text/plain;",
+            "tëxt/plain",
+        ] {
+            message.mimetype = Some(mimetype.to_string());
+            assert!(
+                matches!(message.validate(), Err(GraffitiError::InvalidMimeType(_))),
+                "{:?}",
+                mimetype
+            );
+        }
+
+        // A custom allowlist replaces the default one
+        message.mimetype = Some("application/pdf".to_string());
+        assert!(message
+            .validate_with_allowlist(&["application/pdf"])
+            .is_ok());
+        assert!(message.validate_with_allowlist(&["*/*"]).is_ok());
+        message.mimetype = Some("text/plain".to_string());
+        assert!(message.validate_with_allowlist(&["application/*"]).is_err());
+
+        message.version = 2;
+        assert!(matches!(
+            message.validate(),
+            Err(GraffitiError::InvalidVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_decode_flags_suspicious_timestamps() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let encode_at = |timestamp: u64| {
+            let mut message = PayloadEncoder::text_to_graffiti("when?".to_string());
+            message.timestamp = timestamp;
+            PayloadEncoder::encode(&message).unwrap()
+        };
+
+        let checked = PayloadEncoder::decode_checked(&encode_at(now), DEFAULT_MAX_CLOCK_SKEW)
+            .unwrap()
+            .unwrap();
+        assert!(!checked.suspicious_timestamp);
+
+        for timestamp in [
+            0,
+            KASPA_GENESIS_TIMESTAMP - 1,
+            now + DEFAULT_MAX_CLOCK_SKEW + 60,
+        ] {
+            let payload = encode_at(timestamp);
+            let checked = PayloadEncoder::decode_checked(&payload, DEFAULT_MAX_CLOCK_SKEW)
+                .unwrap()
+                .unwrap();
+            assert!(checked.suspicious_timestamp, "{}", timestamp);
+            assert_eq!(checked.message.timestamp, timestamp);
+            // Plain decode stays lenient
+            assert_eq!(
+                PayloadEncoder::decode(&payload).unwrap().unwrap().timestamp,
+                timestamp
+            );
+        }
+
+        // The allowed skew is the caller's
+        let message = GraffitiMessage {
+            timestamp: now + 3_600,
+            ..PayloadEncoder::text_to_graffiti(String::new())
+        };
+        assert!(message.has_suspicious_timestamp(now, 60));
+        assert!(!message.has_suspicious_timestamp(now, 7_200));
+        assert!(!message.has_suspicious_timestamp(u64::MAX, u64::MAX));
+
+        assert!(PayloadEncoder::decode_checked(b"not graffiti", 0)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_decode_any() {
+        let message = PayloadEncoder::text_to_graffiti("framed".to_string()).with_nonce(7);
+        let decoded = PayloadEncoder::decode_any(&PayloadEncoder::encode(&message).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.content, "framed");
+        assert_eq!(decoded.timestamp, message.timestamp);
+        assert_eq!(decoded.nonce, 7);
+
+        let text = PayloadEncoder::decode_any("gm kaspa ✓".as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(text.content, "gm kaspa ✓");
+        assert_eq!(text.mimetype.as_deref(), Some("text/plain"));
+        assert_eq!(text.timestamp, 0);
+
+        // Starts with the magic but isn't a whole GFX payload
+        let truncated = PayloadEncoder::decode_any(b"GFX").unwrap().unwrap();
+        assert_eq!(truncated.content, "GFX");
+
+        let bytes = [0x00, 0xff, 0xfe, 0x80, 0x01];
+        let binary = PayloadEncoder::decode_any(&bytes).unwrap().unwrap();
+        assert_eq!(binary.mimetype.as_deref(), Some("application/octet-stream"));
+        assert_eq!(BASE64.decode(&binary.content).unwrap(), bytes);
+        assert!(binary.validate().is_ok());
+
+        assert!(PayloadEncoder::decode_any(&[]).unwrap().is_none());
+    }
+
+    fn decode_all(payloads: &[Vec<u8>]) -> Vec<GraffitiMessage> {
+        payloads
+            .iter()
+            .map(|p| PayloadEncoder::decode(p).unwrap().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_chunked_roundtrip() {
+        // Quotes and newlines grow when escaped, multi-byte chars must not be split
+        let content = "line \"quoted\"\n ünïcødé ✓ ".repeat(60);
+        let message = GraffitiMessage::new(content.clone(), Some("text/markdown".to_string()));
+
+        let payloads = PayloadEncoder::encode_chunked(&message, MAX_PAYLOAD_SIZE).unwrap();
+        assert!(payloads.len() > 1);
+        assert!(payloads.iter().all(|p| p.len() <= MAX_PAYLOAD_SIZE));
+
+        let mut chunks = decode_all(&payloads);
+        chunks.reverse();
+        let rebuilt = PayloadEncoder::reassemble(&chunks).unwrap();
+        assert_eq!(rebuilt.content, content);
+        assert_eq!(rebuilt.mime(), MimeType::TextMarkdown);
+        assert_eq!(rebuilt.chunk_total, None);
+    }
+
+    #[test]
+    fn test_chunked_image() {
+        let message =
+            GraffitiMessage::new(BASE64.encode([7u8; 1000]), Some("image/png".to_string()));
+
+        let payloads = PayloadEncoder::encode_chunked(&message, MAX_PAYLOAD_SIZE).unwrap();
+        let rebuilt = PayloadEncoder::reassemble(&decode_all(&payloads)).unwrap();
+        assert_eq!(BASE64.decode(rebuilt.content).unwrap(), vec![7u8; 1000]);
+    }
+
+    #[test]
+    fn test_reassemble_errors() {
+        let message = PayloadEncoder::text_to_graffiti("c".repeat(1200));
+        let chunks =
+            decode_all(&PayloadEncoder::encode_chunked(&message, MAX_PAYLOAD_SIZE).unwrap());
+        let total = chunks.len() as u16;
+        assert!(total >= 3);
+
+        let missing: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.chunk_index != Some(1))
+            .cloned()
+            .collect();
+        assert!(matches!(
+            PayloadEncoder::reassemble(&missing),
+            Err(GraffitiError::MissingChunk(1, t)) if t == total
+        ));
+
+        let mut duplicate = chunks.clone();
+        duplicate.push(chunks[2].clone());
+        assert!(matches!(
+            PayloadEncoder::reassemble(&duplicate),
+            Err(GraffitiError::DuplicateChunk(2))
+        ));
+
+        let other =
+            decode_all(&PayloadEncoder::encode_chunked(&message, MAX_PAYLOAD_SIZE).unwrap());
+        let mut mixed = chunks.clone();
+        mixed[0] = other[0].clone();
+        assert!(matches!(
+            PayloadEncoder::reassemble(&mixed),
+            Err(GraffitiError::InvalidChunks(_))
+        ));
+
+        assert!(PayloadEncoder::reassemble(&[message]).is_err());
+    }
+
+    #[test]
+    fn test_reassemble_checks_content_hash() {
+        let message =
+            GraffitiMessage::new(BASE64.encode([9u8; 1500]), Some("image/png".to_string()));
+        let chunks =
+            decode_all(&PayloadEncoder::encode_chunked(&message, MAX_PAYLOAD_SIZE).unwrap());
+        assert!(chunks.len() >= 3);
+        assert!(chunks
+            .iter()
+            .all(|c| c.content_hash == chunks[0].content_hash));
+        assert_eq!(
+            PayloadEncoder::reassemble(&chunks).unwrap().content,
+            message.content
+        );
+
+        // Drop chunk 1 and renumber the rest, so the sequence looks complete
+        let total = chunks.len() as u16 - 1;
+        let dropped: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.chunk_index != Some(1))
+            .enumerate()
+            .map(|(index, c)| {
+                let mut c = c.clone();
+                c.chunk_index = Some(index as u16);
+                c.chunk_total = Some(total);
+                c
+            })
+            .collect();
+        assert!(matches!(
+            PayloadEncoder::reassemble(&dropped),
+            Err(GraffitiError::ChecksumMismatch)
+        ));
+
+        let mut corrupted = chunks.clone();
+        corrupted[1].content.replace_range(0..4, "AAAA");
+        assert!(matches!(
+            PayloadEncoder::reassemble(&corrupted),
+            Err(GraffitiError::ChecksumMismatch)
+        ));
+    }
+
+    fn recipient() -> ([u8; 32], [u8; 32]) {
+        let secret = SecretKey::from_slice(&rand::random::<[u8; 32]>()).unwrap();
+        let (xonly, _) = PublicKey::from_secret_key(secp(), &secret).x_only_public_key();
+        (secret.secret_bytes(), xonly.serialize())
+    }
+
+    #[test]
+    fn test_encrypted_roundtrip() {
+        let message = PayloadEncoder::text_to_graffiti("for your eyes only".to_string());
+
+        // Random keys cover recipients with both even and odd y
+        for _ in 0..8 {
+            let (privkey, pubkey) = recipient();
+            let encoded = PayloadEncoder::encode_encrypted(&message, &pubkey).unwrap();
+            assert!(encoded.len() <= MAX_PAYLOAD_SIZE);
+            assert!(!encoded
+                .windows(message.content.len())
+                .any(|w| w == message.content.as_bytes()));
+
+            let decoded = PayloadEncoder::decode_encrypted(&encoded, &privkey)
+                .unwrap()
+                .unwrap();
+            assert_eq!(decoded.content, message.content);
+        }
+    }
+
+    #[test]
+    fn test_encrypted_wrong_key() {
+        let message = PayloadEncoder::text_to_graffiti("sealed".to_string());
+        let (_, pubkey) = recipient();
+        let (other_privkey, _) = recipient();
+        let encoded = PayloadEncoder::encode_encrypted(&message, &pubkey).unwrap();
+
+        assert!(matches!(
+            PayloadEncoder::decode_encrypted(&encoded, &other_privkey),
+            Err(GraffitiError::Decryption(_))
+        ));
+        assert!(matches!(
+            PayloadEncoder::decode(&encoded),
+            Err(GraffitiError::Encrypted)
+        ));
+
+        // Plain payloads pass straight through
+        let plain = PayloadEncoder::encode(&message).unwrap();
+        let decoded = PayloadEncoder::decode_encrypted(&plain, &other_privkey)
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.content, "sealed");
+    }
 }
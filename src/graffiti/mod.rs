@@ -1,2 +1,5 @@
 mod graffiti;
-pub use graffiti::{GraffitiMessage, PayloadEncoder};
+pub use graffiti::{
+    CheckedMessage, Compression, GraffitiError, GraffitiMessage, MimeType, PayloadEncoder, DEFAULT_MAX_CLOCK_SKEW,
+    DEFAULT_MIME_ALLOWLIST, KASPA_GENESIS_TIMESTAMP, MAX_PAYLOAD_SIZE,
+};
@@ -4,9 +4,9 @@ pub mod graffiti;
 pub mod commands;
 
 pub use wallet::{KeyPair};
-pub use rpc::RpcClient;
-pub use graffiti::{GraffitiMessage, PayloadEncoder};
-pub use commands::{WalletInfo, BalanceInfo, UtxoInfo, SendResult, HDWalletInfo, DerivedAddressInfo};
+pub use rpc::{RpcClient, RpcTransport};
+pub use graffiti::{GraffitiMessage, MimeType, PayloadEncoder};
+pub use commands::{WalletInfo, BalanceInfo, SpendableBalanceInfo, ScanEstimate, UtxoInfo, SendResult, BuiltSend, DryRun, BurnSendResult, TimelockedGraffiti, SweepResult, TransferBreakdown, MultiTransferResult, TransferOutput, UnsignedTransfer, OfflineSignedTx, SelfTestReport, SelfTestStep, FeeRate, FeeSuggestion, HDWalletInfo, DerivedAddressInfo, WatchOnlyAddress, Chain, ConfirmationInfo, HDScanResult, ScannedAddress, UsedAddresses, HDBalanceInfo, HDSendResult, WalletBackup, BackupVerification, AddressMismatch, AddressGraffiti, DecodedGraffiti, TxGraffiti, GraffitiSource, ScanCheckpoint, ScanOptions, ScanSummary};
 
 use thiserror::Error;
 
@@ -33,9 +33,32 @@ pub enum KaspaGraffitiError {
     #[error("No UTXOs available")]
     NoUtxos,
 
+    #[error("Transaction has no inputs")]
+    EmptyTransaction,
+
     #[error("Insufficient balance: have {0}, need {1}")]
     InsufficientBalance(u64, u64),
 
+    #[error("Invalid lock time: {0}")]
+    InvalidLockTime(String),
+
+    #[error("Anchor UTXO unavailable: {0}")]
+    AnchorUnavailable(String),
+
+    #[error("Network mismatch: address is {address} but the node is on {node}")]
+    NetworkMismatch { address: String, node: String },
+
+    #[error(transparent)]
+    Rejected(rpc::SubmitError),
+
+    #[error("Chunk {chunk} of {total} failed after {} sent: {source}", .sent.len())]
+    ChunkFailed {
+        chunk: usize,
+        total: usize,
+        sent: Vec<String>,
+        source: Box<KaspaGraffitiError>,
+    },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -1,137 +1,296 @@
-use kaspa_graffiti::commands::{generate_wallet, load_wallet, get_balance, get_utxos, transfer, send_graffiti, generate_hd_wallet, load_hd_wallet, derive_address_from_seed, derive_many_addresses};
-use kaspa_graffiti::rpc::PUBLIC_TESTNET10_RPC;
+mod cli;
+mod config;
+#[cfg(feature = "qr")]
+mod qr;
+
+use kaspa_graffiti::commands::{FeeLevel, FeePriority, build_graffiti_on, build_transfer_on, build_unsigned_transfer_on, sign_offline, DryRun, generate_wallet_on, load_wallet_on, get_balance_on, get_balances, get_utxos_on, transfer_on, transfer_multi_on, sweep_on, selftest, suggest_fee_rate, send_graffiti_on, send_graffiti_priority_on, read_graffiti, read_graffiti_for_address, scan_graffiti_history, ScanOptions, TxGraffiti, get_confirmations, generate_hd_wallet_on, load_hd_wallet_on, generate_mnemonic_wallet_on, load_mnemonic_wallet_on, HDWalletInfo, WalletInfo, AddressGraffiti, FeeRate, derive_address_from_seed_on, derive_many_addresses_on, account_xpub_on, derive_many_addresses_from_xpub, scan_used_addresses_on, get_hd_balance_on, DEFAULT_GAP_LIMIT, export_backup, verify_backup, default_rpc_url, check_rpc_network};
+use kaspa_graffiti::rpc::{RpcClient, SubmitError, PUBLIC_TESTNET10_RPC};
+use kaspa_graffiti::wallet::{load_keystore, save_keystore, validate_address, verify_message, xonly_pubkey_from_address, DerivationParams, KeyPair};
+use kaspa_graffiti::graffiti::GraffitiMessage;
+use kaspa_graffiti::KaspaGraffitiError;
+use cli::{print_usage, ParseError, ParsedCommand};
+use config::Config;
 use std::env;
+use std::time::Duration;
+
+/// How long `graffiti --wait` polls for acceptance.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[tokio::main]
 async fn main() {
+    // Library diagnostics are opt-in via RUST_LOG, e.g. RUST_LOG=kaspa_graffiti=debug
+    env_logger::init();
+
     let args: Vec<String> = env::args().collect();
-    
-    if args.len() < 2 {
-        print_usage();
-        return;
-    }
-    
-    // Check for --rpc flag
-    let mut rpc_url: Option<&str> = None;
-    let mut cmd_args: Vec<&str> = vec![];
-    
-    let mut i = 1;
-    while i < args.len() {
-        if args[i] == "--rpc" && i + 1 < args.len() {
-            rpc_url = Some(&args[i + 1]);
-            i += 2;
-        } else {
-            cmd_args.push(&args[i]);
-            i += 1;
+
+    let (command, positional, options) = match cli::parse_args(&args[1..]) {
+        Ok(ParsedCommand::Run { command, args, options }) => (command, args, options),
+        Ok(ParsedCommand::Help(Some(command))) => {
+            if let Some(spec) = cli::find_command(command) {
+                cli::print_command_help(spec);
+            }
+            return;
         }
+        Ok(ParsedCommand::Help(None)) | Err(ParseError::MissingCommand) => {
+            print_usage();
+            return;
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("Run `kaspa-graffiti-cli --help` for usage.");
+            std::process::exit(2);
+        }
+    };
+
+    // Flags win over the config file; config-init doesn't read one
+    let config = match command {
+        "config-init" => Ok(Config::default()),
+        _ => Config::for_options(&options),
+    };
+    let settings = match config.and_then(|config| config.merge(&options)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let rpc_url = settings.rpc_url.as_deref();
+    let network = settings.network;
+    let fee_rate = settings.fee_rate;
+    let raw = options.raw;
+    let allow_orphan = options.allow_orphan;
+    let wait = options.wait;
+    let json = options.json;
+    let qr_png = options.qr_png.as_deref();
+    let change_address = options.change_address.as_deref();
+    let derivation = DerivationParams::for_account(options.account);
+    let keystore_key: String;
+    let mut cmd_args: Vec<&str> = std::iter::once(command)
+        .chain(positional.iter().map(String::as_str))
+        .collect();
+
+    // The key from --keystore takes the place of the <private_key> argument
+    if let Some(path) = options.keystore.as_deref() {
+        let password = match rpassword::prompt_password("Keystore password: ") {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Error reading password: {}", e);
+                std::process::exit(1);
+            }
+        };
+        keystore_key = match load_keystore(path, &password) {
+            Ok(keypair) => keypair.to_hex(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        cmd_args.insert(1, &keystore_key);
     }
     
-    if cmd_args.is_empty() {
-        print_usage();
-        return;
+    let check_network = !options.no_network_check;
+    if let Some(url) = rpc_url {
+        if let Some(warning) = check_rpc_network(url, network) {
+            eprintln!("Warning: {}", warning);
+        }
     }
+    let default_rpc = default_rpc_url(network).unwrap_or(PUBLIC_TESTNET10_RPC);
     
     let cmd = cmd_args[0];
     
     match cmd {
         "generate" => {
-            match generate_wallet().await {
+            match generate_wallet_on(network).await {
                 Ok(info) => {
-                    println!("{{");
-                    println!("  \"private_key\": \"{}\",", info.private_key);
-                    println!("  \"public_key\": \"{}\",", info.public_key);
-                    println!("  \"address\": \"{}\",", info.address);
-                    println!("  \"network\": \"{}\"", info.network);
-                    println!("}}");
+                    if json {
+                        cli::print_json(&info);
+                    } else {
+                        print_wallet(&info);
+                    }
+                    show_qr(&info.address, options.qr, qr_png, json);
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
         "load" => {
             if cmd_args.len() < 2 {
-                eprintln!("Usage: kaspa-graffiti-cli load <private_key>");
+                cli::print_usage_error(cmd);
                 return;
             }
-            match load_wallet(&cmd_args[1]).await {
-                Ok(info) => {
-                    println!("{{");
-                    println!("  \"private_key\": \"{}\",", info.private_key);
-                    println!("  \"public_key\": \"{}\",", info.public_key);
-                    println!("  \"address\": \"{}\",", info.address);
-                    println!("  \"network\": \"{}\"", info.network);
-                    println!("}}");
-                }
+            match load_wallet_on(&cmd_args[1], network).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => print_wallet(&info),
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
         "balance" => {
             if cmd_args.len() < 2 {
-                eprintln!("Usage: kaspa-graffiti-cli balance <address> [--rpc <url>]");
+                cli::print_usage_error(cmd);
                 return;
             }
-            let rpc = rpc_url.or(Some(PUBLIC_TESTNET10_RPC));
-            match get_balance(&cmd_args[1], rpc).await {
-                Ok(info) => {
-                    println!("{{");
-                    println!("  \"address\": \"{}\",", info.address);
-                    println!("  \"balance\": {},", info.balance);
-                    println!("  \"kas\": {:.8}", info.balance as f64 / 100_000_000.0);
-                    println!("}}");
+            let rpc = rpc_url.or(Some(default_rpc));
+            match get_balance_on(&cmd_args[1], rpc, network, check_network).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => println!("{}: {} KAS ({} sompi)", info.address, kas(info.balance), info.balance),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "balances" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let rpc = rpc_url.or(Some(default_rpc));
+            match get_balances(&cmd_args[1..], rpc).await {
+                Ok(balances) if json => cli::print_json(&balances),
+                Ok(balances) => {
+                    for info in &balances {
+                        println!("{}: {} KAS", info.address, kas(info.balance));
+                    }
+                    let total: u64 = balances.iter().map(|info| info.balance).sum();
+                    println!("Total: {} KAS ({} sompi)", kas(total), total);
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
         "utxos" => {
             if cmd_args.len() < 2 {
-                eprintln!("Usage: kaspa-graffiti-cli utxos <address> [--rpc <url>]");
+                cli::print_usage_error(cmd);
                 return;
             }
-            let rpc = rpc_url.or(Some(PUBLIC_TESTNET10_RPC));
-            match get_utxos(&cmd_args[1], rpc).await {
+            let rpc = rpc_url.or(Some(default_rpc));
+            match get_utxos_on(&cmd_args[1], rpc, network, check_network).await {
+                Ok(utxos) if json => cli::print_json(&utxos),
                 Ok(utxos) => {
-                    println!("[");
-                    for (i, utxo) in utxos.iter().enumerate() {
-                        println!("  {{");
-                        println!("    \"txid\": \"{}\",", utxo.txid);
-                        println!("    \"vout\": {},", utxo.vout);
-                        println!("    \"amount\": {},", utxo.amount);
-                        println!("    \"kas\": {:.8}", utxo.amount as f64 / 100_000_000.0);
-                        println!("    \"script_pubkey\": \"{}\"", utxo.script_pubkey);
-                        if i < utxos.len() - 1 {
-                            println!("  }},");
-                        } else {
-                            println!("  }}");
+                    for utxo in &utxos {
+                        match utxo.confirmations {
+                            Some(confirmations) => println!("{}:{}  {} KAS  {} confirmations", utxo.txid, utxo.vout, kas(utxo.amount), confirmations),
+                            None => println!("{}:{}  {} KAS", utxo.txid, utxo.vout, kas(utxo.amount)),
                         }
                     }
-                    println!("]");
+                    let total: u64 = utxos.iter().map(|utxo| utxo.amount).sum();
+                    println!("{} UTXOs, {} KAS in total", utxos.len(), kas(total));
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
         "graffiti" => {
             if cmd_args.len() < 3 {
-                eprintln!("Usage: kaspa-graffiti-cli graffiti <private_key> <message> [mimetype] [fee_rate] [--rpc <url>]");
+                cli::print_usage_error(cmd);
                 return;
             }
             let private_key = &cmd_args[1];
             let message = &cmd_args[2];
             let mimetype = cmd_args.get(3).map(|s| *s);
-            let fee_rate = cmd_args.get(4).and_then(|s| s.parse().ok()).unwrap_or(1000u64);
-            let rpc = rpc_url.or(Some(PUBLIC_TESTNET10_RPC));
+            let fee = match (options.fee_priority, cmd_args.get(4)) {
+                (Some(level), _) => FeePriority::Auto { level, default: fee_rate },
+                (None, Some(&"auto")) => FeePriority::Auto { level: FeeLevel::Normal, default: fee_rate },
+                (None, arg) => FeePriority::Fixed(arg.and_then(|s| s.parse().ok()).unwrap_or(fee_rate)),
+            };
+            let rpc = rpc_url.or(Some(default_rpc));
             
-            println!("Sending graffiti message...");
-            println!("Message: {}", message);
-            println!("Fee rate: {} sompi", fee_rate);
+            if !json {
+                println!("{} graffiti message...", if options.dry_run { "Building" } else { "Sending" });
+                println!("Message: {}", message);
+                match fee {
+                    FeePriority::Fixed(fee_rate) => println!("Fee rate: {} sompi/gram", fee_rate),
+                    FeePriority::Auto { .. } => println!("Fee rate: auto (node estimate)"),
+                }
+            }
             
-            match send_graffiti(private_key, message, mimetype, rpc, fee_rate).await {
+            if options.dry_run {
+                match build_graffiti_on(private_key, message, mimetype, rpc, fee, raw, change_address, network, check_network).await {
+                    Ok(built) => print_dry_run(&built.dry_run(), json),
+                    Err(e) => {
+                        eprintln!("\n✗ Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+
+            match send_graffiti_on(private_key, message, mimetype, rpc, fee, raw, allow_orphan, change_address, network, check_network).await {
+                Ok(result) => {
+                    if json {
+                        cli::print_json(&result);
+                    } else {
+                        println!("\n✓ Transaction sent successfully!");
+                        print_fields(&[
+                            ("Txid", result.txid.clone()),
+                            ("Fee", format!("{} sompi", result.fee)),
+                            ("Change", format!("{} KAS", kas(result.change))),
+                            ("Address", result.address.clone()),
+                            ("Payload", result.payload_hex()),
+                        ]);
+                    }
+
+                    // Kept off stdout with --json so it stays a single document
+                    if wait {
+                        if !json {
+                            println!("\nWaiting up to {}s for acceptance...", WAIT_TIMEOUT.as_secs());
+                        }
+                        let accepted = match RpcClient::new(rpc) {
+                            Ok(client) => client.wait_for_acceptance(&result.txid, WAIT_TIMEOUT).await,
+                            Err(e) => Err(e),
+                        };
+                        let status = match accepted {
+                            Ok(true) => "confirmed",
+                            Ok(false) => "still pending",
+                            Err(e) => {
+                                eprintln!("Error: {}", e);
+                                return;
+                            }
+                        };
+                        if json {
+                            eprintln!("{}", status);
+                        } else {
+                            println!("{}", status);
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("\n✗ Error: {}", e);
+                    match e {
+                        KaspaGraffitiError::Rejected(SubmitError::FeeTooLow) => {
+                            eprintln!("Retry with a higher fee rate, or `auto` to use the node's estimate.");
+                        }
+                        KaspaGraffitiError::Rejected(SubmitError::Orphan) => {
+                            eprintln!("An input is not confirmed yet; retry once the previous transaction is accepted, or pass --allow-orphan.");
+                        }
+                        _ => {}
+                    }
+                    std::process::exit(1);
+                }
+            }
+        }
+        "graffiti-priority" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let private_key = &cmd_args[1];
+            let message = &cmd_args[2];
+            let mimetype = cmd_args.get(3).map(|s| *s);
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            if !json {
+                println!("Sending graffiti message at priority fee rate...");
+                println!("Message: {}", message);
+            }
+
+            match send_graffiti_priority_on(private_key, message, mimetype, rpc, network, check_network).await {
+                Ok(result) if json => cli::print_json(&result),
                 Ok(result) => {
                     println!("\n✓ Transaction sent successfully!");
-                    println!("{{");
-                    println!("  \"txid\": \"{}\",", result.txid);
-                    println!("  \"fee\": {},", result.fee);
-                    println!("  \"change\": {},", result.change);
-                    println!("  \"address\": \"{}\"", result.address);
-                    println!("}}");
+                    print_fields(&[
+                        ("Txid", result.txid.clone()),
+                        ("Fee", format!("{} sompi", result.fee)),
+                        ("Fee rate", format!("{} sompi/gram", result.fee_rate)),
+                        ("Estimated time", result.estimated_seconds.map_or("unknown".to_string(), |secs| format!("{}s", secs))),
+                        ("Change", format!("{} KAS", kas(result.change))),
+                        ("Address", result.address.clone()),
+                        ("Payload", hex::encode(&result.payload)),
+                    ]);
                 }
                 Err(e) => {
                     eprintln!("\n✗ Error: {}", e);
@@ -139,35 +298,201 @@ async fn main() {
                 }
             }
         }
+        "read-graffiti" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let txid = &cmd_args[1];
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match read_graffiti(txid, rpc).await {
+                Ok(decoded) if json => cli::print_json(&decoded),
+                Ok(Some(TxGraffiti::Message(decoded))) => {
+                    print_message(&decoded.message);
+                    if decoded.suspicious_timestamp {
+                        eprintln!("Warning: the message's timestamp ({}) is in the future or before Kaspa's genesis", decoded.message.timestamp);
+                    }
+                }
+                Ok(Some(TxGraffiti::Encrypted)) => println!("Transaction {} carries an encrypted graffiti; only the recipient's key can read it", txid),
+                Ok(None) => println!("Transaction {} does not carry a GFX graffiti in its payload or an OP_RETURN output", txid),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "read" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match read_graffiti_for_address(&cmd_args[1], rpc).await {
+                Ok(messages) if json => cli::print_json(&messages),
+                Ok(messages) if messages.is_empty() => println!("No graffiti found"),
+                Ok(messages) => {
+                    for found in &messages {
+                        print_found(found);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "scan" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            if options.resume && options.checkpoint.is_none() {
+                eprintln!("--resume needs --checkpoint <path>");
+                std::process::exit(2);
+            }
+            let rpc = rpc_url.or(Some(default_rpc));
+            let scan_options = ScanOptions {
+                checkpoint: options.checkpoint.as_ref().map(Into::into),
+                resume: options.resume,
+                ..ScanOptions::default()
+            };
+
+            // Messages are printed as each page arrives. With --json that is
+            // one JSON object per line, the summary included.
+            let print_page = |messages: &[AddressGraffiti]| {
+                for found in messages {
+                    if json {
+                        match serde_json::to_string(found) {
+                            Ok(line) => println!("{}", line),
+                            Err(e) => eprintln!("Error: {}", e),
+                        }
+                    } else {
+                        print_found(found);
+                    }
+                }
+                true
+            };
+            match scan_graffiti_history(cmd_args[1], rpc, &scan_options, print_page).await {
+                Ok(summary) if json => match serde_json::to_string(&summary) {
+                    Ok(line) => println!("{}", line),
+                    Err(e) => eprintln!("Error: {}", e),
+                },
+                Ok(summary) => {
+                    println!(
+                        "Scanned {} transactions of {}, found {} messages{}",
+                        summary.transactions_scanned,
+                        summary.address,
+                        summary.messages_found,
+                        if summary.complete { "" } else { " (stopped before the oldest transaction)" },
+                    );
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "qr" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let address = cmd_args[1];
+            if !matches!(validate_address(address, network), Ok(true)) {
+                eprintln!("Error: {} is not a {} address", address, network.name());
+                std::process::exit(1);
+            }
+            show_qr(address, true, qr_png, json);
+        }
+        "fee" => {
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match suggest_fee_rate(rpc).await {
+                Ok(suggestion) if json => cli::print_json(&suggestion),
+                Ok(suggestion) => {
+                    let rate = |rate: Option<&FeeRate>| match rate {
+                        Some(rate) => format!("{} sompi/gram (~{:.1}s)", rate.fee_rate, rate.estimated_seconds),
+                        None => "unavailable".to_string(),
+                    };
+                    print_fields(&[
+                        ("Priority", rate(Some(&suggestion.priority))),
+                        ("Normal", rate(suggestion.normal.as_ref())),
+                        ("Low", rate(suggestion.low.as_ref())),
+                    ]);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "confirmations" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match get_confirmations(&cmd_args[1], rpc).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => match (info.daa_depth, info.blue_score_depth) {
+                    (Some(daa), Some(blue)) if info.is_accepted => {
+                        println!("{}: accepted, {} DAA score / {} blue score deep", info.txid, daa, blue)
+                    }
+                    _ => println!("{}: not accepted yet", info.txid),
+                },
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         "transfer" => {
             if cmd_args.len() < 4 {
-                eprintln!("Usage: kaspa-graffiti-cli transfer <private_key> <recipient> <amount> [--rpc <url>]");
+                cli::print_usage_error(cmd);
                 eprintln!("Example: kaspa-graffiti-cli transfer <key> <addr> 1.0");
                 return;
             }
             let private_key = &cmd_args[1];
             let recipient = &cmd_args[2];
             let amount_str = &cmd_args[3];
-            let amount: u64 = match amount_str.parse::<f64>() {
-                Ok(a) => (a * 100_000_000.0) as u64,
-                Err(_) => {
+            let amount: u64 = match cli::parse_kas(amount_str) {
+                Some(a) => a,
+                None => {
                     eprintln!("Invalid amount: {}", amount_str);
                     return;
                 }
             };
-            let rpc = rpc_url.or(Some(PUBLIC_TESTNET10_RPC));
+            let fee_rate: u64 = cmd_args.get(4).and_then(|s| s.parse().ok()).unwrap_or(fee_rate);
+            let rpc = rpc_url.or(Some(default_rpc));
             
-            println!("Transferring {} KAS to {}...", amount_str, recipient);
+            if !json {
+                println!("Transferring {} KAS to {}...", amount_str, recipient);
+            }
+
+            if options.dry_run {
+                match build_transfer_on(private_key, recipient, amount, rpc, fee_rate, change_address, network, check_network).await {
+                    Ok(built) => print_dry_run(&built.dry_run(), json),
+                    Err(e) => {
+                        eprintln!("\n✗ Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
             
-            match transfer(private_key, recipient, amount, rpc).await {
+            match transfer_on(private_key, recipient, amount, rpc, fee_rate, change_address, network, check_network).await {
+                Ok(result) if json => cli::print_json(&result),
                 Ok(result) => {
                     println!("\n✓ Transfer successful!");
-                    println!("{{");
-                    println!("  \"txid\": \"{}\",", result.txid);
-                    println!("  \"amount\": {},", result.amount);
-                    println!("  \"recipient\": \"{}\",", result.recipient);
-                    println!("  \"fee\": {}", result.fee);
-                    println!("}}");
+                    print_fields(&[
+                        ("Txid", result.txid.clone()),
+                        ("Amount", format!("{} KAS", kas(result.amount))),
+                        ("Recipient", result.recipient.clone()),
+                        ("Fee", format!("{} sompi", result.fee)),
+                    ]);
                 }
                 Err(e) => {
                     eprintln!("\n✗ Error: {}", e);
@@ -175,113 +500,623 @@ async fn main() {
                 }
             }
         }
+        "transfer-multi" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                eprintln!("Example: kaspa-graffiti-cli {} <key> <addr1>=1.0 <addr2>:0.5", cmd);
+                return;
+            }
+            let private_key = &cmd_args[1];
+            let mut outputs = Vec::new();
+            for arg in &cmd_args[2..] {
+                match cli::parse_recipient(arg) {
+                    Some(output) => outputs.push(output),
+                    None => {
+                        eprintln!("Invalid recipient (expected address=amount or address:amount): {}", arg);
+                        return;
+                    }
+                }
+            }
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            if !json {
+                println!("Paying {} recipients...", outputs.len());
+            }
+
+            match transfer_multi_on(private_key, outputs, rpc, fee_rate, network, check_network).await {
+                Ok(result) if json => cli::print_json(&result),
+                Ok(result) => {
+                    println!("\n✓ Transfer successful!");
+                    println!("Txid: {}", result.txid);
+                    for output in &result.outputs {
+                        println!("  {}  {} KAS", output.recipient, kas(output.amount));
+                    }
+                    println!("Fee: {} sompi", result.fee);
+                    println!("Change: {} KAS", kas(result.change));
+                }
+                Err(e) => {
+                    eprintln!("\n✗ Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "sweep" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let private_key = &cmd_args[1];
+            let destination = &cmd_args[2];
+            let fee_rate: u64 = cmd_args.get(3).and_then(|s| s.parse().ok()).unwrap_or(fee_rate);
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            if !json {
+                println!("Sweeping all funds to {}...", destination);
+            }
+
+            match sweep_on(private_key, destination, rpc, fee_rate, network, check_network).await {
+                Ok(result) if json => cli::print_json(&result),
+                Ok(result) => {
+                    println!("\n✓ Swept {} sompi ({} KAS) from {} UTXOs", result.amount, kas(result.amount), result.inputs);
+                    print_fields(&[
+                        ("Txid", result.txid.clone()),
+                        ("Fee", format!("{} sompi", result.fee)),
+                        ("Destination", result.destination.clone()),
+                    ]);
+                }
+                Err(e) => {
+                    eprintln!("\n✗ Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "build-unsigned" => {
+            if cmd_args.len() < 4 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let amount: u64 = match cli::parse_kas(cmd_args[3]) {
+                Some(a) => a,
+                None => {
+                    eprintln!("Invalid amount: {}", cmd_args[3]);
+                    return;
+                }
+            };
+            let fee_rate: u64 = cmd_args.get(4).and_then(|s| s.parse().ok()).unwrap_or(fee_rate);
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match build_unsigned_transfer_on(cmd_args[1], cmd_args[2], amount, rpc, fee_rate, change_address, network, check_network).await {
+                // The unsigned transaction alone goes to stdout, ready to
+                // redirect into the file sign-tx reads
+                Ok(built) => {
+                    cli::print_json(&built.transaction);
+                    if !json {
+                        eprintln!("Fee: {} sompi, change: {} KAS", built.fee, kas(built.change));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "sign-tx" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let unsigned = match std::fs::read_to_string(cmd_args[2]) {
+                Ok(text) => text,
+                Err(e) => {
+                    eprintln!("Cannot read {}: {}", cmd_args[2], e);
+                    std::process::exit(1);
+                }
+            };
+            match sign_offline(cmd_args[1], &unsigned) {
+                Ok(signed) if json => cli::print_json(&signed),
+                Ok(signed) => {
+                    print_fields(&[
+                        ("Txid", signed.txid.clone()),
+                        ("Fee", format!("{} sompi", signed.fee)),
+                        ("Hex", signed.tx_hex.clone()),
+                    ]);
+                    match serde_json::to_string_pretty(&signed.transaction) {
+                        Ok(text) => println!("Transaction:\n{}", text),
+                        Err(e) => eprintln!("Error: {}", e),
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "sign-message" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let keypair = match KeyPair::from_hex(cmd_args[1])
+                .or_else(|_| KeyPair::from_wif(cmd_args[1]).map(|(keypair, _)| keypair))
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let signature = keypair.sign_message(cmd_args[2].as_bytes());
+            let public_key = hex::encode(&keypair.public_key_bytes()[1..]);
+            if json {
+                cli::print_json(&serde_json::json!({
+                    "public_key": public_key,
+                    "signature": hex::encode(signature),
+                }));
+            } else {
+                print_fields(&[("Public key", public_key), ("Signature", hex::encode(signature))]);
+            }
+        }
+        "verify-message" => {
+            if cmd_args.len() < 4 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            // An address or a compressed public key works too; only the x
+            // coordinate is used
+            let pubkey = if cmd_args[1].contains(':') {
+                match xonly_pubkey_from_address(cmd_args[1]) {
+                    Ok(xonly) => xonly.to_vec(),
+                    Err(e) => {
+                        eprintln!("Invalid address: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                match hex::decode(cmd_args[1]) {
+                    Ok(bytes) if bytes.len() == 33 => bytes[1..].to_vec(),
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        eprintln!("Invalid public key: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            };
+            let signature: [u8; 64] = match hex::decode(cmd_args[3]).ok().and_then(|sig| sig.try_into().ok()) {
+                Some(sig) => sig,
+                None => {
+                    eprintln!("Invalid signature: expected 64 bytes of hex");
+                    std::process::exit(1);
+                }
+            };
+            let valid = verify_message(&pubkey, cmd_args[2].as_bytes(), &signature);
+            if json {
+                cli::print_json(&serde_json::json!({ "valid": valid }));
+            } else if valid {
+                println!("✓ Valid signature");
+            } else {
+                println!("✗ Invalid signature");
+            }
+            if !valid {
+                std::process::exit(1);
+            }
+        }
+        "keystore-save" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let keypair = match KeyPair::from_hex(cmd_args[1])
+                .or_else(|_| KeyPair::from_wif(cmd_args[1]).map(|(keypair, _)| keypair))
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let password = match rpassword::prompt_password("New keystore password: ")
+                .and_then(|p| rpassword::prompt_password("Repeat password: ").map(|again| (p, again)))
+            {
+                Ok((p, again)) if p == again => p,
+                Ok(_) => {
+                    eprintln!("Passwords do not match");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error reading password: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            match save_keystore(cmd_args[2], &keypair, &password) {
+                Ok(_) if json => cli::print_json(&serde_json::json!({ "path": cmd_args[2] })),
+                Ok(_) => println!("Keystore written to {}", cmd_args[2]),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "config-init" => {
+            let written = config::path_for(&options)
+                .and_then(|path| config::write_template(&path).map(|_| path));
+            match written {
+                Ok(path) if json => cli::print_json(&serde_json::json!({ "path": path })),
+                Ok(path) => println!("Wrote {}", path.display()),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "selftest" => {
+            let report = selftest();
+            if json {
+                cli::print_json(&report);
+            } else {
+                for step in &report.steps {
+                    match &step.error {
+                        None => println!("✓ {}", step.name),
+                        Some(e) => println!("✗ {}: {}", step.name, e),
+                    }
+                }
+            }
+            if !report.passed() {
+                std::process::exit(1);
+            }
+        }
         "hd-generate" => {
-            match generate_hd_wallet().await {
+            match generate_hd_wallet_on(network).await {
                 Ok(info) => {
-                    println!("{{");
-                    println!("  \"seed\": \"{}\",", info.seed);
-                    println!("  \"address\": \"{}\",", info.address);
-                    println!("  \"network\": \"{}\"", info.network);
-                    println!("}}");
+                    if json {
+                        cli::print_json(&info);
+                    } else {
+                        print_hd_wallet(&info);
+                    }
+                    show_qr(&info.address, options.qr, qr_png, json);
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
         "hd-load" => {
             if cmd_args.len() < 2 {
-                eprintln!("Usage: kaspa-graffiti-cli hd-load <seed>");
+                cli::print_usage_error(cmd);
+                return;
+            }
+            match load_hd_wallet_on(&cmd_args[1], network).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => print_hd_wallet(&info),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "mnemonic-generate" => {
+            let words = match cmd_args.get(1).map(|s| s.parse::<usize>()) {
+                None => 24,
+                Some(Ok(words)) => words,
+                Some(Err(_)) => {
+                    cli::print_usage_error(cmd);
+                    return;
+                }
+            };
+            match generate_mnemonic_wallet_on(words, network).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => print_hd_wallet(&info),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "mnemonic-load" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
                 return;
             }
-            match load_hd_wallet(&cmd_args[1]).await {
+            let passphrase = cmd_args.get(2).copied().unwrap_or("");
+            match load_mnemonic_wallet_on(&cmd_args[1], passphrase, network).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => print_hd_wallet(&info),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "hd-balance" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let gap_limit: u32 = cmd_args.get(2).and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_GAP_LIMIT);
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match get_hd_balance_on(cmd_args[1], gap_limit, rpc, derivation, network, check_network).await {
+                Ok(info) if json => cli::print_json(&info),
                 Ok(info) => {
-                    println!("{{");
-                    println!("  \"seed\": \"{}\",", info.seed);
-                    println!("  \"address\": \"{}\",", info.address);
-                    println!("  \"network\": \"{}\"", info.network);
-                    println!("}}");
+                    for addr in &info.addresses {
+                        let chain = if addr.is_change { "change" } else { "receive" };
+                        println!("{:<8} {:>4}  {}  {} KAS", chain, addr.index, addr.address, kas(addr.balance));
+                    }
+                    println!("Total: {} KAS ({} sompi)", kas(info.balance), info.balance);
+                }
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "hd-scan" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let gap_limit: u32 = cmd_args.get(2).and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_GAP_LIMIT);
+            let rpc = rpc_url.or(Some(default_rpc));
+
+            match scan_used_addresses_on(cmd_args[1], gap_limit, rpc, derivation, network, check_network).await {
+                Ok(used) if json => cli::print_json(&used),
+                Ok(used) => {
+                    for addr in &used.addresses {
+                        let chain = if addr.is_change { "change" } else { "receive" };
+                        println!("{:<8} {:>4}  {}", chain, addr.index, addr.address);
+                    }
+                    if used.addresses.is_empty() {
+                        println!("No used addresses within the first {} of either chain", gap_limit);
+                    }
+                    println!("Next receive index: {}", used.next_index);
+                    println!("Next change index: {}", used.next_change_index);
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
         "derive-address" => {
             if cmd_args.len() < 3 {
-                eprintln!("Usage: kaspa-graffiti-cli derive-address <seed> <index> [change]");
+                cli::print_usage_error(cmd);
                 return;
             }
             let seed = cmd_args[1];
             let index: u32 = cmd_args[2].parse().unwrap_or(0);
             let is_change = cmd_args.get(3).map(|s| *s == "change" || *s == "true").unwrap_or(false);
             
-            match derive_address_from_seed(seed, index, is_change).await {
+            match derive_address_from_seed_on(seed, index, is_change, None, derivation, network).await {
                 Ok(info) => {
-                    println!("{{");
-                    println!("  \"index\": {},", info.index);
-                    println!("  \"address\": \"{}\",", info.address);
-                    println!("  \"private_key\": \"{}\",", info.private_key);
-                    println!("  \"public_key\": \"{}\",", info.public_key);
-                    println!("  \"is_change\": {}", info.is_change);
-                    println!("}}");
+                    if json {
+                        cli::print_json(&info);
+                    } else {
+                        print_fields(&[
+                            ("Index", info.index.to_string()),
+                            ("Chain", if info.is_change { "change" } else { "receive" }.to_string()),
+                            ("Address", info.address.clone()),
+                            ("Public key", info.public_key.clone()),
+                            ("Private key", info.private_key.clone()),
+                        ]);
+                    }
+                    show_qr(&info.address, options.qr, qr_png, json);
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
+        "derive-path" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+
+            match derive_address_from_seed_on(cmd_args[1], 0, false, Some(cmd_args[2]), derivation, network).await {
+                Ok(info) if json => cli::print_json(&info),
+                Ok(info) => {
+                    print_fields(&[
+                        ("Path", cmd_args[2].to_string()),
+                        ("Address", info.address.clone()),
+                        ("Public key", info.public_key.clone()),
+                        ("Private key", info.private_key.clone()),
+                    ]);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         "derive-many" => {
             if cmd_args.len() < 3 {
-                eprintln!("Usage: kaspa-graffiti-cli derive-many <private_key> <count>");
+                cli::print_usage_error(cmd);
                 return;
             }
             let private_key = cmd_args[1];
             let count: u32 = cmd_args[2].parse().unwrap_or(1);
             
-            match derive_many_addresses(private_key, count, false).await {
+            match derive_many_addresses_on(private_key, count, false, derivation, network).await {
+                Ok(addresses) if json => cli::print_json(&addresses),
+                Ok(addresses) => {
+                    for addr in &addresses {
+                        println!("{:>4}  {}  {}", addr.index, addr.address, addr.private_key);
+                    }
+                }
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "account-xpub" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+
+            match account_xpub_on(cmd_args[1], derivation, network).await {
+                Ok(xpub) if json => cli::print_json(&serde_json::json!({ "xpub": xpub })),
+                Ok(xpub) => println!("{}", xpub),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+        "derive-xpub" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+            let count: u32 = cmd_args[2].parse().unwrap_or(1);
+
+            match derive_many_addresses_from_xpub(cmd_args[1], count).await {
+                Ok(addresses) if json => cli::print_json(&addresses),
                 Ok(addresses) => {
-                    println!("[");
-                    for (i, addr) in addresses.iter().enumerate() {
-                        println!("  {{");
-                        println!("    \"index\": {},", addr.index);
-                        println!("    \"address\": \"{}\",", addr.address);
-                        println!("    \"private_key\": \"{}\",", addr.private_key);
-                        println!("    \"public_key\": \"{}\",", addr.public_key);
-                        println!("    \"is_change\": {}", addr.is_change);
-                        println!("  }}{}", if i < addresses.len() - 1 { "," } else { "" });
+                    for addr in &addresses {
+                        println!("{:>4}  {}", addr.index, addr.address);
                     }
-                    println!("]");
                 }
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
+        "backup" => {
+            if cmd_args.len() < 3 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+
+            match export_backup(cmd_args[1], cmd_args[2], network).await {
+                Ok(backup) if json => {
+                    cli::print_json(&serde_json::json!({ "path": cmd_args[2], "checksum": backup.checksum }));
+                }
+                Ok(backup) => {
+                    println!("Backup written to {}", cmd_args[2]);
+                    println!("Checksum: {}", backup.checksum);
+                    println!("Verify it with: kaspa-graffiti-cli verify-backup {}", cmd_args[2]);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "verify-backup" => {
+            if cmd_args.len() < 2 {
+                cli::print_usage_error(cmd);
+                return;
+            }
+
+            match verify_backup(cmd_args[1]).await {
+                Ok(verification) if json => {
+                    cli::print_json(&verification);
+                    if !verification.is_valid {
+                        std::process::exit(1);
+                    }
+                }
+                Ok(verification) => {
+                    print_fields(&[
+                        ("Network", verification.network.clone()),
+                        ("Checksum", if verification.checksum_valid { "ok" } else { "mismatch" }.to_string()),
+                        ("Addresses checked", verification.addresses_checked.to_string()),
+                    ]);
+                    for mismatch in &verification.mismatches {
+                        println!("  index {}: expected {}, found {}", mismatch.index, mismatch.expected, mismatch.found);
+                    }
+                    if !verification.is_valid {
+                        eprintln!("\n✗ Backup does not verify");
+                        std::process::exit(1);
+                    }
+                    println!("\n✓ Backup verified");
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         _ => {
             print_usage();
         }
     }
 }
 
-fn print_usage() {
-    println!("Kaspa Graffiti CLI");
-    println!();
-    println!("Usage:");
-    println!("  kaspa-graffiti-cli generate                      Generate a new wallet");
-    println!("  kaspa-graffiti-cli load <key>                    Load wallet from private key");
-    println!("  kaspa-graffiti-cli balance <address> [--rpc <url>]  Get address balance");
-    println!("  kaspa-graffiti-cli utxos <address> [--rpc <url>]    Get address UTXOs");
-    println!("  kaspa-graffiti-cli transfer <key> <addr> <amt>  Transfer KAS (no message)");
-    println!("  kaspa-graffiti-cli graffiti <key> <msg> [mime] [fee] [--rpc <url>]  Send graffiti (with message)");
-    println!();
-    println!("HD Wallet Commands:");
-    println!("  kaspa-graffiti-cli hd-generate                   Generate a new HD wallet");
-    println!("  kaspa-graffiti-cli hd-load <seed>                Load HD wallet from seed");
-    println!("  kaspa-graffiti-cli derive-address <seed> <index> [change]  Derive address from seed");
-    println!("  kaspa-graffiti-cli derive-many <key> <count>     Derive multiple addresses");
-    println!();
-    println!("Options:");
-    println!("  --rpc <url>    RPC endpoint (default: {})", PUBLIC_TESTNET10_RPC);
+fn print_hd_wallet(info: &HDWalletInfo) {
+    let mut fields = Vec::new();
+    if let Some(mnemonic) = &info.mnemonic {
+        fields.push(("Mnemonic", mnemonic.clone()));
+    }
+    fields.push(("Seed", info.seed.clone()));
+    fields.push(("Address", info.address.clone()));
+    fields.push(("Network", info.network.clone()));
+    print_fields(&fields);
+}
+
+fn print_wallet(info: &WalletInfo) {
+    print_fields(&[
+        ("Address", info.address.clone()),
+        ("Public key", info.public_key.clone()),
+        ("Private key", info.private_key.clone()),
+        ("Network", info.network.clone()),
+    ]);
+}
+
+fn print_message(message: &GraffitiMessage) {
+    match &message.mimetype {
+        Some(mimetype) => println!("[{}] {}", mimetype, message.content),
+        None => println!("{}", message.content),
+    }
+}
+
+/// One entry of an address's graffiti, followed by a blank line.
+fn print_found(found: &AddressGraffiti) {
+    println!("{}", found.txid);
+    match &found.graffiti {
+        TxGraffiti::Message(decoded) => print_message(&decoded.message),
+        TxGraffiti::Encrypted => println!("[encrypted]"),
+    }
     println!();
-    println!("Examples:");
-    println!("  kaspa-graffiti-cli generate");
-    println!("  kaspa-graffiti-cli hd-generate");
-    println!("  kaspa-graffiti-cli derive-address <seed> 0");
-    println!("  kaspa-graffiti-cli derive-many <private_key> 5");
-    println!("  kaspa-graffiti-cli balance kaspatest:qq...");
-    println!("  kaspa-graffiti-cli transfer <key> <addr> 1.0");
-    println!("  kaspa-graffiti-cli graffiti <private_key> \"Hello Kaspa!\" text/plain 1000");
+}
+
+fn kas(sompi: u64) -> String {
+    format!("{:.8}", sompi as f64 / 100_000_000.0)
+}
+
+/// Print `address` as a QR code if `print`, and save it as a PNG at `png`.
+/// In JSON mode the code goes to stderr, keeping stdout parseable.
+#[cfg(feature = "qr")]
+fn show_qr(address: &str, print: bool, png: Option<&str>, json: bool) {
+    let code = match qr::encode(address) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    if print {
+        let text = qr::to_terminal(&code);
+        if json {
+            eprintln!("{}", text);
+        } else {
+            println!();
+            println!("{}", text);
+        }
+    }
+    if let Some(path) = png {
+        match qr::save_png(&code, std::path::Path::new(path)) {
+            Ok(()) if json => {}
+            Ok(()) => println!("QR code saved to {}", path),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+#[cfg(not(feature = "qr"))]
+fn show_qr(_address: &str, print: bool, png: Option<&str>, _json: bool) {
+    if print || png.is_some() {
+        eprintln!("Error: QR output needs a build with the `qr` feature");
+    }
+}
+
+/// The signed transaction from `--dry-run`; nothing was sent.
+fn print_dry_run(dry_run: &DryRun, json: bool) {
+    if json {
+        cli::print_json(dry_run);
+        return;
+    }
+    println!("\nDry run: the transaction was signed but not sent");
+    print_fields(&[
+        ("Txid", dry_run.txid.clone()),
+        ("Fee", format!("{} sompi", dry_run.fee)),
+        ("Change", format!("{} KAS", kas(dry_run.change))),
+        ("Address", dry_run.address.clone()),
+        ("Hex", dry_run.tx_hex.clone()),
+    ]);
+    match serde_json::to_string_pretty(&dry_run.transaction) {
+        Ok(text) => println!("Transaction:\n{}", text),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Print `label: value` lines with the values lined up.
+fn print_fields(fields: &[(&str, String)]) {
+    let width = fields.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + 1;
+    for (label, value) in fields {
+        println!("{:<width$} {}", format!("{}:", label), value, width = width);
+    }
 }
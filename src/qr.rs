@@ -0,0 +1,245 @@
+//! QR codes for addresses, printed to the terminal or saved as a PNG.
+
+use std::path::Path;
+
+use image::{ImageFormat, Luma};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use thiserror::Error;
+
+/// Smallest side of a saved PNG, in pixels.
+const PNG_SIZE: u32 = 256;
+
+#[derive(Error, Debug)]
+pub enum QrError {
+    #[error("Cannot encode {0} characters as a QR code")]
+    TooLong(usize),
+    #[error("Cannot write QR code to {path}: {source}")]
+    Png { path: String, source: image::ImageError },
+}
+
+pub fn encode(address: &str) -> Result<QrCode, QrError> {
+    QrCode::new(address.as_bytes()).map_err(|_| QrError::TooLong(address.len()))
+}
+
+/// Two modules per character cell. The colours are swapped so the code
+/// reads as dark-on-light on a terminal with a dark background.
+pub fn to_terminal(code: &QrCode) -> String {
+    code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build()
+}
+
+pub fn save_png(code: &QrCode, path: &Path) -> Result<(), QrError> {
+    code.render::<Luma<u8>>()
+        .min_dimensions(PNG_SIZE, PNG_SIZE)
+        .build()
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(|source| QrError::Png { path: path.display().to_string(), source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::bits::Bits;
+    use qrcode::ec::construct_codewords;
+    use qrcode::Version;
+
+    /// Modules of the quiet zone around each side of a rendered code.
+    const QUIET_ZONE: usize = 4;
+
+    const ADDRESS: &str = "kaspatest:qr0lr4ml9fn3chekrqmjdkergxl93l4wrk3dankcgvjq776s9wn9jhtkdksae";
+
+    /// Reads the byte string out of a module grid (`true` is dark). Just
+    /// enough of ISO 18004 for what `QrCode::new` produces, and no error
+    /// correction, so any wrong module fails the test.
+    fn decode(code: &QrCode, grid: &[Vec<bool>]) -> Vec<u8> {
+        let Version::Normal(version) = code.version() else { panic!("micro QR") };
+        let width = grid.len();
+
+        // Format bits next to the top-left finder pattern give the mask
+        let positions = (0..6)
+            .map(|i| (8, i))
+            .chain([(8, 7), (8, 8), (7, 8)])
+            .chain((9..15).map(|i| (14 - i, 8)));
+        let format = positions
+            .enumerate()
+            .fold(0u16, |bits, (i, (x, y))| bits | (u16::from(grid[y][x]) << i))
+            ^ 0x5412;
+        let mask = (format >> 10) & 7;
+        let masked = |x: usize, y: usize| match mask {
+            0 => (x + y) % 2 == 0,
+            1 => y % 2 == 0,
+            2 => x % 3 == 0,
+            3 => (x + y) % 3 == 0,
+            4 => (x / 3 + y / 2) % 2 == 0,
+            5 => x * y % 2 + x * y % 3 == 0,
+            6 => (x * y % 2 + x * y % 3) % 2 == 0,
+            _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+        };
+
+        // `is_functional` leaves out the version blocks from version 7 up
+        let version_block = |x: usize, y: usize| {
+            let near_edge = |a: usize, b: usize| a < 6 && (width - 11..width - 8).contains(&b);
+            version >= 7 && (near_edge(x, y) || near_edge(y, x))
+        };
+
+        // Codewords run in two-column strips from the bottom right,
+        // alternately upwards and downwards, skipping the timing column
+        let mut bits = Vec::new();
+        let mut right = width - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..width {
+                let upward = (right + 1) & 2 == 0;
+                let y = if upward { width - 1 - vert } else { vert };
+                for x in [right, right - 1] {
+                    if !code.is_functional(x, y) && !version_block(x, y) {
+                        bits.push(grid[y][x] ^ masked(x, y));
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+        let stream: Vec<u8> = bits
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | u8::from(bit)))
+            .collect();
+
+        // Undo the block interleaving by interleaving each position's index
+        let data_len = Bits::new(code.version()).max_len(code.error_correction_level()).unwrap() / 8;
+        let interleaved = |part: fn(usize) -> u8| {
+            let probe: Vec<u8> = (0..data_len).map(part).collect();
+            construct_codewords(&probe, code.version(), code.error_correction_level()).unwrap().0
+        };
+        let (low, high) = (interleaved(|i| i as u8), interleaved(|i| (i >> 8) as u8));
+        let mut data = vec![0u8; data_len];
+        for (k, (&lo, &hi)) in low.iter().zip(&high).enumerate() {
+            data[usize::from(hi) << 8 | usize::from(lo)] = stream[k];
+        }
+
+        let mut reader = data.iter().flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1));
+        let mut read = |n: usize| (0..n).fold(0usize, |acc, _| acc << 1 | usize::from(reader.next().unwrap()));
+        let size_class = match version {
+            1..=9 => 0,
+            10..=26 => 1,
+            _ => 2,
+        };
+        const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+        let mut out = Vec::new();
+        loop {
+            match read(4) {
+                0b0000 => return out,
+                0b0001 => {
+                    let mut count = read([10, 12, 14][size_class]);
+                    while count > 0 {
+                        let digits = count.min(3);
+                        let value = read([0, 4, 7, 10][digits]);
+                        out.extend(format!("{:0width$}", value, width = digits).bytes());
+                        count -= digits;
+                    }
+                }
+                0b0010 => {
+                    let mut count = read([9, 11, 13][size_class]);
+                    while count > 1 {
+                        let value = read(11);
+                        out.extend([ALPHANUMERIC[value / 45], ALPHANUMERIC[value % 45]]);
+                        count -= 2;
+                    }
+                    if count == 1 {
+                        out.push(ALPHANUMERIC[read(6)]);
+                    }
+                }
+                0b0100 => {
+                    let count = read([8, 16, 16][size_class]);
+                    out.extend((0..count).map(|_| read(8) as u8));
+                }
+                mode => panic!("unexpected mode {:04b}", mode),
+            }
+        }
+    }
+
+    fn modules(code: &QrCode) -> Vec<Vec<bool>> {
+        let colors = code.to_colors();
+        colors
+            .chunks(code.width())
+            .map(|row| row.iter().map(|&c| c == qrcode::Color::Dark).collect())
+            .collect()
+    }
+
+    /// The grid back out of `to_terminal`, where a filled half is light.
+    fn from_terminal(text: &str) -> Vec<Vec<bool>> {
+        let mut rows = Vec::new();
+        for line in text.lines() {
+            let (top, bottom): (Vec<bool>, Vec<bool>) = line
+                .chars()
+                .map(|c| match c {
+                    ' ' => (true, true),
+                    '\u{2584}' => (true, false),
+                    '\u{2580}' => (false, true),
+                    '\u{2588}' => (false, false),
+                    other => panic!("unexpected {:?}", other),
+                })
+                .unzip();
+            rows.push(top);
+            rows.push(bottom);
+        }
+        let width = rows[0].len() - 2 * QUIET_ZONE;
+        rows[QUIET_ZONE..QUIET_ZONE + width]
+            .iter()
+            .map(|row| row[QUIET_ZONE..QUIET_ZONE + width].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn test_terminal_qr_decodes_to_address() {
+        let code = encode(ADDRESS).unwrap();
+        let grid = from_terminal(&to_terminal(&code));
+        assert_eq!(grid, modules(&code));
+        assert_eq!(decode(&code, &grid), ADDRESS.as_bytes());
+    }
+
+    #[test]
+    fn test_long_addresses_encode() {
+        // Longer than any P2SH or ECDSA address, spanning several blocks
+        for len in [100, 400, 1500] {
+            let address = format!("kaspa:{}", "qpzry9x8gf2tvdw0s3jn54khce6mua7l".repeat(len / 32 + 1));
+            let code = encode(&address).unwrap();
+            assert_eq!(decode(&code, &modules(&code)), address.as_bytes());
+        }
+        assert!(matches!(encode(&"q".repeat(8000)), Err(QrError::TooLong(8000))));
+    }
+
+    #[test]
+    fn test_png_decodes_to_address() {
+        let path = std::env::temp_dir().join(format!("kaspa-graffiti-qr-{}.png", std::process::id()));
+        let code = encode(ADDRESS).unwrap();
+        save_png(&code, &path).unwrap();
+
+        let image = image::open(&path).unwrap().into_luma8();
+        std::fs::remove_file(&path).unwrap();
+        assert!(image.width() >= PNG_SIZE);
+
+        // Sample the centre of each module
+        let width = code.width();
+        let module = image.width() as usize / (width + 2 * QUIET_ZONE);
+        let grid: Vec<Vec<bool>> = (0..width)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let px = (x + QUIET_ZONE) * module + module / 2;
+                        let py = (y + QUIET_ZONE) * module + module / 2;
+                        image.get_pixel(px as u32, py as u32).0[0] < 128
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(decode(&code, &grid), ADDRESS.as_bytes());
+    }
+}
@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 use std::time::Duration;
 use thiserror::Error;
 
+use crate::wallet::{JsonTransaction, Network};
+
 const DEFAULT_RPC_URL: &str = "127.0.0.1:16210";
 pub const PUBLIC_TESTNET10_GRPC: &str = "https://api-tn10.kaspa.org:16110";
 pub const PUBLIC_TESTNET10_RPC: &str = "https://api-tn10.kaspa.org";
+pub const PUBLIC_TESTNET11_RPC: &str = "https://api-tn11.kaspa.org";
+pub const PUBLIC_MAINNET_RPC: &str = "https://api.kaspa.org";
 
 #[derive(Error, Debug)]
 pub enum RpcError {
@@ -16,39 +22,293 @@ pub enum RpcError {
     JsonError(String),
     #[error("Invalid response")]
     InvalidResponse,
+    #[error("Network mismatch: expected {expected}, node is on {actual}")]
+    NetworkMismatch { expected: String, actual: String },
+    #[error(transparent)]
+    Rejected(#[from] SubmitError),
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+}
+
+/// Why the node refused a submitted transaction.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubmitError {
+    #[error("Transaction rejected: spends outputs unknown to the node (orphan)")]
+    Orphan,
+    #[error("Transaction rejected: already accepted")]
+    AlreadyAccepted,
+    #[error("Transaction rejected: fee too low")]
+    FeeTooLow,
+    #[error("Transaction rejected: signature script failed verification")]
+    RejectedScript,
+    #[error("Transaction rejected: {0}")]
+    Other(String),
+}
+
+impl SubmitError {
+    /// Classify the body of a failed submit, either the REST server's JSON
+    /// (`{"error": ...}` or `{"detail": ...}`) or plain text.
+    pub fn from_response(body: &str) -> Self {
+        let message = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| {
+                ["error", "detail"]
+                    .iter()
+                    .find_map(|k| v.get(*k).and_then(|m| m.as_str()).map(str::to_string))
+            })
+            .unwrap_or_else(|| body.to_string());
+
+        let lower = message.to_lowercase();
+        if lower.contains("orphan") {
+            SubmitError::Orphan
+        } else if lower.contains("already accepted") || lower.contains("already in the mempool") {
+            SubmitError::AlreadyAccepted
+        } else if lower.contains("under the required amount")
+            || lower.contains("minimum relay fee")
+            || lower.contains("insufficient fee")
+        {
+            SubmitError::FeeTooLow
+        } else if lower.contains("signature script") || lower.contains("invalid signature") {
+            SubmitError::RejectedScript
+        } else {
+            SubmitError::Other(message)
+        }
+    }
+}
+
+/// Single-address requests `RpcClient::get_balances_by_addresses` and
+/// `RpcClient::get_active_addresses` keep in flight when the node has no
+/// batch endpoint for them.
+const BALANCE_CONCURRENCY: usize = 8;
+
+/// Delay between polls in `RpcClient::wait_for_acceptance`, about one block.
+const ACCEPTANCE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Entries per page from `RpcClient::utxos_stream`.
+pub const UTXO_PAGE_SIZE: u32 = 500;
+
+/// How `RpcClient` retries failed requests.
+///
+/// Reads are retried up to `max_retries` times on connection errors, 5xx and
+/// 429, waiting `backoff` and doubling it each time (or the server's
+/// `Retry-After` on 429, capped at `MAX_RETRY_AFTER`). With `jitter`, each wait is scaled by a random
+/// factor between 0.5 and 1.5 so clients that failed together don't retry
+/// together.
+///
+/// Transaction submits are only retried with `retry_submits`, since a
+/// timed-out submit may still have been broadcast; the retry then fails
+/// with `SubmitError::AlreadyAccepted`.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub backoff: Duration,
+    pub jitter: bool,
+    pub retry_submits: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+            jitter: true,
+            retry_submits: false,
+        }
+    }
+}
+
+/// Request timeout and retry policy for `RpcClient`.
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    pub timeout: Duration,
+    pub retry: RetryConfig,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+/// Settings for an `RpcClient` beyond the URL and retry policy, for callers
+/// such as a GUI that want a short timeout or need to go through a proxy.
+#[derive(Debug, Clone, Default)]
+pub struct RpcClientBuilder {
+    url: Option<String>,
+    config: RpcConfig,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+}
+
+impl RpcClientBuilder {
+    /// Defaults to `PUBLIC_TESTNET10_RPC`.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    /// Limit on a whole request, from connecting to reading the body.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Send every request through the proxy at `url`.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_string());
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryConfig) -> Self {
+        self.config.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> Result<RpcClient, RpcError> {
+        let mut client = reqwest::Client::builder().timeout(self.config.timeout);
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            client = client.user_agent(user_agent);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| RpcError::Config(format!("invalid proxy {}: {}", proxy, e)))?;
+            client = client.proxy(proxy);
+        }
+
+        Ok(RpcClient {
+            url: self.url.as_deref().unwrap_or(PUBLIC_TESTNET10_RPC).trim_end_matches('/').to_string(),
+            client: client.build().map_err(|e| RpcError::Config(e.to_string()))?,
+            config: self.config,
+            network_name: OnceLock::new(),
+        })
+    }
 }
 
+/// Longest `Retry-After` the client will honor; a server asking for more
+/// gets this instead of stalling the caller.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// The wait requested by a `Retry-After` header, capped at `MAX_RETRY_AFTER`.
+///
+/// Only the delay-seconds form is understood. The HTTP-date form returns
+/// `None`, and the caller falls back to its own backoff.
+fn retry_after(value: &str) -> Option<Duration> {
+    let seconds: u64 = value.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// `delay` scaled by a random factor in [0.5, 1.5).
+fn jittered(delay: Duration) -> Duration {
+    delay.mul_f64(rand::random::<f64>() + 0.5)
+}
+
+#[derive(Clone)]
 pub struct RpcClient {
     url: String,
     client: reqwest::Client,
+    config: RpcConfig,
+    /// The node's network name, kept from the first response that carried
+    /// it. A node doesn't switch networks, so it's only asked once.
+    network_name: OnceLock<String>,
 }
 
 impl RpcClient {
-    pub fn new(rpc_url: Option<&str>) -> Self {
-        let url = rpc_url.unwrap_or(PUBLIC_TESTNET10_RPC).trim_end_matches('/').to_string();
-        Self {
-            url,
-            client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .expect("Failed to build HTTP client"),
+    pub fn new(rpc_url: Option<&str>) -> Result<Self, RpcError> {
+        Self::with_config(rpc_url, RpcConfig::default())
+    }
+
+    /// Fails with `RpcError::Config` if the HTTP client can't be built, which
+    /// with the default settings only happens if TLS can't be initialised.
+    pub fn with_config(rpc_url: Option<&str>, config: RpcConfig) -> Result<Self, RpcError> {
+        let mut builder = Self::builder().timeout(config.timeout).retry(config.retry);
+        if let Some(url) = rpc_url {
+            builder = builder.url(url);
+        }
+        builder.build()
+    }
+
+    pub fn builder() -> RpcClientBuilder {
+        RpcClientBuilder::default()
+    }
+
+    pub fn with_retry_config(mut self, retry: RetryConfig) -> Self {
+        self.config.retry = retry;
+        self
+    }
+
+    /// Send a transaction submit, retrying only if `retry_submits` is set.
+    async fn send_submit(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, RpcError> {
+        if self.config.retry.retry_submits {
+            return self.send_with_retry(request).await;
         }
+        request.send().await.map_err(|e| RpcError::Connection(e.to_string()))
     }
 
-    fn build_client(&self) -> Result<&reqwest::Client, RpcError> {
-        Ok(&self.client)
+    /// Send an idempotent request, retrying transient failures per `self.config`.
+    async fn send_with_retry(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, RpcError> {
+        let retry = &self.config.retry;
+        let mut delay = retry.backoff;
+        let mut attempt = 0;
+        loop {
+            let result = request
+                .try_clone()
+                .ok_or_else(|| RpcError::Rpc("Request cannot be retried".to_string()))?
+                .send()
+                .await;
+
+            let backoff = match retry.jitter {
+                true => jittered(delay),
+                false => delay,
+            };
+            let wait = match &result {
+                Err(_) => Some(backoff),
+                Ok(response) if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => Some(
+                    response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(retry_after)
+                        .unwrap_or(backoff),
+                ),
+                Ok(response) if response.status().is_server_error() => Some(backoff),
+                Ok(_) => None,
+            };
+            let wait = match wait {
+                Some(wait) if attempt < retry.max_retries => wait,
+                _ => return result.map_err(|e| RpcError::Connection(e.to_string())),
+            };
+
+            match &result {
+                Err(e) => log::debug!("RPC request failed ({}), retrying in {:?}", e, wait),
+                Ok(response) => log::debug!("RPC returned {}, retrying in {:?}", response.status(), wait),
+            }
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+            delay *= 2;
+        }
     }
 
     pub async fn get_balance_by_address(&self, address: &str) -> Result<GetBalanceByAddressResponse, RpcError> {
-        let client = self.build_client()?;
-        
         let url = format!("{}/addresses/{}/balance", self.url, address);
 
-        let response = client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+        let response = self.send_with_retry(self.client.get(&url)).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -66,16 +326,74 @@ impl RpcClient {
         })
     }
 
-    pub async fn get_utxos_by_address(&self, address: &str) -> Result<GetUtxosByAddressResponse, RpcError> {
-        let client = self.build_client()?;
+    /// Balances of `addresses` in one request to `/addresses/balances`, in
+    /// the order given. Against a server without that endpoint, falls back
+    /// to `get_balance_by_address` calls, at most `BALANCE_CONCURRENCY` at
+    /// a time.
+    pub async fn get_balances_by_addresses(&self, addresses: Vec<String>) -> Result<Vec<(String, u64)>, RpcError> {
+        if addresses.is_empty() {
+            return Ok(Vec::new());
+        }
+        let url = format!("{}/addresses/balances", self.url);
+        let body = serde_json::json!({ "addresses": addresses });
 
-        let url = format!("{}/addresses/{}/utxos", self.url, address);
+        // A read despite the POST, so safe to retry
+        let response = self.send_with_retry(self.client.post(&url).json(&body)).await?;
 
-        let response = client
-            .get(&url)
-            .send()
+        let status = response.status();
+        if matches!(status.as_u16(), 404 | 405 | 501) {
+            log::debug!("No batch balance endpoint (HTTP {}), querying addresses one by one", status);
+            return self.get_balances_one_by_one(addresses).await;
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let balances: Vec<RestBalanceResponse> = response
+            .json()
             .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+            .map_err(|e| RpcError::JsonError(e.to_string()))?;
+        let by_address: HashMap<String, u64> = balances.into_iter().map(|b| (b.address, b.balance)).collect();
+
+        addresses
+            .into_iter()
+            .map(|address| match by_address.get(&address) {
+                Some(&balance) => Ok((address, balance)),
+                None => Err(RpcError::JsonError(format!("No balance returned for {}", address))),
+            })
+            .collect()
+    }
+
+    async fn get_balances_one_by_one(&self, addresses: Vec<String>) -> Result<Vec<(String, u64)>, RpcError> {
+        let mut balances = vec![0; addresses.len()];
+        let mut pending = addresses.iter().cloned().enumerate();
+        let mut tasks = tokio::task::JoinSet::new();
+        loop {
+            while tasks.len() < BALANCE_CONCURRENCY {
+                let Some((i, address)) = pending.next() else { break };
+                let client = self.clone();
+                tasks.spawn(async move { (i, client.get_balance_by_address(&address).await) });
+            }
+            let Some(joined) = tasks.join_next().await else { break };
+            let (i, result) = joined.map_err(|e| RpcError::Connection(e.to_string()))?;
+            balances[i] = result?.balance;
+        }
+        Ok(addresses.into_iter().zip(balances).collect())
+    }
+
+    /// Every UTXO of `address`, fetched a page at a time.
+    pub async fn get_utxos_by_address(&self, address: &str) -> Result<GetUtxosByAddressResponse, RpcError> {
+        let entries = self.utxos_stream(address).collect_all().await?;
+        Ok(GetUtxosByAddressResponse { entries })
+    }
+
+    /// Up to `limit` of `address`'s UTXOs, skipping the first `offset`. A
+    /// server that doesn't paginate ignores both and returns them all.
+    pub async fn get_utxos_by_address_page(&self, address: &str, limit: u32, offset: u64) -> Result<GetUtxosByAddressResponse, RpcError> {
+        let url = format!("{}/addresses/{}/utxos?limit={}&offset={}", self.url, address, limit, offset);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -112,21 +430,48 @@ impl RpcClient {
         Ok(GetUtxosByAddressResponse { entries })
     }
 
-    pub async fn get_utxos_by_addresses(&self, addresses: Vec<String>) -> Result<GetUtxosByAddressesResponse, RpcError> {
-        let client = self.build_client()?;
+    /// Like `get_utxos_by_address`, but drops entries already marked spent.
+    pub async fn get_unspent_utxos_by_address(&self, address: &str) -> Result<GetUtxosByAddressResponse, RpcError> {
+        let mut response = self.get_utxos_by_address(address).await?;
+        response.entries.retain(|e| !e.is_spent);
+        Ok(response)
+    }
+
+    /// The address's UTXOs a page at a time, `UTXO_PAGE_SIZE` entries per
+    /// page. See `UtxoPages`.
+    pub fn utxos_stream(&self, address: &str) -> UtxoPages<'_> {
+        self.get_utxos_paged(address, UTXO_PAGE_SIZE)
+    }
 
+    /// The address's UTXOs `page_size` at a time, each page its own request.
+    pub fn get_utxos_paged(&self, address: &str, page_size: u32) -> UtxoPages<'_> {
+        UtxoPages {
+            client: self,
+            address: address.to_string(),
+            page_size: page_size.max(1),
+            offset: 0,
+            first: None,
+            unpaged: None,
+            done: false,
+        }
+    }
+
+    /// Like `get_utxos_by_addresses`, but drops entries already marked spent.
+    pub async fn get_unspent_utxos_by_addresses(&self, addresses: Vec<String>) -> Result<GetUtxosByAddressesResponse, RpcError> {
+        let mut response = self.get_utxos_by_addresses(addresses).await?;
+        response.entries.retain(|e| !e.is_spent);
+        Ok(response)
+    }
+
+    pub async fn get_utxos_by_addresses(&self, addresses: Vec<String>) -> Result<GetUtxosByAddressesResponse, RpcError> {
         let url = format!("{}/addresses/utxos", self.url);
 
         let body = serde_json::json!({
             "addresses": addresses
         });
 
-        let response = client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+        // A read despite the POST, so safe to retry
+        let response = self.send_with_retry(self.client.post(&url).json(&body)).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -163,26 +508,11 @@ impl RpcClient {
         Ok(GetUtxosByAddressesResponse { entries })
     }
 
-    pub async fn submit_transaction(
-        &self,
-        tx_json: &serde_json::Value,
-    ) -> Result<SubmitTransactionResponse, RpcError> {
-        let client = self.build_client()?;
-        
-        // Try sending transaction as raw JSON (not wrapped)
-        let url = format!("{}/transactions", self.url);
-        
-        let body = serde_json::json!({
-            "transaction": tx_json,
-            "allowOrphan": false
-        });
+    /// Fetch mempool transactions that involve `address`.
+    pub async fn get_mempool_entries_by_address(&self, address: &str) -> Result<GetMempoolEntriesResponse, RpcError> {
+        let url = format!("{}/addresses/{}/mempool", self.url, address);
 
-        let response = client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+        let response = self.send_with_retry(self.client.get(&url)).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -191,42 +521,113 @@ impl RpcClient {
         }
 
         let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
-        let submit_response: SubmitTransactionResult = serde_json::from_str(&text)
-            .map_err(|e| RpcError::JsonError(format!("Failed to parse submit response: {}", e)))?;
 
-        Ok(SubmitTransactionResponse {
-            transaction_id: submit_response.transaction_id,
+        let entries_wrapper: Vec<RestMempoolEntry> = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse mempool response: {}", e)))?;
+
+        let entries: Vec<GetMempoolEntry> = entries_wrapper.into_iter().map(|e| {
+            GetMempoolEntry {
+                transaction_id: e.transaction_id,
+                outputs: e.outputs.into_iter().map(|o| GetMempoolOutput {
+                    amount: o.amount,
+                    address: o.script_public_key_address,
+                }).collect(),
+            }
+        }).collect();
+
+        Ok(GetMempoolEntriesResponse { entries })
+    }
+
+    /// Count the transactions in `address`'s history without fetching them.
+    pub async fn get_transaction_count(&self, address: &str) -> Result<GetTransactionCountResponse, RpcError> {
+        let url = format!("{}/addresses/{}/transactions-count", self.url, address);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let count: RestTransactionCount = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse transaction count response: {}", e)))?;
+
+        Ok(GetTransactionCountResponse {
+            total: count.total,
+            limit_exceeded: count.limit_exceeded,
         })
     }
 
-    pub async fn submit_transaction_hex(
-        &self,
-        tx_hex: &str,
-    ) -> Result<SubmitTransactionResponse, RpcError> {
-        let client = self.build_client()?;
-        
-        let url = format!("{}/transactions", self.url);
-        
-        // Decode hex to bytes
-        let tx_bytes = hex::decode(tx_hex)
-            .map_err(|e| RpcError::JsonError(format!("Invalid hex: {}", e)))?;
-        
-        // The Kaspa API expects the transaction as a JSON object, not hex
-        // We need to construct the JSON manually from borsh-serialized data
-        // For now, try sending as raw bytes with binary content type
-        
-        let body = serde_json::json!({
-            "transaction": tx_hex,
-            "allowOrphan": false
-        });
+    /// Which of `addresses` have any transaction in their history, spent or
+    /// not, in one request to `/addresses/active`. Against a server without
+    /// that endpoint, falls back to `get_transaction_count` calls, at most
+    /// `BALANCE_CONCURRENCY` at a time.
+    pub async fn get_active_addresses(&self, addresses: Vec<String>) -> Result<HashSet<String>, RpcError> {
+        if addresses.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let url = format!("{}/addresses/active", self.url);
+        let body = serde_json::json!({ "addresses": addresses });
+
+        // A read despite the POST, so safe to retry
+        let response = self.send_with_retry(self.client.post(&url).json(&body)).await?;
 
-        let response = client
-            .post(&url)
-            .json(&body)
-            .send()
+        let status = response.status();
+        if matches!(status.as_u16(), 404 | 405 | 501) {
+            log::debug!("No batch activity endpoint (HTTP {}), counting transactions one by one", status);
+            return self.get_active_one_by_one(addresses).await;
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let activity: Vec<RestAddressActivity> = response
+            .json()
             .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+            .map_err(|e| RpcError::JsonError(e.to_string()))?;
+        Ok(activity.into_iter().filter(|a| a.active).map(|a| a.address).collect())
+    }
+
+    async fn get_active_one_by_one(&self, addresses: Vec<String>) -> Result<HashSet<String>, RpcError> {
+        let mut active = HashSet::new();
+        let mut pending = addresses.into_iter();
+        let mut tasks = tokio::task::JoinSet::new();
+        loop {
+            while tasks.len() < BALANCE_CONCURRENCY {
+                let Some(address) = pending.next() else { break };
+                let client = self.clone();
+                tasks.spawn(async move {
+                    let count = client.get_transaction_count(&address).await;
+                    (address, count)
+                });
+            }
+            let Some(joined) = tasks.join_next().await else { break };
+            let (address, count) = joined.map_err(|e| RpcError::Connection(e.to_string()))?;
+            if count?.total > 0 {
+                active.insert(address);
+            }
+        }
+        Ok(active)
+    }
+
+    pub async fn get_transaction(&self, txid: &str) -> Result<GetTransactionResponse, RpcError> {
+        self.find_transaction(txid).await?
+            .ok_or_else(|| RpcError::Rpc(format!("HTTP 404 Not Found: transaction {} not found", txid)))
+    }
 
+    /// Like `get_transaction`, but `None` if the node doesn't know `txid` yet.
+    async fn find_transaction(&self, txid: &str) -> Result<Option<GetTransactionResponse>, RpcError> {
+        let url = format!("{}/transactions/{}", self.url, txid);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
@@ -234,38 +635,265 @@ impl RpcClient {
         }
 
         let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
-        let submit_response: SubmitTransactionResult = serde_json::from_str(&text)
-            .map_err(|e| RpcError::JsonError(format!("Failed to parse submit response: {}", e)))?;
 
-        Ok(SubmitTransactionResponse {
-            transaction_id: submit_response.transaction_id,
+        let tx: RestTransaction = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse transaction response: {}", e)))?;
+
+        Ok(Some(GetTransactionResponse {
+            output_scripts: output_scripts(&tx),
+            transaction_id: tx.transaction_id,
+            payload: tx.payload.unwrap_or_default(),
+            block_hash: tx.block_hash.unwrap_or_default(),
+            block_time: tx.block_time,
+            is_accepted: tx.is_accepted.unwrap_or(false),
+            accepting_block_blue_score: tx.accepting_block_blue_score,
+        }))
+    }
+
+    /// Poll until `txid` is accepted into the DAG. Returns `false` if it is
+    /// still pending (or unknown) after `timeout`.
+    ///
+    /// Dropping the returned future stops polling; nothing is left running.
+    pub async fn wait_for_acceptance(&self, txid: &str, timeout: Duration) -> Result<bool, RpcError> {
+        self.poll_acceptance(txid, timeout, ACCEPTANCE_POLL_INTERVAL).await
+    }
+
+    async fn poll_acceptance(&self, txid: &str, timeout: Duration, interval: Duration) -> Result<bool, RpcError> {
+        let poll = async {
+            loop {
+                if let Some(tx) = self.find_transaction(txid).await? {
+                    if tx.is_accepted {
+                        return Ok(true);
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        };
+
+        match tokio::time::timeout(timeout, poll).await {
+            Ok(result) => result,
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Transactions that pay to or spend from `address`, newest first, as
+    /// returned by the REST server's default page.
+    pub async fn get_transactions_by_address(&self, address: &str) -> Result<Vec<ChainTx>, RpcError> {
+        let url = format!("{}/addresses/{}/full-transactions", self.url, address);
+        self.fetch_chain_txs(&url).await
+    }
+
+    /// Up to `limit` of `address`'s transactions, newest first, skipping the
+    /// `offset` newest.
+    pub async fn get_transactions_page(&self, address: &str, limit: u32, offset: u64) -> Result<Vec<ChainTx>, RpcError> {
+        let url = format!("{}/addresses/{}/full-transactions?limit={}&offset={}", self.url, address, limit, offset);
+        self.fetch_chain_txs(&url).await
+    }
+
+    async fn fetch_chain_txs(&self, url: &str) -> Result<Vec<ChainTx>, RpcError> {
+        let response = self.send_with_retry(self.client.get(url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let txs: Vec<RestTransaction> = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse transactions response: {}", e)))?;
+
+        Ok(txs.into_iter().map(|tx| ChainTx {
+            output_scripts: output_scripts(&tx),
+            transaction_id: tx.transaction_id,
+            payload: tx.payload.unwrap_or_default(),
+            block_time: tx.block_time,
+            is_accepted: tx.is_accepted.unwrap_or(false),
+        }).collect())
+    }
+
+    /// Fetch the name of the network the node is on, e.g. `kaspa-testnet-10`.
+    pub async fn get_network_name(&self) -> Result<String, RpcError> {
+        if let Some(name) = self.network_name.get() {
+            return Ok(name.clone());
+        }
+        let url = format!("{}/info/network", self.url);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let info: RestNetworkInfo = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse network response: {}", e)))?;
+
+        Ok(self.network_name.get_or_init(|| info.network_name).clone())
+    }
+
+    /// Error with `NetworkMismatch` unless the node is on `network`.
+    pub async fn require_network(&self, network: Network) -> Result<(), RpcError> {
+        let name = self.get_network_name().await?;
+        let actual = network_from_node_name(&name);
+
+        if actual != Some(network) {
+            return Err(RpcError::NetworkMismatch {
+                expected: network.name().to_string(),
+                actual: name,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the node's view of the DAG: its network, tips and virtual DAA score.
+    pub async fn get_block_dag_info(&self) -> Result<BlockDagInfo, RpcError> {
+        let url = format!("{}/info/blockdag", self.url);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let info: BlockDagInfo = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse blockdag response: {}", e)))?;
+        if !info.network_name.is_empty() {
+            let _ = self.network_name.set(info.network_name.clone());
+        }
+        Ok(info)
+    }
+
+    /// Fetch the virtual DAA score of the node's current DAG tip.
+    pub async fn get_virtual_daa_score(&self) -> Result<u64, RpcError> {
+        Ok(self.get_block_dag_info().await?.virtual_daa_score)
+    }
+
+    /// Fetch the blue score of the virtual's selected parent, i.e. the tip of
+    /// the selected chain.
+    pub async fn get_virtual_selected_parent_blue_score(&self) -> Result<u64, RpcError> {
+        let url = format!("{}/info/virtual-chain-blue-score", self.url);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let score: RestBlueScore = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse blue score response: {}", e)))?;
+
+        Ok(score.blue_score)
+    }
+
+    pub async fn get_block(&self, hash: &str) -> Result<GetBlockResponse, RpcError> {
+        let url = format!("{}/blocks/{}?includeTransactions=false", self.url, hash);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let block: RestBlock = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse block response: {}", e)))?;
+
+        Ok(GetBlockResponse {
+            hash: block.header.hash.unwrap_or_else(|| hash.to_string()),
+            daa_score: block.header.daa_score,
+            blue_score: block.header.blue_score,
         })
     }
 
+    /// Fetch the node's fee-rate buckets (sompi/gram) and their expected
+    /// confirmation times.
+    pub async fn get_fee_estimate(&self) -> Result<GetFeeEstimateResponse, RpcError> {
+        let url = format!("{}/info/fee-estimate", self.url);
+
+        let response = self.send_with_retry(self.client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+        }
+
+        let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        let estimate: RestFeeEstimate = serde_json::from_str(&text)
+            .map_err(|e| RpcError::JsonError(format!("Failed to parse fee estimate response: {}", e)))?;
+
+        let to_bucket = |b: RestFeeBucket| GetFeeBucket {
+            feerate: b.feerate,
+            estimated_seconds: b.estimated_seconds,
+        };
+
+        Ok(GetFeeEstimateResponse {
+            priority_bucket: to_bucket(estimate.priority_bucket),
+            normal_buckets: estimate.normal_buckets.into_iter().map(to_bucket).collect(),
+            low_buckets: estimate.low_buckets.into_iter().map(to_bucket).collect(),
+        })
+    }
+
+    /// Submit a transaction. With `allow_orphan`, the node keeps it even if
+    /// it spends outputs of transactions it hasn't seen yet.
+    pub async fn submit_transaction(
+        &self,
+        tx_json: &serde_json::Value,
+        allow_orphan: bool,
+    ) -> Result<SubmitTransactionResponse, RpcError> {
+        self.submit_transaction_json(tx_json, allow_orphan).await
+    }
+
+    /// Submit a borsh-serialized transaction (`KaspaSignedTransaction::hex`).
+    ///
+    /// The REST API only accepts JSON transactions, so the bytes are decoded
+    /// and sent in the same envelope as `submit_transaction_json`.
+    pub async fn submit_transaction_hex(
+        &self,
+        tx_hex: &str,
+        allow_orphan: bool,
+    ) -> Result<SubmitTransactionResponse, RpcError> {
+        let json_tx = JsonTransaction::from_hex(tx_hex).map_err(RpcError::JsonError)?;
+        let tx = serde_json::to_value(&json_tx).map_err(|e| RpcError::JsonError(e.to_string()))?;
+
+        self.submit_transaction_json(&tx, allow_orphan).await
+    }
+
     pub async fn submit_transaction_json(
         &self,
         tx: &serde_json::Value,
+        allow_orphan: bool,
     ) -> Result<SubmitTransactionResponse, RpcError> {
-        let client = self.build_client()?;
-        
         let url = format!("{}/transactions", self.url);
         
         let body = serde_json::json!({
             "transaction": tx,
-            "allowOrphan": false
+            "allowOrphan": allow_orphan
         });
 
-        let response = client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+        let response = self.send_submit(self.client.post(&url).json(&body)).await?;
 
         if !response.status().is_success() {
-            let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            return Err(RpcError::Rpc(format!("HTTP {}: {}", status, text)));
+            return Err(SubmitError::from_response(&text).into());
         }
 
         let text = response.text().await.map_err(|e| RpcError::JsonError(e.to_string()))?;
@@ -278,6 +906,66 @@ impl RpcClient {
     }
 }
 
+/// Pages of an address's UTXOs, from `RpcClient::get_utxos_paged`.
+///
+/// Each page is requested with `limit` and `offset`. A server that ignores
+/// them answers the first request with every entry; the pages are then cut
+/// from that response instead.
+pub struct UtxoPages<'a> {
+    client: &'a RpcClient,
+    address: String,
+    page_size: u32,
+    offset: u64,
+    /// Outpoint of the first entry, to spot a server that ignores `offset`
+    first: Option<GetOutPoint>,
+    unpaged: Option<std::vec::IntoIter<GetUtxosByAddressEntry>>,
+    done: bool,
+}
+
+impl UtxoPages<'_> {
+    /// The next page, or `None` once every entry has been returned.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<GetUtxosByAddressEntry>>, RpcError> {
+        let page_size = self.page_size as usize;
+        if let Some(entries) = self.unpaged.as_mut() {
+            let page: Vec<GetUtxosByAddressEntry> = entries.by_ref().take(page_size).collect();
+            return Ok(if page.is_empty() { None } else { Some(page) });
+        }
+        if self.done {
+            return Ok(None);
+        }
+
+        let response = self.client.get_utxos_by_address_page(&self.address, self.page_size, self.offset).await?;
+        let mut entries = response.entries;
+        if entries.len() > page_size {
+            let page = entries.drain(..page_size).collect();
+            self.unpaged = Some(entries.into_iter());
+            return Ok(Some(page));
+        }
+        // Without `offset` every request would return the first page again
+        let first = entries.first().map(|e| &e.outpoint);
+        if self.offset > 0 && first.is_some() && first == self.first.as_ref() {
+            self.done = true;
+            return Ok(None);
+        }
+        if self.offset == 0 {
+            self.first = first.cloned();
+        }
+
+        self.done = entries.len() < page_size;
+        self.offset += entries.len() as u64;
+        Ok(if entries.is_empty() { None } else { Some(entries) })
+    }
+
+    /// Every remaining page, in order, as one list.
+    pub async fn collect_all(mut self) -> Result<Vec<GetUtxosByAddressEntry>, RpcError> {
+        let mut entries = Vec::new();
+        while let Some(page) = self.next_page().await? {
+            entries.extend(page);
+        }
+        Ok(entries)
+    }
+}
+
 // REST API response structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct RestBalanceResponse {
@@ -321,6 +1009,121 @@ struct RestScriptPublicKeyData {
     pub script: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestMempoolEntry {
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    pub outputs: Vec<RestMempoolOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestMempoolOutput {
+    #[serde(deserialize_with = "deserialize_string_or_u64")]
+    pub amount: u64,
+    #[serde(rename = "scriptPublicKeyAddress")]
+    pub script_public_key_address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestTransactionCount {
+    #[serde(deserialize_with = "deserialize_string_or_u64")]
+    pub total: u64,
+    #[serde(default, alias = "limitExceeded")]
+    pub limit_exceeded: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestAddressActivity {
+    pub address: String,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestTransaction {
+    pub transaction_id: String,
+    pub payload: Option<String>,
+    pub block_hash: Option<Vec<String>>,
+    pub block_time: Option<u64>,
+    pub is_accepted: Option<bool>,
+    pub accepting_block_blue_score: Option<u64>,
+    pub outputs: Option<Vec<RestTransactionOutput>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestTransactionOutput {
+    pub script_public_key: String,
+}
+
+fn output_scripts(tx: &RestTransaction) -> Vec<String> {
+    tx.outputs.iter().flatten().map(|output| output.script_public_key.clone()).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestBlueScore {
+    #[serde(rename = "blueScore", deserialize_with = "deserialize_string_or_u64")]
+    pub blue_score: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestBlock {
+    pub header: RestBlockHeader,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestBlockHeader {
+    pub hash: Option<String>,
+    #[serde(rename = "daaScore", deserialize_with = "deserialize_string_or_u64")]
+    pub daa_score: u64,
+    #[serde(rename = "blueScore", deserialize_with = "deserialize_string_or_u64")]
+    pub blue_score: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestNetworkInfo {
+    #[serde(rename = "networkName")]
+    pub network_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockDagInfo {
+    /// E.g. `kaspa-testnet-10`. Empty if the node leaves it out.
+    #[serde(rename = "networkName", default)]
+    pub network_name: String,
+    #[serde(rename = "virtualDaaScore", deserialize_with = "deserialize_string_or_u64")]
+    pub virtual_daa_score: u64,
+    #[serde(rename = "tipHashes", default)]
+    pub tip_hashes: Vec<String>,
+}
+
+impl BlockDagInfo {
+    /// The node's network, if it reported a known one.
+    pub fn network(&self) -> Option<Network> {
+        network_from_node_name(&self.network_name)
+    }
+}
+
+/// `kaspa-testnet-10` and the like, as a `Network`.
+fn network_from_node_name(name: &str) -> Option<Network> {
+    Network::from_name(name.strip_prefix("kaspa-").unwrap_or(name)).ok()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestFeeEstimate {
+    #[serde(rename = "priorityBucket")]
+    pub priority_bucket: RestFeeBucket,
+    #[serde(rename = "normalBuckets", default)]
+    pub normal_buckets: Vec<RestFeeBucket>,
+    #[serde(rename = "lowBuckets", default)]
+    pub low_buckets: Vec<RestFeeBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RestFeeBucket {
+    pub feerate: f64,
+    #[serde(rename = "estimatedSeconds")]
+    pub estimated_seconds: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetBalanceByAddressResponse {
     pub balance: u64,
@@ -336,7 +1139,7 @@ pub struct GetUtxosByAddressesResponse {
     pub entries: Vec<GetUtxosByAddressesEntry>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GetOutPoint {
     pub transaction_id: String,
     pub index: u32,
@@ -372,6 +1175,76 @@ pub struct GetUtxosByAddressesEntry {
     pub is_spent: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTransactionCountResponse {
+    pub total: u64,
+    /// The node stopped counting; `total` is a lower bound.
+    pub limit_exceeded: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetMempoolEntriesResponse {
+    pub entries: Vec<GetMempoolEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetMempoolEntry {
+    pub transaction_id: String,
+    pub outputs: Vec<GetMempoolOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetMempoolOutput {
+    pub amount: u64,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTransactionResponse {
+    pub transaction_id: String,
+    /// Hex-encoded payload, empty if the transaction has none.
+    pub payload: String,
+    /// Hex-encoded script public key of each output, in order.
+    pub output_scripts: Vec<String>,
+    pub block_hash: Vec<String>,
+    pub block_time: Option<u64>,
+    pub is_accepted: bool,
+    pub accepting_block_blue_score: Option<u64>,
+}
+
+/// A transaction touching an address, from `get_transactions_by_address`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainTx {
+    pub transaction_id: String,
+    /// Hex-encoded payload, empty if the transaction has none.
+    pub payload: String,
+    /// Hex-encoded script public key of each output, in order.
+    pub output_scripts: Vec<String>,
+    /// Milliseconds since the epoch, once in a block.
+    pub block_time: Option<u64>,
+    pub is_accepted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBlockResponse {
+    pub hash: String,
+    pub daa_score: u64,
+    pub blue_score: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFeeEstimateResponse {
+    pub priority_bucket: GetFeeBucket,
+    pub normal_buckets: Vec<GetFeeBucket>,
+    pub low_buckets: Vec<GetFeeBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFeeBucket {
+    pub feerate: f64,
+    pub estimated_seconds: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SubmitTransactionResult {
     #[serde(rename = "transactionId")]
@@ -406,3 +1279,560 @@ where
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::mock::{MockResponse, MockRpc};
+
+    fn rest_utxo(txid_byte: u8, amount: u64, is_spent: Option<bool>) -> serde_json::Value {
+        let mut entry = serde_json::json!({
+            "address": "kaspatest:qq",
+            "outpoint": { "transactionId": hex::encode([txid_byte; 32]), "index": 0 },
+            "utxoEntry": {
+                "amount": amount.to_string(),
+                "scriptPublicKey": { "scriptPublicKey": "20" },
+                "blockDaaScore": "100",
+                "isCoinbase": false
+            }
+        });
+        if let Some(spent) = is_spent {
+            entry["isSpent"] = serde_json::json!(spent);
+        }
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_unspent_utxos_filter() {
+        let mock = MockRpc::start().await;
+        let utxos = serde_json::json!([
+            rest_utxo(0x01, 1_000, Some(false)),
+            rest_utxo(0x02, 2_000, Some(true)),
+            rest_utxo(0x03, 3_000, None),
+        ]);
+        mock.on("GET", "/addresses/kaspatest:qq/utxos", 200, &utxos.to_string());
+        mock.on("POST", "/addresses/utxos", 200, &utxos.to_string());
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+
+        let all = client.get_utxos_by_address("kaspatest:qq").await.unwrap();
+        assert_eq!(all.entries.len(), 3);
+
+        let unspent = client.get_unspent_utxos_by_address("kaspatest:qq").await.unwrap();
+        let amounts: Vec<u64> = unspent.entries.iter().map(|e| e.utxo_entry.amount).collect();
+        assert_eq!(amounts, vec![1_000, 3_000]);
+
+        let unspent = client
+            .get_unspent_utxos_by_addresses(vec!["kaspatest:qq".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(unspent.entries.len(), 2);
+        assert!(unspent.entries.iter().all(|e| !e.is_spent));
+    }
+
+    #[tokio::test]
+    async fn test_utxos_stream_matches_bulk() {
+        let mock = MockRpc::start().await;
+        let utxos: Vec<serde_json::Value> = (1..=7u8).map(|i| rest_utxo(i, i as u64 * 1_000, None)).collect();
+        mock.on("GET", "/addresses/kaspatest:qq/utxos", 200, &serde_json::Value::from(utxos).to_string());
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let bulk = client.get_utxos_by_address("kaspatest:qq").await.unwrap().entries;
+        let outpoint = |e: &GetUtxosByAddressEntry| (e.outpoint.transaction_id.clone(), e.outpoint.index);
+
+        let mut stream = client.get_utxos_paged("kaspatest:qq", 3);
+        let mut sizes = Vec::new();
+        let mut streamed = Vec::new();
+        while let Some(page) = stream.next_page().await.unwrap() {
+            sizes.push(page.len());
+            streamed.extend(page);
+        }
+        assert_eq!(sizes, vec![3, 3, 1]);
+        assert_eq!(streamed.iter().map(outpoint).collect::<Vec<_>>(), bulk.iter().map(outpoint).collect::<Vec<_>>());
+        assert!(stream.next_page().await.unwrap().is_none());
+
+        let all = client.utxos_stream("kaspatest:qq").collect_all().await.unwrap();
+        assert_eq!(all.iter().map(outpoint).collect::<Vec<_>>(), bulk.iter().map(outpoint).collect::<Vec<_>>());
+        // The mock ignores limit and offset, so each stream is one request
+        assert_eq!(mock.requests_to("GET", "/addresses/kaspatest:qq/utxos").len(), 3);
+
+        // An address with no UTXOs has no pages
+        mock.on("GET", "/addresses/kaspatest:qe/utxos", 200, "[]");
+        assert!(client.utxos_stream("kaspatest:qe").next_page().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_utxos_paged_offsets() {
+        let mock = MockRpc::start().await;
+        let page = |ids: std::ops::RangeInclusive<u8>| {
+            let entries: Vec<serde_json::Value> = ids.map(|i| rest_utxo(i, i as u64 * 1_000, None)).collect();
+            MockResponse::new(200, &serde_json::Value::from(entries).to_string())
+        };
+        mock.on_sequence("GET", "/addresses/kaspatest:qq/utxos", vec![page(1..=3), page(4..=6), page(7..=7)]);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let mut stream = client.get_utxos_paged("kaspatest:qq", 3);
+        let mut amounts = Vec::new();
+        while let Some(page) = stream.next_page().await.unwrap() {
+            amounts.extend(page.iter().map(|e| e.utxo_entry.amount));
+        }
+        assert_eq!(amounts, (1..=7).map(|i| i * 1_000).collect::<Vec<u64>>());
+
+        let paths: Vec<String> = mock.requests_to("GET", "/addresses/kaspatest:qq/utxos").into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/addresses/kaspatest:qq/utxos?limit=3&offset=0",
+                "/addresses/kaspatest:qq/utxos?limit=3&offset=3",
+                "/addresses/kaspatest:qq/utxos?limit=3&offset=6",
+            ]
+        );
+
+        // A server ignoring offset repeats a full first page; that ends the stream
+        mock.on("GET", "/addresses/kaspatest:qf/utxos", 200, &serde_json::json!([rest_utxo(1, 1_000, None), rest_utxo(2, 2_000, None)]).to_string());
+        let all = client.get_utxos_paged("kaspatest:qf", 2).collect_all().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(mock.requests_to("GET", "/addresses/kaspatest:qf/utxos").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_block_dag_info() {
+        let mock = MockRpc::start().await;
+        let body = r#"{
+            "networkName": "kaspa-testnet-10",
+            "blockCount": "1571231",
+            "headerCount": "1571231",
+            "tipHashes": [
+                "3f5d1b0c1a2e4e6b8c0d2f4a6b8c0e2f4a6c8e0a2c4e6a8c0e2a4c6e8a0c2e4a",
+                "9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f1a0b9c8d7e6f5a4b3c2d1e0f9a8b"
+            ],
+            "difficulty": 1.6453726e13,
+            "pastMedianTime": "1713791024021",
+            "virtualParentHashes": ["3f5d1b0c1a2e4e6b8c0d2f4a6b8c0e2f4a6c8e0a2c4e6a8c0e2a4c6e8a0c2e4a"],
+            "pruningPointHash": "0c2e4a6c8e0a2c4e6a8c0e2a4c6e8a0c2e4a6c8e0a2c4e6a8c0e2a4c6e8a0c2e",
+            "virtualDaaScore": "82510147"
+        }"#;
+        mock.on("GET", "/info/blockdag", 200, body);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let info = client.get_block_dag_info().await.unwrap();
+        assert_eq!(info.network_name, "kaspa-testnet-10");
+        assert_eq!(info.network(), Some(Network::Testnet10));
+        assert_eq!(info.virtual_daa_score, 82_510_147);
+        assert_eq!(info.tip_hashes.len(), 2);
+        assert!(info.tip_hashes[1].starts_with("9a8b"));
+        assert_eq!(client.get_virtual_daa_score().await.unwrap(), 82_510_147);
+
+        let info: BlockDagInfo = serde_json::from_str(r#"{"virtualDaaScore": 5000}"#).unwrap();
+        assert_eq!(info.network(), None);
+        assert!(info.tip_hashes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_balances() {
+        let mock = MockRpc::start().await;
+        // The server may answer in any order
+        let body = r#"[
+            {"address": "kaspatest:qb", "balance": "2500"},
+            {"address": "kaspatest:qa", "balance": 1000}
+        ]"#;
+        mock.on("POST", "/addresses/balances", 200, body);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let addresses = vec!["kaspatest:qa".to_string(), "kaspatest:qb".to_string()];
+        let balances = client.get_balances_by_addresses(addresses).await.unwrap();
+        assert_eq!(balances, vec![("kaspatest:qa".to_string(), 1_000), ("kaspatest:qb".to_string(), 2_500)]);
+
+        let requests = mock.requests_to("POST", "/addresses/balances");
+        assert_eq!(requests.len(), 1);
+        let sent: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(sent["addresses"], serde_json::json!(["kaspatest:qa", "kaspatest:qb"]));
+
+        let err = client.get_balances_by_addresses(vec!["kaspatest:qc".to_string()]).await.unwrap_err();
+        assert!(matches!(err, RpcError::JsonError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_batch_balances_fallback() {
+        let mock = MockRpc::start().await;
+        // No route for /addresses/balances, so the mock answers 404
+        let addresses: Vec<String> = (0..20).map(|i| format!("kaspatest:q{}", i)).collect();
+        for (i, address) in addresses.iter().enumerate() {
+            let body = serde_json::json!({ "address": address, "balance": (i as u64 * 100).to_string() });
+            mock.on("GET", &format!("/addresses/{}/balance", address), 200, &body.to_string());
+        }
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let balances = client.get_balances_by_addresses(addresses.clone()).await.unwrap();
+        let expected: Vec<(String, u64)> = addresses.iter().cloned().zip((0..20).map(|i| i * 100)).collect();
+        assert_eq!(balances, expected);
+        assert_eq!(mock.requests_to("POST", "/addresses/balances").len(), 1);
+        assert_eq!(mock.requests_to("GET", "/addresses/kaspatest:q7/balance").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_active_addresses() {
+        let mock = MockRpc::start().await;
+        let body = r#"[
+            {"address": "kaspatest:qa", "active": true},
+            {"address": "kaspatest:qb", "active": false}
+        ]"#;
+        mock.on("POST", "/addresses/active", 200, body);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let active = client.get_active_addresses(vec!["kaspatest:qa".to_string(), "kaspatest:qb".to_string()]).await.unwrap();
+        assert_eq!(active, HashSet::from(["kaspatest:qa".to_string()]));
+
+        // Without the batch endpoint, each address's history is counted
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/addresses/kaspatest:qa/transactions-count", 200, r#"{"total": 3}"#);
+        mock.on("GET", "/addresses/kaspatest:qb/transactions-count", 200, r#"{"total": 0}"#);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let active = client.get_active_addresses(vec!["kaspatest:qa".to_string(), "kaspatest:qb".to_string()]).await.unwrap();
+        assert_eq!(active, HashSet::from(["kaspatest:qa".to_string()]));
+        assert_eq!(mock.requests_to("POST", "/addresses/active").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_require_network() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, r#"{"networkName": "kaspa-mainnet", "virtualDaaScore": "1"}"#);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        assert!(client.require_network(Network::Mainnet).await.is_ok());
+
+        let err = client.require_network(Network::Testnet10).await.unwrap_err();
+        assert!(matches!(err, RpcError::NetworkMismatch { .. }));
+        assert_eq!(err.to_string(), "Network mismatch: expected testnet-10, node is on kaspa-mainnet");
+    }
+
+    #[tokio::test]
+    async fn test_network_name_is_fetched_once() {
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/info/network", 200, r#"{"networkName": "kaspa-mainnet"}"#);
+        mock.on("GET", "/info/blockdag", 200, r#"{"networkName": "kaspa-testnet-10", "virtualDaaScore": "1"}"#);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        assert_eq!(client.get_network_name().await.unwrap(), "kaspa-mainnet");
+        assert_eq!(client.get_network_name().await.unwrap(), "kaspa-mainnet");
+        assert_eq!(mock.requests_to("GET", "/info/network").len(), 1);
+
+        // The DAG info names the network too
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        client.get_block_dag_info().await.unwrap();
+        assert_eq!(client.get_network_name().await.unwrap(), "kaspa-testnet-10");
+        assert_eq!(mock.requests_to("GET", "/info/network").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fee_estimate_parse() {
+        let mock = MockRpc::start().await;
+        let body = r#"{
+            "priorityBucket": { "feerate": 1.0180487, "estimatedSeconds": 0.004 },
+            "normalBuckets": [
+                { "feerate": 1.0, "estimatedSeconds": 0.52 },
+                { "feerate": 1.0, "estimatedSeconds": 0.52 }
+            ],
+            "lowBuckets": [{ "feerate": 1.0, "estimatedSeconds": 3.14 }]
+        }"#;
+        mock.on("GET", "/info/fee-estimate", 200, body);
+
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        let estimate = client.get_fee_estimate().await.unwrap();
+        assert_eq!(estimate.priority_bucket.feerate, 1.0180487);
+        assert_eq!(estimate.priority_bucket.estimated_seconds, 0.004);
+        assert_eq!(estimate.normal_buckets.len(), 2);
+        assert_eq!(estimate.low_buckets[0].estimated_seconds, 3.14);
+    }
+
+    /// A one-input, one-output transaction with an "envelope" payload,
+    /// signed deterministically, and its P2PK script.
+    fn envelope_fixture() -> (crate::wallet::KaspaSignedTransaction, Vec<u8>) {
+        use crate::wallet::KaspaTransactionSigner;
+
+        let key = hex::decode("1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac").unwrap();
+        let keypair = crate::wallet::KeyPair::from_hex(&hex::encode(&key)).unwrap();
+        let xonly = &keypair.public_key_bytes()[1..];
+        let script: Vec<u8> = std::iter::once(0x20).chain(xonly.iter().copied()).chain(std::iter::once(0xac)).collect();
+        let address = crate::wallet::generate_address(keypair.public_key(), Network::Testnet10);
+
+        let mut signer = KaspaTransactionSigner::new();
+        signer.add_input(&"11".repeat(32), 1, 100_000, &script).unwrap();
+        signer.add_output(&address, 90_000).unwrap();
+        signer.set_payload(b"envelope");
+        (signer.sign(&key).unwrap(), script)
+    }
+
+    #[tokio::test]
+    async fn test_submit_envelope_shape() {
+        let (signed, script) = envelope_fixture();
+
+        // Response body as returned by api-tn10.kaspa.org
+        let recorded = format!(r#"{{"transactionId":"{}","error":null}}"#, signed.id());
+        let mock = MockRpc::start().await;
+        mock.on("POST", "/transactions", 200, &recorded);
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+
+        let by_json = client.submit_transaction_json(&serde_json::to_value(signed.json()).unwrap(), false).await.unwrap();
+        let by_hex = client.submit_transaction_hex(signed.hex(), false).await.unwrap();
+        assert_eq!(by_json.transaction_id, signed.id());
+        assert_eq!(by_hex.transaction_id, signed.id());
+
+        let requests = mock.requests_to("POST", "/transactions");
+        let json_body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        let hex_body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(json_body, hex_body);
+
+        assert_eq!(json_body["allowOrphan"], false);
+        let tx = &json_body["transaction"];
+        assert!(tx.is_object(), "transaction must be a JSON object, got {}", tx);
+        assert_eq!(tx["version"], 0);
+        assert_eq!(tx["inputs"][0]["previousOutpoint"]["transactionId"], "11".repeat(32));
+        assert_eq!(tx["inputs"][0]["previousOutpoint"]["index"], 1);
+        assert_eq!(tx["inputs"][0]["sigOpCount"], 1);
+        assert!(tx["inputs"][0]["signatureScript"].is_string());
+        assert_eq!(tx["outputs"][0]["amount"], 90_000);
+        assert_eq!(tx["outputs"][0]["scriptPublicKey"]["scriptPublicKey"], hex::encode(&script));
+        assert_eq!(tx["payload"], hex::encode(b"envelope"));
+        assert_eq!(tx["subnetworkId"], "0".repeat(40));
+    }
+
+    #[tokio::test]
+    async fn test_submit_body_snapshot() {
+        let (signed, _) = envelope_fixture();
+
+        let mock = MockRpc::start().await;
+        mock.on("POST", "/transactions", 200, &format!(r#"{{"transactionId":"{}"}}"#, signed.id()));
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        client.submit_transaction_hex(signed.hex(), false).await.unwrap();
+
+        // A snapshot of what this client sent when the test was written, not
+        // a capture from a live node: it guards against unintended changes to
+        // the body, so only update it for a deliberate format change.
+        let snapshot: serde_json::Value = serde_json::from_str(SUBMIT_BODY_SNAPSHOT).unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&mock.requests_to("POST", "/transactions")[0].body).unwrap();
+        assert_eq!(sent, snapshot);
+    }
+
+    const SUBMIT_BODY_SNAPSHOT: &str = r#"{
+        "transaction": {
+            "version": 0,
+            "inputs": [{
+                "previousOutpoint": {
+                    "transactionId": "1111111111111111111111111111111111111111111111111111111111111111",
+                    "index": 1
+                },
+                "signatureScript": "41f9fb8044941a0f1845b47624f17e73e6cb309bf8c476ac5a7892b8bbc696a0b946f17bf45ef843b02b58165d1ad9fc82f2ef0dcafd8947b83f15cf26bbe5254501",
+                "sequence": 0,
+                "sigOpCount": 1
+            }],
+            "outputs": [{
+                "amount": 90000,
+                "scriptPublicKey": {
+                    "version": 0,
+                    "scriptPublicKey": "20205b72d9f3c8f58c574533c0686361408e393e9edaf028fbdcd850d1bc37f8acac"
+                }
+            }],
+            "lockTime": 0,
+            "subnetworkId": "0000000000000000000000000000000000000000",
+            "gas": 0,
+            "payload": "656e76656c6f7065",
+            "mass": 1592
+        },
+        "allowOrphan": false
+    }"#;
+
+    #[test]
+    fn test_submit_error_classification() {
+        // Messages as emitted by the node's mempool, wrapped by the REST server
+        let cases = [
+            (
+                r#"{"error": "Rejected transaction 7d9e...: transaction 7d9e... is an orphan where orphan is disallowed"}"#,
+                SubmitError::Orphan,
+            ),
+            (
+                r#"{"error": "Rejected transaction 7d9e...: transaction 7d9e... was already accepted by the consensus"}"#,
+                SubmitError::AlreadyAccepted,
+            ),
+            (
+                r#"{"error": "Rejected transaction 7d9e...: transaction 7d9e... is already in the mempool"}"#,
+                SubmitError::AlreadyAccepted,
+            ),
+            (
+                r#"{"error": "Rejected transaction 7d9e...: transaction 7d9e... has 1000 fees which is under the required amount of 2036"}"#,
+                SubmitError::FeeTooLow,
+            ),
+            (
+                r#"{"detail": "transaction 7d9e... is invalid: failed to verify the signature script: false stack entry at end of script execution"}"#,
+                SubmitError::RejectedScript,
+            ),
+            (
+                "transaction 7d9e... is double spending an output",
+                SubmitError::Other("transaction 7d9e... is double spending an output".to_string()),
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(SubmitError::from_response(body), expected, "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejection_is_typed() {
+        let mock = MockRpc::start().await;
+        mock.on(
+            "POST",
+            "/transactions",
+            400,
+            r#"{"transactionId": null, "error": "transaction ab is an orphan where orphan is disallowed"}"#,
+        );
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+
+        let err = client.submit_transaction_json(&serde_json::json!({}), false).await.unwrap_err();
+        assert!(matches!(err, RpcError::Rejected(SubmitError::Orphan)));
+    }
+
+    fn fast_retries(max_retries: u32) -> RpcConfig {
+        RpcConfig {
+            retry: RetryConfig {
+                max_retries,
+                backoff: Duration::from_millis(10),
+                ..RetryConfig::default()
+            },
+            ..RpcConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_jittered_stays_within_bounds() {
+        let delay = Duration::from_millis(100);
+        for _ in 0..100 {
+            let wait = jittered(delay);
+            assert!(wait >= Duration::from_millis(50) && wait < Duration::from_millis(150), "{:?}", wait);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_transient_errors() {
+        let mock = MockRpc::start().await;
+        mock.on_sequence("GET", "/addresses/kaspatest:qq/balance", vec![
+            MockResponse::new(503, "unavailable"),
+            MockResponse::new(502, "bad gateway"),
+            MockResponse::new(200, r#"{"address": "kaspatest:qq", "balance": "1234"}"#),
+        ]);
+        let client = RpcClient::with_config(Some(mock.url()), fast_retries(3)).unwrap();
+
+        let balance = client.get_balance_by_address("kaspatest:qq").await.unwrap();
+        assert_eq!(balance.balance, 1234);
+        assert_eq!(mock.requests_to("GET", "/addresses/kaspatest:qq/balance").len(), 3);
+
+        // Out of retries, the last error is returned
+        let mock = MockRpc::start().await;
+        mock.on("GET", "/addresses/kaspatest:qq/balance", 503, "unavailable");
+        let client = RpcClient::with_config(Some(mock.url()), fast_retries(1)).unwrap();
+        assert!(client.get_balance_by_address("kaspatest:qq").await.is_err());
+        assert_eq!(mock.requests_to("GET", "/addresses/kaspatest:qq/balance").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_on_429() {
+        let mock = MockRpc::start().await;
+        mock.on_sequence("GET", "/info/network", vec![
+            MockResponse::new(429, "slow down").with_header("Retry-After", "1"),
+            MockResponse::new(200, r#"{"networkName": "kaspa-testnet-10"}"#),
+        ]);
+        let client = RpcClient::with_config(Some(mock.url()), fast_retries(3)).unwrap();
+
+        let start = std::time::Instant::now();
+        assert_eq!(client.get_network_name().await.unwrap(), "kaspa-testnet-10");
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_retry_after_parsing() {
+        assert_eq!(retry_after(" 2 "), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after("3600"), Some(MAX_RETRY_AFTER));
+        assert_eq!(retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        assert_eq!(retry_after("-1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_submit_is_not_retried() {
+        let mock = MockRpc::start().await;
+        mock.on_sequence("POST", "/transactions", vec![
+            MockResponse::new(503, "unavailable"),
+            MockResponse::new(200, r#"{"transactionId": "ab"}"#),
+        ]);
+        let client = RpcClient::with_config(Some(mock.url()), fast_retries(3)).unwrap();
+
+        assert!(client.submit_transaction_json(&serde_json::json!({}), false).await.is_err());
+        assert_eq!(mock.requests_to("POST", "/transactions").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_builder_timeout() {
+        let mock = MockRpc::start().await;
+        mock.on_sequence("GET", "/info/network", vec![
+            MockResponse::new(200, r#"{"networkName": "kaspa-testnet-10"}"#).with_delay(Duration::from_secs(2)),
+            MockResponse::new(200, r#"{"networkName": "kaspa-testnet-10"}"#),
+        ]);
+        let client = RpcClient::builder()
+            .url(mock.url())
+            .timeout(Duration::from_millis(200))
+            .user_agent("graffiti-test/1.0")
+            .retry(RetryConfig { max_retries: 0, ..RetryConfig::default() })
+            .build()
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        assert!(matches!(client.get_network_name().await, Err(RpcError::Connection(_))));
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        assert_eq!(client.get_network_name().await.unwrap(), "kaspa-testnet-10");
+
+        let err = RpcClient::builder().proxy("not a proxy url").build().err().unwrap();
+        assert!(matches!(err, RpcError::Config(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_submit_retries_when_enabled() {
+        let mock = MockRpc::start().await;
+        mock.on_sequence("POST", "/transactions", vec![
+            MockResponse::new(503, "unavailable"),
+            MockResponse::new(502, "bad gateway"),
+            MockResponse::new(200, r#"{"transactionId": "ab"}"#),
+        ]);
+        let client = RpcClient::new(Some(mock.url())).unwrap().with_retry_config(RetryConfig {
+            retry_submits: true,
+            ..fast_retries(3).retry
+        });
+
+        let response = client.submit_transaction_json(&serde_json::json!({}), false).await.unwrap();
+        assert_eq!(response.transaction_id, "ab");
+        assert_eq!(mock.requests_to("POST", "/transactions").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_acceptance() {
+        let txid = "ab".repeat(32);
+        let path = format!("/transactions/{}", txid);
+        let tx = |accepted: bool| serde_json::json!({ "transaction_id": txid, "is_accepted": accepted }).to_string();
+
+        let mock = MockRpc::start().await;
+        mock.on_sequence("GET", &path, vec![
+            MockResponse::new(404, r#"{"detail": "Transaction not found"}"#),
+            MockResponse::new(200, &tx(false)),
+            MockResponse::new(200, &tx(true)),
+        ]);
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+
+        let interval = Duration::from_millis(10);
+        assert!(client.poll_acceptance(&txid, Duration::from_secs(5), interval).await.unwrap());
+        assert_eq!(mock.requests_to("GET", &path).len(), 3);
+
+        let mock = MockRpc::start().await;
+        mock.on("GET", &path, 200, &tx(false));
+        let client = RpcClient::new(Some(mock.url())).unwrap();
+        assert!(!client.poll_acceptance(&txid, Duration::from_millis(100), interval).await.unwrap());
+    }
+}
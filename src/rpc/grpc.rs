@@ -0,0 +1,126 @@
+use borsh::BorshDeserialize;
+use kaspa_consensus_core::tx::Transaction;
+use kaspa_grpc_client::GrpcClient;
+use kaspa_rpc_core::api::rpc::RpcApi;
+use kaspa_rpc_core::{RpcTransaction, RpcUtxosByAddressesEntry};
+
+use super::client::{
+    GetBalanceByAddressResponse, GetOutPoint, GetScriptPublicKey, GetUtxoEntry,
+    GetUtxosByAddressesEntry, GetUtxosByAddressesResponse, RpcError, SubmitError,
+    SubmitTransactionResponse,
+};
+use super::transport::RpcTransport;
+use crate::wallet::KaspaSignedTransaction;
+
+/// Default gRPC port of a local testnet-10 node.
+pub const LOCAL_TESTNET10_GRPC: &str = "grpc://127.0.0.1:16210";
+
+/// Talks to a node's gRPC interface directly, returning the same response
+/// types as the REST `RpcClient`.
+#[derive(Debug, Clone)]
+pub struct GrpcRpcClient {
+    client: GrpcClient,
+}
+
+impl GrpcRpcClient {
+    /// Connect to `url`, which must use the `grpc://` scheme.
+    pub async fn connect(url: &str) -> Result<Self, RpcError> {
+        let client = GrpcClient::connect(url.to_string())
+            .await
+            .map_err(|e| RpcError::Connection(e.to_string()))?;
+
+        Ok(Self { client })
+    }
+
+    pub async fn disconnect(&self) -> Result<(), RpcError> {
+        self.client
+            .disconnect()
+            .await
+            .map_err(|e| RpcError::Connection(e.to_string()))
+    }
+}
+
+fn parse_address(address: &str) -> Result<kaspa_addresses::Address, RpcError> {
+    kaspa_addresses::Address::try_from(address)
+        .map_err(|e| RpcError::InvalidResponse(format!("invalid address {}: {}", address, e)))
+}
+
+fn to_entry(entry: RpcUtxosByAddressesEntry) -> GetUtxosByAddressesEntry {
+    GetUtxosByAddressesEntry {
+        address: entry.address.map(|a| a.to_string()).unwrap_or_default(),
+        outpoint: GetOutPoint {
+            transaction_id: entry.outpoint.transaction_id.to_string(),
+            index: entry.outpoint.index,
+        },
+        utxo_entry: GetUtxoEntry {
+            amount: entry.utxo_entry.amount,
+            script_public_key: GetScriptPublicKey {
+                version: entry.utxo_entry.script_public_key.version(),
+                script: hex::encode(entry.utxo_entry.script_public_key.script()),
+            },
+            block_daa_score: entry.utxo_entry.block_daa_score,
+            is_coinbase: entry.utxo_entry.is_coinbase,
+        },
+        // The node only returns unspent outputs
+        is_spent: false,
+    }
+}
+
+impl RpcTransport for GrpcRpcClient {
+    async fn get_balance_by_address(
+        &self,
+        address: &str,
+    ) -> Result<GetBalanceByAddressResponse, RpcError> {
+        let balance = self
+            .client
+            .get_balance_by_address(parse_address(address)?)
+            .await
+            .map_err(|e| RpcError::Rpc(e.to_string()))?;
+
+        Ok(GetBalanceByAddressResponse { balance })
+    }
+
+    async fn get_utxos_by_addresses(
+        &self,
+        addresses: Vec<String>,
+    ) -> Result<GetUtxosByAddressesResponse, RpcError> {
+        let addresses = addresses
+            .iter()
+            .map(|a| parse_address(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let entries = self
+            .client
+            .get_utxos_by_addresses(addresses)
+            .await
+            .map_err(|e| RpcError::Rpc(e.to_string()))?;
+
+        Ok(GetUtxosByAddressesResponse {
+            entries: entries.into_iter().map(to_entry).collect(),
+        })
+    }
+
+    async fn submit_transaction(
+        &self,
+        tx: &KaspaSignedTransaction,
+        allow_orphan: bool,
+    ) -> Result<SubmitTransactionResponse, RpcError> {
+        let bytes = hex::decode(tx.hex()).map_err(|e| RpcError::JsonError(e.to_string()))?;
+        let consensus_tx = Transaction::try_from_slice(&bytes)
+            .map_err(|e| RpcError::JsonError(format!("Invalid transaction bytes: {}", e)))?;
+
+        let transaction_id = self
+            .client
+            .submit_transaction(RpcTransaction::from(&consensus_tx), allow_orphan)
+            .await
+            .map_err(|e| match e {
+                kaspa_rpc_core::RpcError::RejectedTransaction(_, reason) => {
+                    SubmitError::from_response(&reason).into()
+                }
+                e => RpcError::Connection(e.to_string()),
+            })?;
+
+        Ok(SubmitTransactionResponse {
+            transaction_id: transaction_id.to_string(),
+        })
+    }
+}
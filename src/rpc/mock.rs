@@ -0,0 +1,198 @@
+//! Minimal in-process HTTP server standing in for the Kaspa REST API in tests.
+//!
+//! Routes are matched on method and path (query string ignored). A route can
+//! hold a sequence of responses; each request consumes one and the last one
+//! repeats. Every request is recorded so tests can inspect what was sent.
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: String,
+    pub headers: Vec<(String, String)>,
+    pub delay: Option<Duration>,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            body: body.to_string(),
+            headers: Vec::new(),
+            delay: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+struct Route {
+    method: String,
+    path: String,
+    responses: Vec<MockResponse>,
+}
+
+#[derive(Clone)]
+pub struct MockRpc {
+    url: String,
+    routes: Arc<Mutex<Vec<Route>>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockRpc {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let mock = Self {
+            url,
+            routes: Arc::new(Mutex::new(Vec::new())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let server = mock.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let server = server.clone();
+                tokio::spawn(async move { server.handle(stream).await });
+            }
+        });
+
+        mock
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn on(&self, method: &str, path: &str, status: u16, body: &str) -> &Self {
+        self.on_sequence(method, path, vec![MockResponse::new(status, body)])
+    }
+
+    pub fn on_sequence(&self, method: &str, path: &str, responses: Vec<MockResponse>) -> &Self {
+        self.routes.lock().unwrap().push(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            responses,
+        });
+        self
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn requests_to(&self, method: &str, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.method == method && r.path.split('?').next() == Some(path))
+            .collect()
+    }
+
+    async fn handle(&self, mut stream: TcpStream) {
+        let request = match read_request(&mut stream).await {
+            Some(request) => request,
+            None => return,
+        };
+        self.requests.lock().unwrap().push(request.clone());
+
+        let response = {
+            let mut routes = self.routes.lock().unwrap();
+            let route_path = request.path.split('?').next().unwrap_or_default();
+            routes
+                .iter_mut()
+                .find(|r| r.method == request.method && r.path == route_path)
+                .map(|route| {
+                    if route.responses.len() > 1 {
+                        route.responses.remove(0)
+                    } else {
+                        route.responses[0].clone()
+                    }
+                })
+                .unwrap_or_else(|| MockResponse::new(404, "{\"error\": \"not found\"}"))
+        };
+
+        if let Some(delay) = response.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        let mut raw = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            response.status,
+            response.body.len()
+        );
+        for (name, value) in &response.headers {
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        raw.push_str("\r\n");
+        raw.push_str(&response.body);
+
+        let _ = stream.write_all(raw.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+
+    let body_end = buffer.len().min(header_end + content_length);
+    let body = String::from_utf8_lossy(&buffer[header_end..body_end]).to_string();
+
+    Some(RecordedRequest { method, path, body })
+}
@@ -1,2 +1,10 @@
 pub mod client;
-pub use client::{RpcClient, PUBLIC_TESTNET10_RPC};
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(test)]
+pub(crate) mod mock;
+mod transport;
+pub use client::{BlockDagInfo, RetryConfig, RpcClient, RpcClientBuilder, RpcConfig, RpcError, SubmitError, UtxoPages, PUBLIC_MAINNET_RPC, PUBLIC_TESTNET10_RPC, PUBLIC_TESTNET11_RPC, UTXO_PAGE_SIZE};
+pub use transport::RpcTransport;
+#[cfg(feature = "grpc")]
+pub use grpc::{GrpcRpcClient, LOCAL_TESTNET10_GRPC};
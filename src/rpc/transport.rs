@@ -0,0 +1,55 @@
+use std::future::Future;
+
+use super::client::{
+    GetBalanceByAddressResponse, GetUtxosByAddressesResponse, RpcClient, RpcError,
+    SubmitTransactionResponse,
+};
+use crate::wallet::KaspaSignedTransaction;
+
+/// The node operations a send needs, so commands can run over either the
+/// REST `RpcClient` or, with the `grpc` feature, `GrpcRpcClient`.
+pub trait RpcTransport {
+    fn get_balance_by_address(
+        &self,
+        address: &str,
+    ) -> impl Future<Output = Result<GetBalanceByAddressResponse, RpcError>> + Send;
+
+    fn get_utxos_by_addresses(
+        &self,
+        addresses: Vec<String>,
+    ) -> impl Future<Output = Result<GetUtxosByAddressesResponse, RpcError>> + Send;
+
+    /// Submit a signed transaction. With `allow_orphan`, the node keeps it
+    /// even if it spends outputs of transactions it hasn't seen yet.
+    fn submit_transaction(
+        &self,
+        tx: &KaspaSignedTransaction,
+        allow_orphan: bool,
+    ) -> impl Future<Output = Result<SubmitTransactionResponse, RpcError>> + Send;
+}
+
+impl RpcTransport for RpcClient {
+    async fn get_balance_by_address(
+        &self,
+        address: &str,
+    ) -> Result<GetBalanceByAddressResponse, RpcError> {
+        RpcClient::get_balance_by_address(self, address).await
+    }
+
+    async fn get_utxos_by_addresses(
+        &self,
+        addresses: Vec<String>,
+    ) -> Result<GetUtxosByAddressesResponse, RpcError> {
+        RpcClient::get_utxos_by_addresses(self, addresses).await
+    }
+
+    async fn submit_transaction(
+        &self,
+        tx: &KaspaSignedTransaction,
+        allow_orphan: bool,
+    ) -> Result<SubmitTransactionResponse, RpcError> {
+        let json_tx =
+            serde_json::to_value(tx.json()).map_err(|e| RpcError::JsonError(e.to_string()))?;
+        self.submit_transaction_json(&json_tx, allow_orphan).await
+    }
+}
@@ -9,9 +9,11 @@ pub enum AddressError {
     BadChecksum,
     #[error("Unknown network")]
     UnknownNetwork,
+    #[error("Not a Schnorr public key address")]
+    NotPubKey,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Network {
     Mainnet,
     Testnet10,
@@ -29,6 +31,31 @@ impl Network {
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet10 => "testnet-10",
+            Network::Testnet11 => "testnet-11",
+            Network::Simnet => "simnet",
+        }
+    }
+
+    /// P2PK address of the all-zero x-only key. No curve point has x = 0,
+    /// so coins sent here can never be spent.
+    pub fn burn_address(&self) -> &'static str {
+        match self {
+            Network::Mainnet => {
+                "kaspa:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqkx9awp4e"
+            }
+            Network::Testnet10 | Network::Testnet11 => {
+                "kaspatest:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqhqrxplya"
+            }
+            Network::Simnet => {
+                "kaspasim:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqekcujlt2"
+            }
+        }
+    }
+
     pub fn from_name(name: &str) -> Result<Self, AddressError> {
         match name.to_lowercase().as_str() {
             "mainnet" => Ok(Network::Mainnet),
@@ -65,6 +92,19 @@ pub fn extract_pubkey_hash_from_address(address: &str) -> Result<Vec<u8>, Addres
     Ok(addr.payload.to_vec())
 }
 
+/// The x-only public key a Schnorr P2PK address pays to, for checking a
+/// message signature against an address.
+pub fn xonly_pubkey_from_address(address: &str) -> Result<[u8; 32], AddressError> {
+    let addr = Address::try_from(address).map_err(|_| AddressError::InvalidFormat)?;
+    if addr.version != Version::PubKey {
+        return Err(AddressError::NotPubKey);
+    }
+    addr.payload
+        .as_slice()
+        .try_into()
+        .map_err(|_| AddressError::InvalidFormat)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,5 +162,34 @@ mod tests {
     fn test_burn_address() {
         let burn_address = "kaspa:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqkx9awp4e";
         assert!(validate_address(burn_address, Network::Mainnet).unwrap());
+        assert_eq!(Network::Mainnet.burn_address(), burn_address);
+
+        for network in [
+            Network::Mainnet,
+            Network::Testnet10,
+            Network::Testnet11,
+            Network::Simnet,
+        ] {
+            let expected = Address::new(network.to_prefix(), Version::PubKey, &[0u8; 32]);
+            assert_eq!(network.burn_address(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_xonly_pubkey_from_address() {
+        let keypair = KeyPair::new();
+        let address = generate_address(keypair.public_key(), Network::Testnet10);
+        let xonly = xonly_pubkey_from_address(&address).unwrap();
+        assert_eq!(xonly[..], keypair.public_key_bytes()[1..]);
+
+        let p2sh = Address::new(Prefix::Testnet, Version::ScriptHash, &[7u8; 32]);
+        assert!(matches!(
+            xonly_pubkey_from_address(&p2sh.to_string()),
+            Err(AddressError::NotPubKey)
+        ));
+        assert!(matches!(
+            xonly_pubkey_from_address("kaspatest:not-an-address"),
+            Err(AddressError::InvalidFormat)
+        ));
     }
 }
@@ -0,0 +1,264 @@
+use super::kaspa_signer::input_mass;
+use crate::rpc::client::GetUtxosByAddressesEntry;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CoinSelectionError {
+    #[error("Insufficient funds: have {available}, need {required}")]
+    InsufficientFunds { available: u64, required: u64 },
+}
+
+/// Coinbase outputs become spendable this many DAA scores after inclusion.
+pub const COINBASE_MATURITY: u64 = 1000;
+
+/// Whether `entry` can be spent at `virtual_daa_score`.
+pub fn is_mature(entry: &GetUtxosByAddressesEntry, virtual_daa_score: u64) -> bool {
+    !entry.utxo_entry.is_coinbase
+        || virtual_daa_score
+            >= entry
+                .utxo_entry
+                .block_daa_score
+                .saturating_add(COINBASE_MATURITY)
+}
+
+/// Order in which `select_utxos` considers candidate UTXOs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectionStrategy {
+    /// Fewest inputs, lowest fee.
+    #[default]
+    LargestFirst,
+    /// Spends dust first, consolidating small UTXOs at a higher fee.
+    SmallestFirst,
+    /// Prefer the smallest single UTXO that covers the target, falling back
+    /// to largest-first when none does.
+    MinimizeChange,
+}
+
+/// Pick UTXOs according to `strategy` until they cover `target_amount` plus
+/// the fee for the selected inputs themselves at `fee_rate`.
+///
+/// `target_amount` must already include the outputs, the fee for the rest of
+/// the transaction and any minimum change. Spent entries and immature
+/// coinbase outputs are skipped.
+pub fn select_utxos(
+    entries: &[GetUtxosByAddressesEntry],
+    target_amount: u64,
+    fee_rate: u64,
+    virtual_daa_score: u64,
+    strategy: SelectionStrategy,
+) -> Result<Vec<GetUtxosByAddressesEntry>, CoinSelectionError> {
+    let mature: Vec<GetUtxosByAddressesEntry> = entries
+        .iter()
+        .filter(|e| is_mature(e, virtual_daa_score))
+        .cloned()
+        .collect();
+    select_spendable(&mature, target_amount, fee_rate, strategy)
+}
+
+/// `select_utxos` over entries already known to be mature, e.g. filtered
+/// with `is_mature` by the caller. Spent entries are still skipped.
+pub fn select_spendable(
+    entries: &[GetUtxosByAddressesEntry],
+    target_amount: u64,
+    fee_rate: u64,
+    strategy: SelectionStrategy,
+) -> Result<Vec<GetUtxosByAddressesEntry>, CoinSelectionError> {
+    // Saturating, so an absurd fee rate is just unaffordable
+    let input_fee = input_mass().saturating_mul(fee_rate);
+    let required =
+        |inputs: usize| target_amount.saturating_add((inputs as u64).saturating_mul(input_fee));
+
+    let mut candidates: Vec<&GetUtxosByAddressesEntry> =
+        entries.iter().filter(|e| !e.is_spent).collect();
+
+    match strategy {
+        SelectionStrategy::LargestFirst | SelectionStrategy::MinimizeChange => {
+            candidates.sort_by(|a, b| b.utxo_entry.amount.cmp(&a.utxo_entry.amount))
+        }
+        SelectionStrategy::SmallestFirst => {
+            candidates.sort_by(|a, b| a.utxo_entry.amount.cmp(&b.utxo_entry.amount))
+        }
+    }
+
+    if strategy == SelectionStrategy::MinimizeChange {
+        // Candidates are sorted descending, so the last match is the smallest
+        if let Some(single) = candidates
+            .iter()
+            .rev()
+            .find(|e| e.utxo_entry.amount >= required(1))
+        {
+            return Ok(vec![(*single).clone()]);
+        }
+    }
+
+    let mut selected = Vec::new();
+    let mut total: u64 = 0;
+    for entry in candidates {
+        selected.push(entry.clone());
+        total = total.saturating_add(entry.utxo_entry.amount);
+
+        if total >= required(selected.len()) {
+            return Ok(selected);
+        }
+    }
+
+    Err(CoinSelectionError::InsufficientFunds {
+        available: total,
+        required: required(selected.len().max(1)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::client::{GetOutPoint, GetScriptPublicKey, GetUtxoEntry};
+
+    fn entry(
+        index: u32,
+        amount: u64,
+        is_coinbase: bool,
+        block_daa_score: u64,
+    ) -> GetUtxosByAddressesEntry {
+        GetUtxosByAddressesEntry {
+            address: "kaspatest:test".to_string(),
+            outpoint: GetOutPoint {
+                transaction_id: hex::encode([index as u8; 32]),
+                index,
+            },
+            utxo_entry: GetUtxoEntry {
+                amount,
+                script_public_key: GetScriptPublicKey {
+                    version: 0,
+                    script: String::new(),
+                },
+                block_daa_score,
+                is_coinbase,
+            },
+            is_spent: false,
+        }
+    }
+
+    #[test]
+    fn test_select_largest_first() {
+        let entries = vec![
+            entry(0, 10_000, false, 0),
+            entry(1, 500_000, false, 0),
+            entry(2, 20_000, false, 0),
+        ];
+
+        let selected =
+            select_utxos(&entries, 100_000, 1, 5_000, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].utxo_entry.amount, 500_000);
+    }
+
+    #[test]
+    fn test_select_accounts_for_input_fees() {
+        // Two inputs cover the target exactly, but not their own fees
+        let entries = vec![
+            entry(0, 50_000, false, 0),
+            entry(1, 50_000, false, 0),
+            entry(2, 5_000, false, 0),
+        ];
+
+        let selected =
+            select_utxos(&entries, 100_000, 1, 5_000, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected.len(), 3);
+
+        let err =
+            select_utxos(&entries, 103_000, 1, 5_000, SelectionStrategy::LargestFirst).unwrap_err();
+        let CoinSelectionError::InsufficientFunds {
+            available,
+            required,
+        } = err;
+        assert_eq!(available, 105_000);
+        assert_eq!(required, 103_000 + 3 * input_mass());
+    }
+
+    #[test]
+    fn test_select_skips_immature_coinbase() {
+        let mut spent = entry(2, 5_000_000, false, 0);
+        spent.is_spent = true;
+        let entries = vec![
+            entry(0, 1_000_000, true, 4_500),
+            entry(1, 200_000, false, 0),
+            spent,
+        ];
+
+        let selected =
+            select_utxos(&entries, 100_000, 1, 5_000, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].utxo_entry.amount, 200_000);
+
+        let selected =
+            select_utxos(&entries, 100_000, 1, 5_500, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected[0].utxo_entry.amount, 1_000_000);
+
+        // The caller vouches for maturity, but spent entries are still skipped
+        let selected =
+            select_spendable(&entries, 100_000, 1, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected[0].utxo_entry.amount, 1_000_000);
+        let selected =
+            select_spendable(&entries[1..], 100_000, 1, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected[0].utxo_entry.amount, 200_000);
+    }
+
+    #[test]
+    fn test_select_strategies_single_large_utxo() {
+        let entries = vec![
+            entry(0, 30_000, false, 0),
+            entry(1, 150_000, false, 0),
+            entry(2, 1_000_000, false, 0),
+            entry(3, 40_000, false, 0),
+        ];
+
+        let largest =
+            select_utxos(&entries, 100_000, 1, 0, SelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(largest.len(), 1);
+        assert_eq!(largest[0].utxo_entry.amount, 1_000_000);
+
+        // The 150_000 UTXO covers the target with far less change
+        let exact =
+            select_utxos(&entries, 100_000, 1, 0, SelectionStrategy::MinimizeChange).unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].utxo_entry.amount, 150_000);
+
+        let smallest =
+            select_utxos(&entries, 100_000, 1, 0, SelectionStrategy::SmallestFirst).unwrap();
+        let amounts: Vec<u64> = smallest.iter().map(|e| e.utxo_entry.amount).collect();
+        assert_eq!(amounts, vec![30_000, 40_000, 150_000]);
+    }
+
+    #[test]
+    fn test_select_strategies_several_small_utxos() {
+        let entries: Vec<_> = (0..10).map(|i| entry(i, 20_000, false, 0)).collect();
+
+        for strategy in [
+            SelectionStrategy::LargestFirst,
+            SelectionStrategy::SmallestFirst,
+            SelectionStrategy::MinimizeChange,
+        ] {
+            let selected = select_utxos(&entries, 100_000, 1, 0, strategy).unwrap();
+            // 6 inputs: 120_000 >= 100_000 + 6 * input_mass(), 5 would not cover their own fee
+            assert_eq!(selected.len(), 6, "{:?}", strategy);
+        }
+
+        assert!(select_utxos(&entries, 250_000, 1, 0, SelectionStrategy::MinimizeChange).is_err());
+    }
+
+    #[test]
+    fn test_select_saturates_instead_of_overflowing() {
+        let entries = vec![
+            entry(0, u64::MAX - 1, false, 0),
+            entry(1, 1, true, u64::MAX),
+        ];
+
+        let err =
+            select_utxos(&entries, 1, u64::MAX, 0, SelectionStrategy::LargestFirst).unwrap_err();
+        let CoinSelectionError::InsufficientFunds { required, .. } = err;
+        assert_eq!(required, u64::MAX);
+
+        // A coinbase output this late never matures rather than wrapping around
+        assert!(!is_mature(&entries[1], u64::MAX - 1));
+    }
+}
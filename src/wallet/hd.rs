@@ -1,7 +1,8 @@
-use crate::wallet::{KeyPair, PrivateKey};
+use crate::wallet::{secp, KeyPair, Network, PrivateKey};
 use hmac::{Hmac, Mac};
-use secp256k1::{PublicKey, Secp256k1};
-use sha2::{Digest, Sha512};
+use ripemd::Ripemd160;
+use secp256k1::{PublicKey, Scalar};
+use sha2::{Digest, Sha256, Sha512};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,15 +15,94 @@ pub enum HdError {
     DerivationFailed,
     #[error("Hardened derivation requires private key")]
     HardenedRequiresPrivate,
+    #[error("Invalid seed length: {0} bytes (expected 16 to 64)")]
+    InvalidSeedLength(usize),
+    #[error("Invalid extended key: {0}")]
+    InvalidExtendedKey(String),
+    #[error("Extended key is public-only")]
+    PublicOnly,
+    #[error("Coin type {0} is not Kaspa's (111111)")]
+    WrongCoinType(u32),
 }
 
 type HmacSha512 = Hmac<Sha512>;
 
-const HARDENED_OFFSET: u32 = 0x80000000;
+/// Added to a child index to make it hardened, written `'` in paths.
+pub const HARDENED_OFFSET: u32 = 0x80000000;
+const MIN_SEED_LEN: usize = 16;
+/// SLIP-44 coin type registered for Kaspa.
+pub const KASPA_COIN_TYPE: u32 = 111111;
+const MAX_SEED_LEN: usize = 64;
+
+// BIP32 serialization version bytes
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const TPRV_VERSION: [u8; 4] = [0x04, 0x35, 0x83, 0x94];
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+/// The account and coin type levels of `m/44'/coin_type'/account'`, and
+/// how the `change/index` levels below it are derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerivationParams {
+    pub account: u32,
+    pub coin_type: u32,
+    /// Derive `change'/index'` instead of `change/index`. Hardened leaves
+    /// isolate sibling keys from a leaked account xpub but produce a
+    /// different address set that other wallets using standard BIP44
+    /// derivation will not find.
+    pub hardened_leaves: bool,
+}
+
+impl DerivationParams {
+    /// Account `account` of the Kaspa coin type.
+    pub fn for_account(account: u32) -> Self {
+        Self {
+            account,
+            ..Self::default()
+        }
+    }
+
+    /// Parse an account path such as `m/44'/111111'/2'`. Only Kaspa's coin
+    /// type is accepted; set `coin_type` directly to use another.
+    pub fn from_path(path: &str) -> Result<Self, HdError> {
+        match parse_path(path)?[..] {
+            [purpose, coin_type, account]
+                if purpose == 44 + HARDENED_OFFSET
+                    && coin_type >= HARDENED_OFFSET
+                    && account >= HARDENED_OFFSET =>
+            {
+                let coin_type = coin_type - HARDENED_OFFSET;
+                if coin_type != KASPA_COIN_TYPE {
+                    return Err(HdError::WrongCoinType(coin_type));
+                }
+                Ok(Self {
+                    account: account - HARDENED_OFFSET,
+                    coin_type,
+                    ..Self::default()
+                })
+            }
+            _ => Err(HdError::InvalidPath),
+        }
+    }
+}
+
+impl Default for DerivationParams {
+    fn default() -> Self {
+        Self {
+            account: 0,
+            coin_type: KASPA_COIN_TYPE,
+            hardened_leaves: false,
+        }
+    }
+}
+
+/// Version, depth, parent fingerprint, child index, chain code and key.
+const SERIALIZED_LEN: usize = 4 + 1 + 4 + 4 + 32 + 33;
 
 #[derive(Debug, Clone)]
 pub struct ExtendedKey {
-    keypair: KeyPair,
+    /// `None` for a public-only key, as parsed from an xpub.
+    keypair: Option<KeyPair>,
+    public_key: PublicKey,
     chain_code: [u8; 32],
     depth: u8,
     parent_fingerprint: [u8; 4],
@@ -30,7 +110,13 @@ pub struct ExtendedKey {
 }
 
 impl ExtendedKey {
+    /// Master key from a BIP32 seed of 16 to 64 bytes (64 when derived from a
+    /// BIP39 mnemonic).
     pub fn from_seed(seed: &[u8]) -> Result<Self, HdError> {
+        if !(MIN_SEED_LEN..=MAX_SEED_LEN).contains(&seed.len()) {
+            return Err(HdError::InvalidSeedLength(seed.len()));
+        }
+
         let mut mac =
             HmacSha512::new_from_slice(b"Bitcoin seed").map_err(|_| HdError::DerivationFailed)?;
         mac.update(seed);
@@ -41,8 +127,7 @@ impl ExtendedKey {
         let secret_key =
             PrivateKey::from_slice(key_bytes).map_err(|_| HdError::DerivationFailed)?;
 
-        let secp = Secp256k1::new();
-        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        let public_key = PublicKey::from_secret_key(secp(), &secret_key);
 
         let keypair = KeyPair::from_secret_and_public(secret_key, public_key);
 
@@ -50,7 +135,8 @@ impl ExtendedKey {
         chain_code_arr.copy_from_slice(chain_code);
 
         Ok(Self {
-            keypair,
+            keypair: Some(keypair),
+            public_key,
             chain_code: chain_code_arr,
             depth: 0,
             parent_fingerprint: [0u8; 4],
@@ -58,15 +144,21 @@ impl ExtendedKey {
         })
     }
 
+    /// Wrap a plain key as a master key.
+    ///
+    /// BIP32 has no way to give a bare key a chain code, so this one is made
+    /// up from the public key's SHA512. Children and xprvs of such a key
+    /// won't match any other wallet's; prefer `from_seed`.
+    #[deprecated(note = "non-standard chain code; derive from a seed instead")]
     pub fn from_keypair(keypair: KeyPair) -> Self {
-        // Generate a deterministic chain code from the public key
         let pubkey_bytes = keypair.public_key_bytes();
         let hash = Sha512::digest(&pubkey_bytes);
         let mut chain_code = [0u8; 32];
         chain_code.copy_from_slice(&hash[..32]);
 
         Self {
-            keypair,
+            public_key: *keypair.public_key(),
+            keypair: Some(keypair),
             chain_code,
             depth: 0,
             parent_fingerprint: [0u8; 4],
@@ -74,6 +166,8 @@ impl ExtendedKey {
         }
     }
 
+    /// Hardened children need the private key; a public-only key derives
+    /// only the public half of normal children.
     pub fn derive_child(&self, index: u32) -> Result<Self, HdError> {
         let is_hardened = index >= HARDENED_OFFSET;
 
@@ -81,10 +175,14 @@ impl ExtendedKey {
             HmacSha512::new_from_slice(&self.chain_code).map_err(|_| HdError::DerivationFailed)?;
 
         if is_hardened {
+            let keypair = self
+                .keypair
+                .as_ref()
+                .ok_or(HdError::HardenedRequiresPrivate)?;
             mac.update(&[0u8]);
-            mac.update(&self.keypair.secret_key().secret_bytes());
+            mac.update(&keypair.secret_key().secret_bytes());
         } else {
-            mac.update(&self.keypair.public_key_bytes());
+            mac.update(&self.public_key.serialize());
         }
 
         mac.update(&index.to_be_bytes());
@@ -92,101 +190,116 @@ impl ExtendedKey {
         let bytes = result.into_bytes();
 
         let (key_bytes, chain_code) = bytes.split_at(32);
-        let mut child_key_bytes = [0u8; 32];
-        child_key_bytes.copy_from_slice(key_bytes);
-
-        let secp = Secp256k1::new();
-        let parent_key_scalar = self.keypair.secret_key();
-        let child_key_scalar =
-            PrivateKey::from_slice(&child_key_bytes).map_err(|_| HdError::DerivationFailed)?;
-
-        let mut new_secret_bytes = parent_key_scalar.secret_bytes();
-        let child_bytes = child_key_scalar.secret_bytes();
-
-        let mut carry: u16 = 0;
-        for i in (0..32).rev() {
-            let sum = new_secret_bytes[i] as u16 + child_bytes[i] as u16 + carry;
-            new_secret_bytes[i] = (sum & 0xff) as u8;
-            carry = sum >> 8;
-        }
-
-        // Attempt to create secret key - if invalid (0 or >= curve order), this index is invalid
-        let new_secret = match PrivateKey::from_slice(&new_secret_bytes) {
-            Ok(key) => key,
-            Err(_) => return Err(HdError::InvalidIndex),
+        let mut tweak_bytes = [0u8; 32];
+        tweak_bytes.copy_from_slice(key_bytes);
+        // A tweak at or above the curve order, or a zero child key, makes
+        // this index invalid
+        let tweak = Scalar::from_be_bytes(tweak_bytes).map_err(|_| HdError::InvalidIndex)?;
+
+        let (keypair, public_key) = match &self.keypair {
+            Some(parent) => {
+                let secret = parent
+                    .secret_key()
+                    .add_tweak(&tweak)
+                    .map_err(|_| HdError::InvalidIndex)?;
+                let public = PublicKey::from_secret_key(secp(), &secret);
+                (
+                    Some(KeyPair::from_secret_and_public(secret, public)),
+                    public,
+                )
+            }
+            None => {
+                let public = self
+                    .public_key
+                    .add_exp_tweak(secp(), &tweak)
+                    .map_err(|_| HdError::InvalidIndex)?;
+                (None, public)
+            }
         };
-        let new_public = PublicKey::from_secret_key(&secp, &new_secret);
-
-        let keypair = KeyPair::from_secret_and_public(new_secret, new_public);
 
         let mut chain_code_arr = [0u8; 32];
         chain_code_arr.copy_from_slice(chain_code);
 
-        let fingerprint = self.calculate_fingerprint();
-
         Ok(Self {
             keypair,
+            public_key,
             chain_code: chain_code_arr,
-            depth: self.depth + 1,
-            parent_fingerprint: fingerprint,
+            depth: self.depth.checked_add(1).ok_or(HdError::InvalidIndex)?,
+            parent_fingerprint: self.calculate_fingerprint(),
             child_index: index,
         })
     }
 
+    /// Derive along a path such as `m/44'/111111'/0'/0/7`, where `'` or
+    /// `h` marks a hardened level.
     pub fn derive_path(&self, path: &str) -> Result<Self, HdError> {
-        if !path.starts_with('m') {
-            return Err(HdError::InvalidPath);
-        }
+        parse_path(path)?
+            .into_iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(index))
+    }
 
-        let path_part = &path[1..];
-        if path_part.is_empty() {
-            return Ok(self.clone());
+    /// The `m/44'/coin_type'/account'` node for `params`. Derive it once and
+    /// call [`derive_leaf`](Self::derive_leaf) on it to skip the three
+    /// hardened steps per address.
+    pub fn account_key(&self, params: DerivationParams) -> Result<Self, HdError> {
+        if params.account >= HARDENED_OFFSET || params.coin_type >= HARDENED_OFFSET {
+            return Err(HdError::InvalidIndex);
         }
+        let purpose = self.derive_child(44 + HARDENED_OFFSET)?;
+        let coin_type = purpose.derive_child(params.coin_type + HARDENED_OFFSET)?;
+        coin_type.derive_child(params.account + HARDENED_OFFSET)
+    }
 
-        let indices: Vec<u32> = path_part
-            .trim_start_matches('/')
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .map(|s| {
-                if s.ends_with('\'') || s.ends_with('h') {
-                    let num: u32 = s
-                        .trim_end_matches('\'')
-                        .trim_end_matches('h')
-                        .parse()
-                        .map_err(|_| HdError::InvalidPath)?;
-                    Ok(num + HARDENED_OFFSET)
-                } else {
-                    s.parse().map_err(|_| HdError::InvalidPath)
-                }
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let mut current = self.clone();
-        for index in indices {
-            current = current.derive_child(index)?;
+    /// Address key `index` on the receive or change chain of an account node
+    /// from [`account_key`](Self::account_key), hardened at both levels when
+    /// `params.hardened_leaves` is set.
+    pub fn derive_leaf(
+        &self,
+        params: DerivationParams,
+        is_change: bool,
+        index: u32,
+    ) -> Result<Self, HdError> {
+        if index >= HARDENED_OFFSET {
+            return Err(HdError::InvalidIndex);
         }
+        let leaf_offset = if params.hardened_leaves {
+            HARDENED_OFFSET
+        } else {
+            0
+        };
+        self.derive_child(is_change as u32 + leaf_offset)?
+            .derive_child(index + leaf_offset)
+    }
 
-        Ok(current)
+    /// Panics on a public-only key; use [`try_keypair`](Self::try_keypair)
+    /// when the key may have come from an xpub.
+    pub fn keypair(&self) -> &KeyPair {
+        self.keypair
+            .as_ref()
+            .expect("public-only extended key has no private key")
     }
 
-    pub fn derive_address_index(&self, index: u32) -> Result<Self, HdError> {
-        let purpose = self.derive_child(44 + HARDENED_OFFSET)?;
-        let coin_type = purpose.derive_child(111111 + HARDENED_OFFSET)?;
-        let account = coin_type.derive_child(0 + HARDENED_OFFSET)?;
-        let change = account.derive_child(0)?;
-        change.derive_child(index)
+    /// The key pair, or `PublicOnly` for a key parsed from an xpub or
+    /// [`neuter`](Self::neuter)ed.
+    pub fn try_keypair(&self) -> Result<&KeyPair, HdError> {
+        self.keypair.as_ref().ok_or(HdError::PublicOnly)
     }
 
-    pub fn derive_change_index(&self, index: u32) -> Result<Self, HdError> {
-        let purpose = self.derive_child(44 + HARDENED_OFFSET)?;
-        let coin_type = purpose.derive_child(111111 + HARDENED_OFFSET)?;
-        let account = coin_type.derive_child(0 + HARDENED_OFFSET)?;
-        let change = account.derive_child(1)?;
-        change.derive_child(index)
+    /// This key without its private half, for a watch-only wallet that can
+    /// derive normal children and addresses but can't sign.
+    pub fn neuter(&self) -> ExtendedKey {
+        ExtendedKey {
+            keypair: None,
+            ..self.clone()
+        }
     }
 
-    pub fn keypair(&self) -> &KeyPair {
-        &self.keypair
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.keypair.is_some()
     }
 
     pub fn chain_code(&self) -> &[u8; 32] {
@@ -201,15 +314,181 @@ impl ExtendedKey {
         self.child_index
     }
 
+    /// Base58check xprv (mainnet) or tprv (testnets and simnet) string.
+    pub fn to_xprv(&self, network: Network) -> Result<String, HdError> {
+        let keypair = self.try_keypair()?;
+        let version = match network {
+            Network::Mainnet => XPRV_VERSION,
+            Network::Testnet10 | Network::Testnet11 | Network::Simnet => TPRV_VERSION,
+        };
+        let mut key = [0u8; 33];
+        key[1..].copy_from_slice(&keypair.secret_key().secret_bytes());
+        Ok(self.serialize(version, &key))
+    }
+
+    /// Base58check xpub or tpub string, carrying only the public key.
+    pub fn to_xpub(&self, network: Network) -> String {
+        let version = match network {
+            Network::Mainnet => XPUB_VERSION,
+            Network::Testnet10 | Network::Testnet11 | Network::Simnet => TPUB_VERSION,
+        };
+        self.serialize(version, &self.public_key.serialize())
+    }
+
+    /// Parse an xprv or tprv string. Testnet, testnet-11 and simnet share
+    /// the tprv version, so those are reported as `Testnet10`.
+    pub fn from_xprv(xprv: &str) -> Result<(Self, Network), HdError> {
+        let (version, key) = Self::deserialize(xprv)?;
+        let network = match version {
+            XPRV_VERSION => Network::Mainnet,
+            TPRV_VERSION => Network::Testnet10,
+            _ => return Err(HdError::InvalidExtendedKey("not an xprv".to_string())),
+        };
+        if key.key[0] != 0 {
+            return Err(HdError::InvalidExtendedKey(
+                "private key must be prefixed with 0x00".to_string(),
+            ));
+        }
+
+        let secret = PrivateKey::from_slice(&key.key[1..])
+            .map_err(|e| HdError::InvalidExtendedKey(e.to_string()))?;
+        let public = PublicKey::from_secret_key(secp(), &secret);
+
+        Ok((
+            Self {
+                keypair: Some(KeyPair::from_secret_and_public(secret, public)),
+                public_key: public,
+                chain_code: key.chain_code,
+                depth: key.depth,
+                parent_fingerprint: key.parent_fingerprint,
+                child_index: key.child_index,
+            },
+            network,
+        ))
+    }
+
+    /// Parse an xpub or tpub string into a public-only key.
+    pub fn from_xpub(xpub: &str) -> Result<(Self, Network), HdError> {
+        let (version, key) = Self::deserialize(xpub)?;
+        let network = match version {
+            XPUB_VERSION => Network::Mainnet,
+            TPUB_VERSION => Network::Testnet10,
+            _ => return Err(HdError::InvalidExtendedKey("not an xpub".to_string())),
+        };
+
+        let public = PublicKey::from_slice(&key.key)
+            .map_err(|e| HdError::InvalidExtendedKey(e.to_string()))?;
+
+        Ok((
+            Self {
+                keypair: None,
+                public_key: public,
+                chain_code: key.chain_code,
+                depth: key.depth,
+                parent_fingerprint: key.parent_fingerprint,
+                child_index: key.child_index,
+            },
+            network,
+        ))
+    }
+
+    fn serialize(&self, version: [u8; 4], key: &[u8; 33]) -> String {
+        let mut data = Vec::with_capacity(SERIALIZED_LEN + 4);
+        data.extend_from_slice(&version);
+        data.push(self.depth);
+        data.extend_from_slice(&self.parent_fingerprint);
+        data.extend_from_slice(&self.child_index.to_be_bytes());
+        data.extend_from_slice(&self.chain_code);
+        data.extend_from_slice(key);
+        let checksum = Sha256::digest(Sha256::digest(&data));
+        data.extend_from_slice(&checksum[..4]);
+        bs58::encode(data).into_string()
+    }
+
+    /// Split a base58check extended key into its version and fields, leaving
+    /// the 33 key bytes for the caller to interpret.
+    fn deserialize(encoded: &str) -> Result<([u8; 4], SerializedKey), HdError> {
+        let data = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| HdError::InvalidExtendedKey(e.to_string()))?;
+        if data.len() != SERIALIZED_LEN + 4 {
+            return Err(HdError::InvalidExtendedKey(format!(
+                "{} bytes, expected {}",
+                data.len(),
+                SERIALIZED_LEN + 4
+            )));
+        }
+
+        let (body, checksum) = data.split_at(SERIALIZED_LEN);
+        if Sha256::digest(Sha256::digest(body))[..4] != *checksum {
+            return Err(HdError::InvalidExtendedKey("bad checksum".to_string()));
+        }
+
+        let mut key = SerializedKey {
+            depth: body[4],
+            parent_fingerprint: [0u8; 4],
+            child_index: u32::from_be_bytes(body[9..13].try_into().unwrap()),
+            chain_code: [0u8; 32],
+            key: [0u8; 33],
+        };
+        key.parent_fingerprint.copy_from_slice(&body[5..9]);
+        key.chain_code.copy_from_slice(&body[13..45]);
+        key.key.copy_from_slice(&body[45..]);
+        if key.depth == 0 && (key.parent_fingerprint != [0u8; 4] || key.child_index != 0) {
+            return Err(HdError::InvalidExtendedKey(
+                "master key with a parent".to_string(),
+            ));
+        }
+
+        Ok((body[..4].try_into().unwrap(), key))
+    }
+
+    /// First 4 bytes of HASH160 (RIPEMD160 of SHA256) of the public key.
     fn calculate_fingerprint(&self) -> [u8; 4] {
-        let pubkey_bytes = self.keypair.public_key_bytes();
-        let hash = sha2::Sha512::digest(&pubkey_bytes);
+        let hash = Ripemd160::digest(Sha256::digest(self.public_key.serialize()));
         let mut fingerprint = [0u8; 4];
         fingerprint.copy_from_slice(&hash[..4]);
         fingerprint
     }
 }
 
+/// The child indexes of a path such as `m/44'/111111'/0'/0/7`.
+pub(crate) fn parse_path(path: &str) -> Result<Vec<u32>, HdError> {
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(HdError::InvalidPath);
+    }
+    segments.map(parse_path_segment).collect()
+}
+
+/// Child index of one path level, rejecting anything but digits with an
+/// optional hardened marker.
+fn parse_path_segment(segment: &str) -> Result<u32, HdError> {
+    let (number, hardened) = match segment.strip_suffix(['\'', 'h']) {
+        Some(number) => (number, true),
+        None => (segment, false),
+    };
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(HdError::InvalidPath);
+    }
+    let index: u32 = number.parse().map_err(|_| HdError::InvalidPath)?;
+    if index >= HARDENED_OFFSET {
+        return Err(HdError::InvalidPath);
+    }
+
+    let offset = if hardened { HARDENED_OFFSET } else { 0 };
+    Ok(index + offset)
+}
+
+struct SerializedKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_index: u32,
+    chain_code: [u8; 32],
+    /// 0x00 and the secret for a private key, the compressed point otherwise.
+    key: [u8; 33],
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +516,293 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bip32_vector_1() {
+        // BIP32 test vector 1
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+        assert_eq!(
+            master.keypair().to_hex(),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            hex::encode(master.chain_code()),
+            "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508"
+        );
+
+        let child = master.derive_path("m/0'").unwrap();
+        assert_eq!(
+            child.keypair().to_hex(),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+        assert_eq!(
+            hex::encode(child.chain_code()),
+            "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141"
+        );
+    }
+
+    #[test]
+    fn test_bip32_vector_1_chain() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+
+        for (path, secret) in [
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+            ),
+            (
+                "m/0'/1/2'",
+                "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+            ),
+            (
+                "m/0'/1/2'/2",
+                "0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4",
+            ),
+            (
+                "m/0'/1/2'/2/1000000000",
+                "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+            ),
+        ] {
+            assert_eq!(master.derive_path(path).unwrap().keypair().to_hex(), secret);
+        }
+    }
+
+    #[test]
+    fn test_xprv_xpub_vectors() {
+        for (seed, xprv, xpub) in [
+            (
+                "000102030405060708090a0b0c0d0e0f",
+                "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+                "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+            ),
+            (
+                "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+                "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+                "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+            ),
+        ] {
+            let master = ExtendedKey::from_seed(&hex::decode(seed).unwrap()).unwrap();
+            assert_eq!(master.to_xprv(Network::Mainnet).unwrap(), xprv);
+            assert_eq!(master.to_xpub(Network::Mainnet), xpub);
+
+            let (parsed, network) = ExtendedKey::from_xprv(xprv).unwrap();
+            assert_eq!(network, Network::Mainnet);
+            assert_eq!(parsed.keypair().to_hex(), master.keypair().to_hex());
+            assert_eq!(parsed.chain_code(), master.chain_code());
+
+            let (public, _) = ExtendedKey::from_xpub(xpub).unwrap();
+            assert!(!public.is_private());
+            assert_eq!(public.public_key(), master.public_key());
+            assert!(matches!(public.to_xprv(Network::Mainnet), Err(HdError::PublicOnly)));
+        }
+    }
+
+    #[test]
+    fn test_child_fingerprint_vector() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+        assert_eq!(hex::encode(master.calculate_fingerprint()), "3442193e");
+
+        let child = master.derive_child(HARDENED_OFFSET).unwrap();
+        assert_eq!(child.parent_fingerprint, [0x34, 0x42, 0x19, 0x3e]);
+        assert_eq!(
+            child.to_xprv(Network::Mainnet).unwrap(),
+            "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7"
+        );
+        assert_eq!(
+            child.to_xpub(Network::Mainnet),
+            "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"
+        );
+    }
+
+    #[test]
+    fn test_extended_key_roundtrip_and_errors() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let account = ExtendedKey::from_seed(&seed)
+            .unwrap()
+            .account_key(DerivationParams::default())
+            .unwrap();
+
+        let tprv = account.to_xprv(Network::Testnet10).unwrap();
+        assert!(tprv.starts_with("tprv"));
+        let (parsed, network) = ExtendedKey::from_xprv(&tprv).unwrap();
+        assert_eq!(network, Network::Testnet10);
+        assert_eq!(parsed.depth(), 3);
+        assert_eq!(parsed.child_index(), account.child_index());
+        assert_eq!(parsed.to_xprv(Network::Testnet10).unwrap(), tprv);
+
+        // Normal children of the public key match the private derivation
+        let (public, _) = ExtendedKey::from_xpub(&account.to_xpub(Network::Testnet10)).unwrap();
+        assert_eq!(
+            public.derive_child(5).unwrap().public_key(),
+            account.derive_child(5).unwrap().public_key()
+        );
+        assert!(matches!(
+            public.derive_child(HARDENED_OFFSET),
+            Err(HdError::HardenedRequiresPrivate)
+        ));
+
+        // The xpub carries no secret, and neither parser takes the other's form
+        assert!(ExtendedKey::from_xprv(&account.to_xpub(Network::Mainnet)).is_err());
+        assert!(ExtendedKey::from_xpub(&tprv).is_err());
+
+        let mut corrupted = tprv.clone().into_bytes();
+        corrupted[20] = if corrupted[20] == b'a' { b'b' } else { b'a' };
+        assert!(matches!(
+            ExtendedKey::from_xprv(std::str::from_utf8(&corrupted).unwrap()),
+            Err(HdError::InvalidExtendedKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_derivation_params() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+        let derive = |params: DerivationParams, is_change, index| {
+            master
+                .account_key(params)
+                .and_then(|account| account.derive_leaf(params, is_change, index))
+                .unwrap()
+        };
+
+        let default = DerivationParams::default();
+        assert_eq!(
+            derive(default, false, 3).public_key(),
+            master
+                .derive_path("m/44'/111111'/0'/0/3")
+                .unwrap()
+                .public_key()
+        );
+
+        let account_1 = derive(DerivationParams::for_account(1), true, 2);
+        let by_path = master.derive_path("m/44'/111111'/1'/1/2").unwrap();
+        assert_eq!(account_1.public_key(), by_path.public_key());
+
+        let other_coin = DerivationParams {
+            coin_type: 972,
+            ..default
+        };
+        let by_path = master.derive_path("m/44'/972'/0'/0/0").unwrap();
+        assert_eq!(
+            derive(other_coin, false, 0).public_key(),
+            by_path.public_key()
+        );
+
+        assert!(matches!(
+            master.account_key(DerivationParams::for_account(HARDENED_OFFSET)),
+            Err(HdError::InvalidIndex)
+        ));
+
+        assert_eq!(
+            DerivationParams::from_path("m/44'/111111'/2'").unwrap(),
+            DerivationParams::for_account(2)
+        );
+        assert!(matches!(
+            DerivationParams::from_path("m/44'/972'/0'"),
+            Err(HdError::WrongCoinType(972))
+        ));
+        for malformed in [
+            "m/44'/111111'",
+            "m/44'/111111'/2",
+            "m/44'/111111'/2'/0",
+            "44'/111111'/2'",
+        ] {
+            assert!(matches!(
+                DerivationParams::from_path(malformed),
+                Err(HdError::InvalidPath)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_public_only_key_rejects_hardened_paths() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+        let public = master.neuter();
+
+        // Every standard path starts hardened
+        assert!(matches!(
+            public.account_key(DerivationParams::default()),
+            Err(HdError::HardenedRequiresPrivate)
+        ));
+        assert!(matches!(
+            public.derive_path("m/0/44'"),
+            Err(HdError::HardenedRequiresPrivate)
+        ));
+        assert!(matches!(public.try_keypair(), Err(HdError::PublicOnly)));
+
+        // Normal paths give the same public keys as from the private key
+        let child = public.derive_path("m/0/7").unwrap();
+        assert!(!child.is_private());
+        assert!(matches!(child.try_keypair(), Err(HdError::PublicOnly)));
+        let private_child = master.derive_path("m/0/7").unwrap();
+        assert_eq!(child.public_key(), private_child.public_key());
+        assert_eq!(
+            private_child.try_keypair().unwrap().to_hex(),
+            private_child.keypair().to_hex()
+        );
+    }
+
+    #[test]
+    fn test_neutered_key_derives_public_children() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let account = ExtendedKey::from_seed(&seed)
+            .unwrap()
+            .account_key(DerivationParams::default())
+            .unwrap();
+        let watch = account.neuter();
+
+        assert!(!watch.is_private());
+        assert!(matches!(
+            watch.to_xprv(Network::Mainnet),
+            Err(HdError::PublicOnly)
+        ));
+        assert_eq!(
+            watch.to_xpub(Network::Mainnet),
+            account.to_xpub(Network::Mainnet)
+        );
+        let params = DerivationParams::default();
+        for index in [0, 1, 19] {
+            let public = watch.derive_leaf(params, false, index).unwrap();
+            assert!(!public.is_private());
+            assert_eq!(
+                public.public_key(),
+                account
+                    .derive_leaf(params, false, index)
+                    .unwrap()
+                    .public_key()
+            );
+        }
+        // A public-only key can't derive hardened leaves
+        let hardened = DerivationParams {
+            hardened_leaves: true,
+            ..params
+        };
+        assert!(matches!(
+            watch.derive_leaf(hardened, false, 0),
+            Err(HdError::HardenedRequiresPrivate)
+        ));
+        assert!(matches!(
+            watch.derive_path("m/0'"),
+            Err(HdError::HardenedRequiresPrivate)
+        ));
+    }
+
+    #[test]
+    fn test_seed_length_validation() {
+        assert!(matches!(
+            ExtendedKey::from_seed(&[0u8; 15]),
+            Err(HdError::InvalidSeedLength(15))
+        ));
+        assert!(matches!(
+            ExtendedKey::from_seed(&[0u8; 65]),
+            Err(HdError::InvalidSeedLength(65))
+        ));
+        assert!(ExtendedKey::from_seed(&[1u8; 16]).is_ok());
+        assert!(ExtendedKey::from_seed(&[1u8; 64]).is_ok());
+    }
+
     #[test]
     fn test_path_derivation() {
         let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
@@ -245,6 +811,51 @@ mod tests {
         let child = master.derive_path("m/0/1").unwrap();
         assert_eq!(child.depth(), 2);
         assert_eq!(child.child_index(), 1);
+
+        let account_3 = master.derive_path("m/44'/111111'/3'/0/7").unwrap();
+        let by_hand = [
+            44 + HARDENED_OFFSET,
+            111111 + HARDENED_OFFSET,
+            3 + HARDENED_OFFSET,
+            0,
+            7,
+        ]
+        .into_iter()
+        .try_fold(master.clone(), |key, index| key.derive_child(index))
+        .unwrap();
+        assert_eq!(account_3.public_key(), by_hand.public_key());
+        assert_eq!(
+            master
+                .derive_path("m/44h/111111h/3h/0/7")
+                .unwrap()
+                .public_key(),
+            by_hand.public_key()
+        );
+        assert_eq!(
+            master.derive_path("m").unwrap().public_key(),
+            master.public_key()
+        );
+
+        for malformed in [
+            "",
+            "44'/0",
+            "M/0",
+            "m0/1",
+            "m/",
+            "m//0",
+            "m/x",
+            "m/-1",
+            "m/+1",
+            "m/1''",
+            "m/2147483648",
+            "m/2147483648'",
+        ] {
+            assert!(
+                matches!(master.derive_path(malformed), Err(HdError::InvalidPath)),
+                "{} was accepted",
+                malformed
+            );
+        }
     }
 
     #[test]
@@ -252,8 +863,10 @@ mod tests {
         let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
         let master = ExtendedKey::from_seed(&seed).unwrap();
 
-        let addr0 = master.derive_address_index(0).unwrap();
-        let addr1 = master.derive_address_index(1).unwrap();
+        let params = DerivationParams::default();
+        let account = master.account_key(params).unwrap();
+        let addr0 = account.derive_leaf(params, false, 0).unwrap();
+        let addr1 = account.derive_leaf(params, false, 1).unwrap();
 
         assert_ne!(
             addr0.keypair().to_hex(),
@@ -262,14 +875,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_account_key_matches_full_path() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+        let params = DerivationParams::default();
+        let account = master.account_key(params).unwrap();
+        assert_eq!(account.depth(), 3);
+        assert_eq!(
+            account.keypair().to_hex(),
+            master
+                .derive_path("m/44'/111111'/0'")
+                .unwrap()
+                .keypair()
+                .to_hex()
+        );
+
+        for index in [0, 1, 7, 1000] {
+            for (is_change, change) in [(false, 0), (true, 1)] {
+                let path = format!("m/44'/111111'/0'/{}/{}", change, index);
+                assert_eq!(
+                    account
+                        .derive_leaf(params, is_change, index)
+                        .unwrap()
+                        .keypair()
+                        .to_hex(),
+                    master.derive_path(&path).unwrap().keypair().to_hex()
+                );
+            }
+        }
+        assert!(matches!(
+            account.derive_leaf(params, false, HARDENED_OFFSET),
+            Err(HdError::InvalidIndex)
+        ));
+    }
+
     #[test]
     fn test_deterministic_derivation() {
         let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
         let master1 = ExtendedKey::from_seed(&seed).unwrap();
         let master2 = ExtendedKey::from_seed(&seed).unwrap();
 
-        let addr1 = master1.derive_address_index(5).unwrap();
-        let addr2 = master2.derive_address_index(5).unwrap();
+        let params = DerivationParams::default();
+        let derive = |master: &ExtendedKey| {
+            master
+                .account_key(params)
+                .and_then(|account| account.derive_leaf(params, false, 5))
+                .unwrap()
+        };
+        let addr1 = derive(&master1);
+        let addr2 = derive(&master2);
 
         assert_eq!(
             addr1.keypair().to_hex(),
@@ -277,4 +932,39 @@ mod tests {
             "Same seed and index should produce same key"
         );
     }
+
+    #[test]
+    fn test_hardened_leaf_derivation() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed).unwrap();
+        let standard = DerivationParams::default();
+        let hardened = DerivationParams {
+            hardened_leaves: true,
+            ..standard
+        };
+        let account = master.account_key(hardened).unwrap();
+        // Only the leaves differ, so both share the account node
+        assert_eq!(
+            account.public_key(),
+            master.account_key(standard).unwrap().public_key()
+        );
+
+        for is_change in [false, true] {
+            for index in 0..3 {
+                let plain = account.derive_leaf(standard, is_change, index).unwrap();
+                let leaf = account.derive_leaf(hardened, is_change, index).unwrap();
+                let again = account.derive_leaf(hardened, is_change, index).unwrap();
+
+                assert_ne!(plain.keypair().to_hex(), leaf.keypair().to_hex());
+                assert_eq!(leaf.keypair().to_hex(), again.keypair().to_hex());
+                assert_eq!(leaf.child_index(), index + HARDENED_OFFSET);
+            }
+        }
+
+        let by_path = master.derive_path("m/44'/111111'/0'/1'/4'").unwrap();
+        assert_eq!(
+            account.derive_leaf(hardened, true, 4).unwrap().public_key(),
+            by_path.public_key()
+        );
+    }
 }
@@ -12,8 +12,12 @@ use kaspa_consensus_core::tx::{
     TransactionOutpoint, TransactionOutput, UtxoEntry,
 };
 use kaspa_txscript::pay_to_address_script;
-use secp256k1::{Message, Secp256k1};
-use serde::Serialize;
+use secp256k1::constants::{SCHNORR_PUBLIC_KEY_SIZE, SCHNORR_SIGNATURE_SIZE};
+use secp256k1::Message;
+use serde::{Deserialize, Serialize};
+
+use super::key::{secp, KeyPair};
+use super::transaction::{dust_threshold, is_dust, P2PK_SCRIPT_LEN};
 
 const SIG_HASH_ALL_U8: u8 = 0x01;
 
@@ -21,7 +25,10 @@ const MASS_PER_TX_BYTE: u64 = 1;
 const MASS_PER_SCRIPT_PUB_KEY_BYTE: u64 = 10;
 const MASS_PER_SIG_OP: u64 = 1000;
 
-fn compute_transaction_mass(tx: &Transaction) -> u64 {
+/// OP_DATA_65 + 64-byte signature + 1-byte sighash type.
+pub(super) const SCHNORR_SIGNATURE_SCRIPT_LEN: usize = 1 + SCHNORR_SIGNATURE_SIZE + 1;
+
+pub(super) fn compute_transaction_mass(tx: &Transaction) -> u64 {
     let mut size: u64 = 0;
     size += 2;
     size += 8;
@@ -61,6 +68,24 @@ fn compute_transaction_mass(tx: &Transaction) -> u64 {
     compute_mass_for_size + total_script_pub_key_mass + total_sigops_mass
 }
 
+/// Mass one signed P2PK input adds to a transaction, as
+/// `compute_transaction_mass` counts it, for selecting inputs before the
+/// transaction exists.
+pub fn input_mass() -> u64 {
+    let empty = KaspaTransactionSigner::new();
+    let mut one = KaspaTransactionSigner::new();
+    one.transaction.inputs.push(TransactionInput {
+        previous_outpoint: TransactionOutpoint {
+            transaction_id: TransactionId::from_bytes([0; 32]),
+            index: 0,
+        },
+        signature_script: Vec::new(),
+        sequence: 0,
+        sig_op_count: 1,
+    });
+    one.estimate_mass() - empty.estimate_mass()
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct JsonTransactionInput {
     #[serde(rename = "previousOutpoint")]
@@ -109,6 +134,56 @@ pub struct JsonTransaction {
     pub mass: u64,
 }
 
+impl JsonTransaction {
+    /// The REST API's JSON form of `tx`.
+    pub fn from_transaction(tx: &Transaction) -> Self {
+        let inputs = tx
+            .inputs
+            .iter()
+            .map(|input| JsonTransactionInput {
+                previous_outpoint: JsonOutPoint {
+                    transaction_id: hex::encode(input.previous_outpoint.transaction_id.as_bytes()),
+                    index: input.previous_outpoint.index,
+                },
+                signature_script: hex::encode(&input.signature_script),
+                sequence: input.sequence,
+                sig_op_count: input.sig_op_count,
+            })
+            .collect();
+
+        let outputs = tx
+            .outputs
+            .iter()
+            .map(|output| JsonTransactionOutput {
+                amount: output.value,
+                script_public_key: JsonScriptPublicKey {
+                    version: output.script_public_key.version(),
+                    script: hex::encode(output.script_public_key.script()),
+                },
+            })
+            .collect();
+
+        JsonTransaction {
+            version: tx.version as u32,
+            inputs,
+            outputs,
+            lock_time: tx.lock_time,
+            subnetwork_id: format!("{}", tx.subnetwork_id),
+            gas: 0,
+            payload: hex::encode(&tx.payload),
+            mass: compute_transaction_mass(tx),
+        }
+    }
+
+    /// Decode a borsh-serialized transaction, as in [`KaspaSignedTransaction::hex`].
+    pub fn from_hex(tx_hex: &str) -> Result<Self, String> {
+        let bytes = hex::decode(tx_hex).map_err(|e| format!("Invalid hex: {}", e))?;
+        let tx = <Transaction as borsh::BorshDeserialize>::try_from_slice(&bytes)
+            .map_err(|e| format!("Invalid transaction bytes: {}", e))?;
+        Ok(Self::from_transaction(&tx))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KaspaSignedTransaction {
     pub tx_hex: String,
@@ -130,6 +205,108 @@ impl KaspaSignedTransaction {
     }
 }
 
+/// Produces Schnorr signatures over transaction sighashes.
+///
+/// Implement this to sign with a key held outside the process, such as a
+/// hardware wallet or remote signer.
+pub trait TxSigner {
+    fn sign_hash(&self, sighash: &[u8; 32]) -> Result<[u8; 64], String>;
+    fn xonly_pubkey(&self) -> [u8; 32];
+}
+
+impl<T: TxSigner + ?Sized> TxSigner for &T {
+    fn sign_hash(&self, sighash: &[u8; 32]) -> Result<[u8; 64], String> {
+        (**self).sign_hash(sighash)
+    }
+
+    fn xonly_pubkey(&self) -> [u8; 32] {
+        (**self).xonly_pubkey()
+    }
+}
+
+impl TxSigner for secp256k1::KeyPair {
+    fn sign_hash(&self, sighash: &[u8; 32]) -> Result<[u8; 64], String> {
+        let msg =
+            Message::from_slice(sighash).map_err(|e| format!("Failed to create message: {}", e))?;
+        Ok(*secp().sign_schnorr_no_aux_rand(&msg, self).as_ref())
+    }
+
+    fn xonly_pubkey(&self) -> [u8; 32] {
+        self.x_only_public_key().0.serialize()
+    }
+}
+
+impl TxSigner for KeyPair {
+    fn sign_hash(&self, sighash: &[u8; 32]) -> Result<[u8; 64], String> {
+        secp256k1::KeyPair::from_secret_key(secp(), self.secret_key()).sign_hash(sighash)
+    }
+
+    fn xonly_pubkey(&self) -> [u8; 32] {
+        self.public_key().x_only_public_key().0.serialize()
+    }
+}
+
+fn keypair_from_slice(private_key: &[u8]) -> Result<secp256k1::KeyPair, String> {
+    secp256k1::KeyPair::from_seckey_slice(secp(), private_key)
+        .map_err(|e| format!("Invalid private key: {}", e))
+}
+
+/// OP_DATA_32 <x-only pubkey> OP_CHECKSIG
+fn p2pk_script(xonly_pubkey: &[u8; 32]) -> Vec<u8> {
+    once(0x20)
+        .chain(xonly_pubkey.iter().copied())
+        .chain(once(0xac))
+        .collect()
+}
+
+/// A previous output to spend, with its script already decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerInput {
+    pub txid: String,
+    pub vout: u32,
+    pub amount: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// A transaction built but not yet signed, carrying everything an offline
+/// machine needs to recompute the sighashes. Byte fields are hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedTx {
+    pub version: u16,
+    pub inputs: Vec<UnsignedInput>,
+    pub outputs: Vec<UnsignedOutput>,
+    pub lock_time: u64,
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedInput {
+    pub txid: String,
+    pub vout: u32,
+    /// Amount of the UTXO being spent.
+    pub amount: u64,
+    /// Script of the UTXO being spent.
+    pub script_pubkey: String,
+    pub sequence: u64,
+    pub sig_op_count: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedOutput {
+    pub amount: u64,
+    pub script_version: u16,
+    pub script_pubkey: String,
+}
+
+/// Sign a transaction produced by [`KaspaTransactionSigner::build`], e.g. on
+/// an air-gapped machine.
+pub fn sign_unsigned<S: TxSigner + ?Sized>(
+    unsigned: &UnsignedTx,
+    signer: &S,
+) -> Result<KaspaSignedTransaction, String> {
+    KaspaTransactionSigner::from_unsigned(unsigned)?.sign_with(signer)
+}
+
 pub struct KaspaTransactionSigner {
     transaction: Transaction,
     utxos: Vec<UtxoEntry>,
@@ -187,6 +364,87 @@ impl KaspaTransactionSigner {
         Ok(())
     }
 
+    /// Snapshot the transaction built so far for signing elsewhere.
+    pub fn build(&self) -> UnsignedTx {
+        let inputs = self
+            .transaction
+            .inputs
+            .iter()
+            .zip(&self.utxos)
+            .map(|(input, utxo)| UnsignedInput {
+                txid: hex::encode(input.previous_outpoint.transaction_id.as_bytes()),
+                vout: input.previous_outpoint.index,
+                amount: utxo.amount,
+                script_pubkey: hex::encode(utxo.script_public_key.script()),
+                sequence: input.sequence,
+                sig_op_count: input.sig_op_count,
+            })
+            .collect();
+
+        let outputs = self
+            .transaction
+            .outputs
+            .iter()
+            .map(|output| UnsignedOutput {
+                amount: output.value,
+                script_version: output.script_public_key.version(),
+                script_pubkey: hex::encode(output.script_public_key.script()),
+            })
+            .collect();
+
+        UnsignedTx {
+            version: self.transaction.version,
+            inputs,
+            outputs,
+            lock_time: self.transaction.lock_time,
+            payload: hex::encode(&self.transaction.payload),
+        }
+    }
+
+    /// Rebuild a signer from an [`UnsignedTx`] serialized as JSON, the file
+    /// an online machine hands to an offline one.
+    pub fn from_unsigned_json(json: &str) -> Result<Self, String> {
+        let unsigned: UnsignedTx = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid unsigned transaction: {}", e))?;
+        Self::from_unsigned(&unsigned)
+    }
+
+    /// Rebuild a signer from the output of [`build`](Self::build).
+    pub fn from_unsigned(unsigned: &UnsignedTx) -> Result<Self, String> {
+        let mut signer = Self::new();
+        signer.transaction.version = unsigned.version;
+        signer.transaction.lock_time = unsigned.lock_time;
+
+        for input in &unsigned.inputs {
+            let script_pubkey = hex::decode(&input.script_pubkey)
+                .map_err(|e| format!("Invalid input script: {}", e))?;
+            signer.add_input(&input.txid, input.vout, input.amount, &script_pubkey)?;
+
+            let tx_input = signer.transaction.inputs.last_mut().unwrap();
+            tx_input.sequence = input.sequence;
+            tx_input.sig_op_count = input.sig_op_count;
+        }
+
+        for output in &unsigned.outputs {
+            let script = hex::decode(&output.script_pubkey)
+                .map_err(|e| format!("Invalid output script: {}", e))?;
+            signer.transaction.outputs.push(TransactionOutput {
+                value: output.amount,
+                script_public_key: ScriptPublicKey::new(output.script_version, script.into()),
+            });
+        }
+
+        let payload =
+            hex::decode(&unsigned.payload).map_err(|e| format!("Invalid payload: {}", e))?;
+        signer.set_payload(&payload);
+
+        Ok(signer)
+    }
+
+    pub fn add_signer_input(&mut self, input: &SignerInput) -> Result<(), String> {
+        self.add_input(&input.txid, input.vout, input.amount, &input.script_pubkey)
+    }
+
     pub fn add_output(&mut self, address: &str, amount: u64) -> Result<(), String> {
         let address = Address::try_from(address).map_err(|e| format!("Invalid address: {}", e))?;
         let script_pubkey = pay_to_address_script(&address);
@@ -205,30 +463,150 @@ impl KaspaTransactionSigner {
         self.transaction.payload = payload.to_vec();
     }
 
+    /// A DAA score, or a unix time in milliseconds at or above
+    /// `LOCK_TIME_THRESHOLD`. Enforced because inputs use sequence 0.
+    pub fn set_lock_time(&mut self, lock_time: u64) {
+        self.transaction.lock_time = lock_time;
+    }
+
+    /// Mass of the transaction once every input carries a Schnorr signature
+    /// script, so fees can be computed before signing.
+    pub fn estimate_mass(&self) -> u64 {
+        let mut tx = self.transaction.clone();
+        for input in tx.inputs.iter_mut() {
+            input.signature_script = vec![0u8; SCHNORR_SIGNATURE_SCRIPT_LEN];
+        }
+        compute_transaction_mass(&tx)
+    }
+
+    /// Check that every input of `signed`, a transaction signed from this
+    /// signer, carries a valid signature by the key of the P2PK script it spends.
+    pub fn verify(&self, signed: &KaspaSignedTransaction) -> Result<(), String> {
+        let inputs = &signed.json().inputs;
+        if inputs.len() != self.utxos.len() {
+            return Err(format!(
+                "Expected {} inputs, found {}",
+                self.utxos.len(),
+                inputs.len()
+            ));
+        }
+
+        let verifiable =
+            MutableTransaction::with_entries(self.transaction.clone(), self.utxos.clone());
+        let reused_values = SigHashReusedValuesUnsync::new();
+
+        for (i, input) in inputs.iter().enumerate() {
+            let script = hex::decode(&input.signature_script)
+                .map_err(|e| format!("Invalid signature script: {}", e))?;
+            let script_pubkey = self.utxos[i].script_public_key.script();
+            if script.len() != SCHNORR_SIGNATURE_SCRIPT_LEN
+                || script_pubkey.len() != P2PK_SCRIPT_LEN
+            {
+                return Err(format!("Input {} is not a P2PK spend", i));
+            }
+
+            let sig_hash = calc_schnorr_signature_hash(
+                &verifiable.as_verifiable(),
+                i,
+                SIG_HASH_ALL,
+                &reused_values,
+            );
+            let msg = Message::from_slice(sig_hash.as_bytes().as_slice())
+                .map_err(|e| format!("Failed to create message: {}", e))?;
+            let sig =
+                secp256k1::schnorr::Signature::from_slice(&script[1..=SCHNORR_SIGNATURE_SIZE])
+                    .map_err(|e| format!("Invalid signature on input {}: {}", i, e))?;
+            let pubkey =
+                secp256k1::XOnlyPublicKey::from_slice(&script_pubkey[1..=SCHNORR_PUBLIC_KEY_SIZE])
+                    .map_err(|e| format!("Invalid public key on input {}: {}", i, e))?;
+
+            secp()
+                .verify_schnorr(&sig, &msg, &pubkey)
+                .map_err(|_| format!("Signature on input {} does not verify", i))?;
+        }
+
+        Ok(())
+    }
+
+    /// A transaction without inputs would otherwise sign "successfully" and
+    /// only be rejected by the node.
+    fn check_inputs(&self) -> Result<(), String> {
+        if self.transaction.inputs.is_empty() {
+            return Err("Transaction has no inputs".to_string());
+        }
+        Ok(())
+    }
+
+    /// Outputs are only checked when signing, so fee estimates can use
+    /// placeholder amounts.
+    fn check_dust(&self) -> Result<(), String> {
+        match self
+            .transaction
+            .outputs
+            .iter()
+            .position(|output| is_dust(output.value, output.script_public_key.script().len()))
+        {
+            Some(i) => {
+                let output = &self.transaction.outputs[i];
+                Err(format!(
+                    "Output {} of {} sompi is below the dust threshold of {} sompi",
+                    i,
+                    output.value,
+                    dust_threshold(output.script_public_key.script().len())
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
     pub fn sign(&mut self, private_key: &[u8]) -> Result<KaspaSignedTransaction, String> {
-        let secp = Secp256k1::new();
-        let keypair = secp256k1::KeyPair::from_seckey_slice(&secp, private_key)
-            .map_err(|e| format!("Invalid private key: {}", e))?;
+        self.sign_with(&keypair_from_slice(private_key)?)
+    }
 
-        let (xonly_pubkey, _) = keypair.x_only_public_key();
-        let pubkey_bytes: [u8; 32] = xonly_pubkey.serialize();
+    /// Sign with several raw private keys, matching each input to the key
+    /// whose address it spends.
+    pub fn sign_with_keys(
+        &mut self,
+        private_keys: &[&[u8]],
+    ) -> Result<KaspaSignedTransaction, String> {
+        let keypairs = private_keys
+            .iter()
+            .map(|key| keypair_from_slice(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.sign_with_signers(&keypairs)
+    }
+
+    /// Like [`sign`](Self::sign), but every input is signed by `signer`, which
+    /// may keep its key outside this process.
+    pub fn sign_with<S: TxSigner + ?Sized>(
+        &mut self,
+        signer: &S,
+    ) -> Result<KaspaSignedTransaction, String> {
+        self.sign_with_signers(&[signer])
+    }
 
-        eprintln!("DEBUG: X-only public key: {}", hex::encode(&pubkey_bytes));
+    /// Sign inputs locked to different keys, such as UTXOs spread across HD
+    /// wallet addresses. Each input is matched to the signer whose P2PK script
+    /// it spends; an input no signer can spend is an error.
+    pub fn sign_with_signers<S: TxSigner>(
+        &mut self,
+        signers: &[S],
+    ) -> Result<KaspaSignedTransaction, String> {
+        self.check_inputs()?;
+        self.check_dust()?;
+
+        // Map each signer's P2PK script to the signer
+        let mut map: BTreeMap<Vec<u8>, &S> = BTreeMap::new();
+        for signer in signers {
+            let pubkey_bytes = signer.xonly_pubkey();
+            log::trace!("X-only public key: {}", hex::encode(pubkey_bytes));
+            map.insert(p2pk_script(&pubkey_bytes), signer);
+        }
 
         // Create signable transaction with UTXO entries
         let mut signable_tx =
             MutableTransaction::with_entries(self.transaction.clone(), self.utxos.clone());
 
-        // Create map from script pubkey to keypair (using x-only pubkey from keypair)
-        let mut map: BTreeMap<Vec<u8>, secp256k1::KeyPair> = BTreeMap::new();
-        // Get x-only public key directly from keypair (this is the correct Kaspa way)
-        let schnorr_public_key = keypair.public_key().x_only_public_key().0;
-        let script_pub_key_script: Vec<u8> = once(0x20)
-            .chain(schnorr_public_key.serialize().into_iter())
-            .chain(once(0xac))
-            .collect();
-        map.insert(script_pub_key_script, keypair);
-
         let reused_values = SigHashReusedValuesUnsync::new();
 
         // Sign each input (same as kaswallet's sign_with_multiple)
@@ -239,7 +617,7 @@ impl KaspaTransactionSigner {
                 .script_public_key
                 .script();
 
-            if let Some(schnorr_key) = map.get(script) {
+            if let Some(signer) = map.get(script) {
                 // Calculate sighash using Kaspa's official function
                 let sig_hash = calc_schnorr_signature_hash(
                     &signable_tx.as_verifiable(),
@@ -248,23 +626,18 @@ impl KaspaTransactionSigner {
                     &reused_values,
                 );
 
-                eprintln!("DEBUG: Sighash {}: {}", i, hex::encode(sig_hash.as_bytes()));
-
-                // Create message from sighash using from_slice
-                let msg = secp256k1::Message::from_slice(sig_hash.as_bytes().as_slice())
-                    .map_err(|e| format!("Failed to create message: {}", e))?;
+                log::trace!("Sighash {}: {}", i, hex::encode(sig_hash.as_bytes()));
 
-                // Sign with Schnorr using Secp256k1 context
-                let sig: [u8; 64] = *secp.sign_schnorr_no_aux_rand(&msg, &schnorr_key).as_ref();
+                let sig = signer.sign_hash(&sig_hash.as_bytes())?;
 
                 // Build signature script: OP_DATA_65 + 64-byte signature + 1-byte sighash
-                let signature_script: Vec<u8> = once(65u8)
+                let signature_script: Vec<u8> = once(SCHNORR_SIGNATURE_SIZE as u8 + 1)
                     .chain(sig.iter().copied())
                     .chain([SIG_HASH_ALL.to_u8()])
                     .collect();
 
-                eprintln!(
-                    "DEBUG: Signature script ({} bytes): {}",
+                log::trace!(
+                    "Signature script ({} bytes): {}",
                     signature_script.len(),
                     hex::encode(&signature_script)
                 );
@@ -276,56 +649,20 @@ impl KaspaTransactionSigner {
             }
         }
 
-        // Serialize transaction using borsh
+        // The cached ID is part of the borsh encoding, so finalize first
+        signable_tx.tx.finalize();
         let mut serialized = Vec::new();
         borsh::BorshSerialize::serialize(&signable_tx.tx, &mut serialized)
             .map_err(|e| format!("Serialization error: {}", e))?;
-        let tx_hex = hex::encode(serialized.clone());
+        let tx_hex = hex::encode(serialized);
 
-        // Calculate transaction ID by finalizing the transaction
-        let mut tx_final = signable_tx.tx.clone();
-        tx_final.finalize();
-        let tx_id = tx_final.id();
-        let tx_id_hex = hex::encode(tx_id.as_bytes());
+        let tx_id_hex = hex::encode(signable_tx.tx.id().as_bytes());
 
-        eprintln!("DEBUG: Signed tx ID: {}", tx_id_hex);
-        eprintln!("DEBUG: Signed tx hex length: {}", tx_hex.len());
+        log::debug!("Signed tx ID: {}", tx_id_hex);
+        log::debug!("Signed tx hex length: {}", tx_hex.len());
 
         // Build JSON transaction for API submission
-        let mut json_inputs = Vec::new();
-        for input in &signable_tx.tx.inputs {
-            json_inputs.push(JsonTransactionInput {
-                previous_outpoint: JsonOutPoint {
-                    transaction_id: hex::encode(input.previous_outpoint.transaction_id.as_bytes()),
-                    index: input.previous_outpoint.index,
-                },
-                signature_script: hex::encode(&input.signature_script),
-                sequence: input.sequence,
-                sig_op_count: input.sig_op_count,
-            });
-        }
-
-        let mut json_outputs = Vec::new();
-        for output in &signable_tx.tx.outputs {
-            json_outputs.push(JsonTransactionOutput {
-                amount: output.value,
-                script_public_key: JsonScriptPublicKey {
-                    version: output.script_public_key.version(),
-                    script: hex::encode(output.script_public_key.script()),
-                },
-            });
-        }
-
-        let json_tx = JsonTransaction {
-            version: signable_tx.tx.version as u32,
-            inputs: json_inputs,
-            outputs: json_outputs,
-            lock_time: signable_tx.tx.lock_time,
-            subnetwork_id: format!("{}", signable_tx.tx.subnetwork_id),
-            gas: 0,
-            payload: hex::encode(&signable_tx.tx.payload),
-            mass: compute_transaction_mass(&signable_tx.tx),
-        };
+        let json_tx = JsonTransaction::from_transaction(&signable_tx.tx);
 
         Ok(KaspaSignedTransaction {
             tx_hex,
@@ -338,140 +675,323 @@ impl KaspaTransactionSigner {
         &mut self,
         private_key: &[u8],
     ) -> Result<KaspaSignedTransaction, String> {
-        let secp = Secp256k1::new();
-        let keypair = secp256k1::KeyPair::from_seckey_slice(&secp, private_key)
-            .map_err(|e| format!("Invalid private key: {}", e))?;
+        self.sign_no_payload_with(&keypair_from_slice(private_key)?)
+    }
 
-        let (xonly_pubkey, _) = keypair.x_only_public_key();
-        let pubkey_bytes: [u8; 32] = xonly_pubkey.serialize();
+    /// Like [`sign_no_payload`](Self::sign_no_payload), using `signer` for
+    /// every input. An input locked to another script is an error.
+    pub fn sign_no_payload_with<S: TxSigner + ?Sized>(
+        &mut self,
+        signer: &S,
+    ) -> Result<KaspaSignedTransaction, String> {
+        self.sign_with(signer)
+    }
+}
 
-        eprintln!(
-            "DEBUG: X-only public key (transfer): {}",
-            hex::encode(&pubkey_bytes)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::DUST_THRESHOLD;
+
+    /// A signer spending one 100_000 sompi input locked to `key`, with the
+    /// key's x-only pubkey and testnet address.
+    fn test_signer_with_input(key: &[u8]) -> (KaspaTransactionSigner, [u8; 32], Address) {
+        let xonly = keypair_from_slice(key).unwrap().xonly_pubkey();
+        let address = Address::new(
+            kaspa_addresses::Prefix::Testnet,
+            kaspa_addresses::Version::PubKey,
+            &xonly,
         );
+        let mut signer = KaspaTransactionSigner::new();
+        signer.add_input(&"11".repeat(32), 0, 100_000, &p2pk_script(&xonly)).unwrap();
+        (signer, xonly, address)
+    }
 
-        let mut signable_tx =
-            MutableTransaction::with_entries(self.transaction.clone(), self.utxos.clone());
+    #[test]
+    fn test_signer_creation() {
+        let signer = KaspaTransactionSigner::new();
+        assert!(signer.transaction.inputs.is_empty());
+        assert!(signer.transaction.outputs.is_empty());
+    }
 
-        let mut map: BTreeMap<Vec<u8>, secp256k1::KeyPair> = BTreeMap::new();
-        let schnorr_public_key = keypair.public_key().x_only_public_key().0;
-        let script_pub_key_script: Vec<u8> = once(0x20)
-            .chain(schnorr_public_key.serialize().into_iter())
-            .chain(once(0xac))
-            .collect();
-        map.insert(script_pub_key_script, keypair);
+    #[test]
+    fn test_signing_path_has_no_stderr_output() {
+        // Key material must go through `log`, never straight to stderr
+        let forbidden = concat!("eprint", "ln!");
+        for (name, source) in [
+            ("kaspa_signer.rs", include_str!("kaspa_signer.rs")),
+            ("transaction.rs", include_str!("transaction.rs")),
+        ] {
+            assert!(!source.contains(forbidden), "{} writes to stderr", name);
+        }
+    }
 
-        let reused_values = SigHashReusedValuesUnsync::new();
+    #[test]
+    fn test_add_signer_input() {
+        let input = SignerInput {
+            txid: "ab".repeat(32),
+            vout: 3,
+            amount: 42_000,
+            script_pubkey: vec![0x20, 0x01, 0xac],
+        };
 
-        for i in 0..signable_tx.tx.inputs.len() {
-            let script = signable_tx.entries[i]
-                .as_ref()
-                .unwrap()
-                .script_public_key
-                .script();
+        let mut signer = KaspaTransactionSigner::new();
+        signer.add_signer_input(&input).unwrap();
 
-            if let Some(schnorr_key) = map.get(script) {
-                let sig_hash = calc_schnorr_signature_hash(
-                    &signable_tx.as_verifiable(),
-                    i,
-                    SIG_HASH_ALL,
-                    &reused_values,
-                );
+        let tx_input = &signer.transaction.inputs[0];
+        assert_eq!(hex::encode(tx_input.previous_outpoint.transaction_id.as_bytes()), input.txid);
+        assert_eq!(tx_input.previous_outpoint.index, 3);
+        assert_eq!(signer.utxos[0].amount, 42_000);
+        assert_eq!(signer.utxos[0].script_public_key.script(), input.script_pubkey.as_slice());
+    }
 
-                eprintln!(
-                    "DEBUG: Sighash {} (transfer): {}",
-                    i,
-                    hex::encode(sig_hash.as_bytes())
-                );
+    #[test]
+    fn test_estimate_mass_matches_signed_mass() {
+        let private_key =
+            hex::decode("1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac").unwrap();
+        let (mut signer, xonly, address) = test_signer_with_input(&private_key);
+        signer.add_input(&"22".repeat(32), 1, 100_000, &p2pk_script(&xonly)).unwrap();
+        signer.add_output(&address.to_string(), 150_000).unwrap();
+        signer.set_payload(&[0u8; 300]);
+
+        let estimated = signer.estimate_mass();
+        let signed = signer.sign(&private_key).unwrap();
+        assert_eq!(estimated, signed.json().mass);
+
+        // One input fewer is `input_mass` lighter
+        let (mut fewer, _, _) = test_signer_with_input(&private_key);
+        fewer.add_output(&address.to_string(), 150_000).unwrap();
+        fewer.set_payload(&[0u8; 300]);
+        assert_eq!(estimated - fewer.estimate_mass(), input_mass());
+    }
 
-                let msg = secp256k1::Message::from_slice(sig_hash.as_bytes().as_slice())
-                    .map_err(|e| format!("Failed to create message: {}", e))?;
+    struct FixedSigner {
+        pubkey: [u8; 32],
+    }
 
-                let sig: [u8; 64] = *secp.sign_schnorr_no_aux_rand(&msg, &schnorr_key).as_ref();
+    impl TxSigner for FixedSigner {
+        fn sign_hash(&self, _sighash: &[u8; 32]) -> Result<[u8; 64], String> {
+            Ok([0x5a; 64])
+        }
 
-                let signature_script: Vec<u8> = once(65u8)
-                    .chain(sig.iter().copied())
-                    .chain([SIG_HASH_ALL.to_u8()])
-                    .collect();
+        fn xonly_pubkey(&self) -> [u8; 32] {
+            self.pubkey
+        }
+    }
 
-                eprintln!(
-                    "DEBUG: Signature script ({} bytes): {}",
-                    signature_script.len(),
-                    hex::encode(&signature_script)
-                );
+    #[test]
+    fn test_sign_with_external_signer() {
+        let signer = FixedSigner { pubkey: [0x02; 32] };
+        let script_pubkey = p2pk_script(&signer.pubkey);
+        let address = Address::new(
+            kaspa_addresses::Prefix::Testnet,
+            kaspa_addresses::Version::PubKey,
+            &signer.pubkey,
+        );
 
-                signable_tx.tx.inputs[i].signature_script = signature_script;
-                signable_tx.tx.inputs[i].sig_op_count = 1;
-            }
-        }
+        let mut tx = KaspaTransactionSigner::new();
+        tx.add_input(&"11".repeat(32), 0, 100_000, &script_pubkey).unwrap();
+        tx.add_input(&"22".repeat(32), 1, 100_000, &script_pubkey).unwrap();
+        tx.add_output(&address.to_string(), 150_000).unwrap();
 
-        signable_tx.tx.finalize();
-        let mut serialized = Vec::new();
-        borsh::BorshSerialize::serialize(&signable_tx.tx, &mut serialized)
-            .map_err(|e| format!("Serialization error: {}", e))?;
-        let tx_hex = hex::encode(serialized.clone());
+        let signed = tx.sign_with(&signer).unwrap();
+        let expected = format!("41{}01", "5a".repeat(64));
+        for input in &signed.json().inputs {
+            assert_eq!(input.signature_script, expected);
+        }
 
-        let tx_id = signable_tx.tx.id();
-        let tx_id_hex = hex::encode(tx_id.as_bytes());
+        // Inputs locked to another key are rejected
+        let other = FixedSigner { pubkey: [0x03; 32] };
+        assert!(tx.sign_with(&other).is_err());
+    }
 
-        eprintln!("DEBUG: Signed tx ID (transfer): {}", tx_id_hex);
-        eprintln!("DEBUG: Signed tx hex length (transfer): {}", tx_hex.len());
+    #[test]
+    fn test_keypair_signer_matches_raw_key() {
+        let keypair = KeyPair::from_hex(
+            "1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac",
+        )
+        .unwrap();
+        let (mut tx, _, _) = test_signer_with_input(&keypair.to_bytes());
+        tx.set_payload(b"hello");
+
+        let from_bytes = tx.sign(&keypair.to_bytes()).unwrap();
+        let from_trait = tx.sign_with(&keypair).unwrap();
+        assert_eq!(from_bytes.id(), from_trait.id());
+        assert_eq!(from_bytes.hex(), from_trait.hex());
+    }
 
-        let mut json_inputs = Vec::new();
-        for input in &signable_tx.tx.inputs {
-            json_inputs.push(JsonTransactionInput {
-                previous_outpoint: JsonOutPoint {
-                    transaction_id: hex::encode(input.previous_outpoint.transaction_id.as_bytes()),
-                    index: input.previous_outpoint.index,
-                },
-                signature_script: hex::encode(&input.signature_script),
-                sequence: input.sequence,
-                sig_op_count: input.sig_op_count,
-            });
+    #[test]
+    fn test_sign_inputs_with_distinct_keys() {
+        let key_a =
+            hex::decode("1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac").unwrap();
+        let key_b = [0x42u8; 32];
+        let (mut tx, xonly_a, address) = test_signer_with_input(&key_a);
+        let xonly_b = keypair_from_slice(&key_b).unwrap().xonly_pubkey();
+        tx.add_input(&"22".repeat(32), 1, 100_000, &p2pk_script(&xonly_b)).unwrap();
+        tx.add_output(&address.to_string(), 190_000).unwrap();
+
+        let signed = tx.sign_with_keys(&[&key_b, &key_a]).unwrap();
+
+        // Each signature must verify against the key of the input it spends
+        let verifiable = MutableTransaction::with_entries(tx.transaction.clone(), tx.utxos.clone());
+        let reused_values = SigHashReusedValuesUnsync::new();
+        for (i, xonly) in [xonly_a, xonly_b].iter().enumerate() {
+            let sig_hash =
+                calc_schnorr_signature_hash(&verifiable.as_verifiable(), i, SIG_HASH_ALL, &reused_values);
+            let msg = Message::from_slice(sig_hash.as_bytes().as_slice()).unwrap();
+            let script = hex::decode(&signed.json().inputs[i].signature_script).unwrap();
+            let sig = secp256k1::schnorr::Signature::from_slice(&script[1..65]).unwrap();
+            let pubkey = secp256k1::XOnlyPublicKey::from_slice(xonly).unwrap();
+            assert!(secp().verify_schnorr(&sig, &msg, &pubkey).is_ok(), "input {}", i);
         }
 
-        let mut json_outputs = Vec::new();
-        for output in &signable_tx.tx.outputs {
-            json_outputs.push(JsonTransactionOutput {
-                amount: output.value,
-                script_public_key: JsonScriptPublicKey {
-                    version: output.script_public_key.version(),
-                    script: hex::encode(output.script_public_key.script()),
-                },
-            });
+        // Without key B the second input can't be signed
+        let err = tx.sign_with_keys(&[&key_a]).unwrap_err();
+        assert_eq!(err, "No key found for input 1");
+    }
+
+    #[test]
+    fn test_lock_time_is_signed() {
+        let key =
+            hex::decode("1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac").unwrap();
+        let (mut tx, xonly, address) = test_signer_with_input(&key);
+        let lock_time = 90_000_000;
+
+        tx.add_output(&address.to_string(), 90_000).unwrap();
+        let sighash = |tx: &KaspaTransactionSigner| {
+            let verifiable = MutableTransaction::with_entries(tx.transaction.clone(), tx.utxos.clone());
+            let reused_values = SigHashReusedValuesUnsync::new();
+            let sig_hash =
+                calc_schnorr_signature_hash(&verifiable.as_verifiable(), 0, SIG_HASH_ALL, &reused_values);
+            sig_hash
+        };
+        let unlocked = sighash(&tx);
+
+        tx.set_lock_time(lock_time);
+        let signed = tx.sign(&key).unwrap();
+        assert_eq!(signed.json().lock_time, lock_time);
+        assert_eq!(tx.build().lock_time, lock_time);
+
+        // The signature commits to the lock time
+        let locked = sighash(&tx);
+        assert_ne!(locked, unlocked);
+        let msg = Message::from_slice(locked.as_bytes().as_slice()).unwrap();
+        let script = hex::decode(&signed.json().inputs[0].signature_script).unwrap();
+        let sig = secp256k1::schnorr::Signature::from_slice(&script[1..65]).unwrap();
+        let pubkey = secp256k1::XOnlyPublicKey::from_slice(&xonly).unwrap();
+        assert!(secp().verify_schnorr(&sig, &msg, &pubkey).is_ok());
+    }
+
+    #[test]
+    fn test_verify() {
+        let key = [0x42u8; 32];
+        let (mut tx, xonly, address) = test_signer_with_input(&key);
+        tx.add_input(&"22".repeat(32), 1, 100_000, &p2pk_script(&xonly)).unwrap();
+        tx.add_output(&address.to_string(), 190_000).unwrap();
+        let mut signed = tx.sign(&key).unwrap();
+        assert_eq!(tx.verify(&signed), Ok(()));
+
+        // Swap in the other input's signature
+        let other = signed.json_tx.inputs[0].signature_script.clone();
+        signed.json_tx.inputs[1].signature_script = other;
+        assert_eq!(
+            tx.verify(&signed),
+            Err("Signature on input 1 does not verify".to_string())
+        );
+    }
+
+    #[test]
+    fn test_serialized_id_matches_signed_id() {
+        let key = [0x42u8; 32];
+        let (mut tx, _, address) = test_signer_with_input(&key);
+        tx.add_output(&address.to_string(), 90_000).unwrap();
+
+        for signed in [tx.sign(&key).unwrap(), tx.sign_no_payload(&key).unwrap()] {
+            let bytes = hex::decode(signed.hex()).unwrap();
+            let decoded = <Transaction as borsh::BorshDeserialize>::try_from_slice(&bytes).unwrap();
+            assert_eq!(hex::encode(decoded.id().as_bytes()), signed.id());
         }
+    }
 
-        let json_tx = JsonTransaction {
-            version: signable_tx.tx.version as u32,
-            inputs: json_inputs,
-            outputs: json_outputs,
-            lock_time: signable_tx.tx.lock_time,
-            subnetwork_id: format!("{}", signable_tx.tx.subnetwork_id),
-            gas: 0,
-            payload: String::new(),
-            mass: compute_transaction_mass(&signable_tx.tx),
+    #[test]
+    fn test_dust_outputs_rejected() {
+        let key = [0x42u8; 32];
+        let with_output = |amount: u64| {
+            let (mut tx, _, address) = test_signer_with_input(&key);
+            tx.add_output(&address.to_string(), 50_000).unwrap();
+            tx.add_output(&address.to_string(), amount).unwrap();
+            tx
         };
 
-        let json_tx_str = serde_json::to_string_pretty(&json_tx)
-            .map_err(|e| format!("Failed to serialize JSON tx: {}", e))?;
-        eprintln!("DEBUG: JSON transaction:\n{}", json_tx_str);
+        assert!(with_output(DUST_THRESHOLD).sign(&key).is_ok());
+        assert_eq!(
+            with_output(DUST_THRESHOLD - 1).sign(&key).unwrap_err(),
+            "Output 1 of 599 sompi is below the dust threshold of 600 sompi"
+        );
+        assert!(with_output(DUST_THRESHOLD - 1)
+            .sign_no_payload(&key)
+            .is_err());
+    }
 
-        Ok(KaspaSignedTransaction {
-            tx_hex,
-            tx_id: tx_id_hex,
-            json_tx,
-        })
+    #[test]
+    fn test_sign_no_payload_rejects_foreign_input() {
+        let (mut tx, _, address) = test_signer_with_input(&[0x42u8; 32]);
+        let other = keypair_from_slice(&[0x43u8; 32]).unwrap().xonly_pubkey();
+        tx.add_input(&"22".repeat(32), 0, 100_000, &p2pk_script(&other)).unwrap();
+        tx.add_output(&address.to_string(), 150_000).unwrap();
+
+        assert_eq!(
+            tx.sign_no_payload(&[0x42u8; 32]).unwrap_err(),
+            "No key found for input 1"
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_sign_without_inputs() {
+        let key = [0x42u8; 32];
+        let xonly = keypair_from_slice(&key).unwrap().xonly_pubkey();
+        let address = Address::new(
+            kaspa_addresses::Prefix::Testnet,
+            kaspa_addresses::Version::PubKey,
+            &xonly,
+        );
+        let mut tx = KaspaTransactionSigner::new();
+        tx.add_output(&address.to_string(), 50_000).unwrap();
+
+        assert_eq!(tx.sign(&key).unwrap_err(), "Transaction has no inputs");
+        assert_eq!(
+            tx.sign_no_payload(&key).unwrap_err(),
+            "Transaction has no inputs"
+        );
+    }
 
     #[test]
-    fn test_signer_creation() {
-        let signer = KaspaTransactionSigner::new();
-        assert!(signer.transaction.inputs.is_empty());
-        assert!(signer.transaction.outputs.is_empty());
+    fn test_unsigned_tx_roundtrip() {
+        let keypair = KeyPair::from_hex(
+            "1bd7f7e8800271a8e9d165442e97e3174d2b0789f695ceff5b8dfe8af3569dac",
+        )
+        .unwrap();
+        let (mut online, xonly, address) = test_signer_with_input(&keypair.to_bytes());
+        online.add_input(&"22".repeat(32), 5, 250_000, &p2pk_script(&xonly)).unwrap();
+        online.add_output(&address.to_string(), 340_000).unwrap();
+        online.set_payload(b"offline");
+
+        // Online machine writes the file, offline machine reads and signs it
+        let file = serde_json::to_string(&online.build()).unwrap();
+        let unsigned: UnsignedTx = serde_json::from_str(&file).unwrap();
+        assert_eq!(unsigned, online.build());
+        assert_eq!(unsigned.inputs[1].amount, 250_000);
+
+        let offline = sign_unsigned(&unsigned, &keypair).unwrap();
+        let direct = online.sign(&keypair.to_bytes()).unwrap();
+        assert_eq!(offline.id(), direct.id());
+        assert_eq!(offline.hex(), direct.hex());
+
+        let from_file = KaspaTransactionSigner::from_unsigned_json(&file)
+            .unwrap()
+            .sign_with(&keypair)
+            .unwrap();
+        assert_eq!(from_file.hex(), direct.hex());
+        assert!(KaspaTransactionSigner::from_unsigned_json("{\"version\": 0}").is_err());
     }
 }
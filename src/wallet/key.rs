@@ -1,9 +1,13 @@
 use hex;
 use rand::rngs::OsRng;
 use rand::RngCore;
-use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use secp256k1::{All, Message, PublicKey, Secp256k1, SecretKey, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
 use thiserror::Error;
 
+use super::address::Network;
+
 #[derive(Error, Debug)]
 pub enum KeyError {
     #[error("Invalid private key length")]
@@ -12,8 +16,26 @@ pub enum KeyError {
     InvalidFormat,
     #[error("Failed to parse key")]
     ParseError,
+    #[error("Invalid WIF checksum")]
+    InvalidChecksum,
+}
+
+// WIF version bytes (same values as Bitcoin tooling uses)
+const WIF_MAINNET_VERSION: u8 = 0x80;
+const WIF_TESTNET_VERSION: u8 = 0xef;
+const WIF_COMPRESSED_FLAG: u8 = 0x01;
+
+/// Context shared by every key and signing operation. Building one fills
+/// precomputation tables, which is too slow to repeat per call.
+pub fn secp() -> &'static Secp256k1<All> {
+    static CONTEXT: OnceLock<Secp256k1<All>> = OnceLock::new();
+    CONTEXT.get_or_init(Secp256k1::new)
 }
 
+/// Key of the keyed Blake2b-256 hash that signed messages commit to, the
+/// same domain Kaspa wallets use for personal messages.
+const MESSAGE_SIGNING_KEY: &[u8] = b"PersonalMessageSigningHash";
+
 pub type PrivateKey = SecretKey;
 pub type PublicKeyCompressed = PublicKey;
 
@@ -25,7 +47,6 @@ pub struct KeyPair {
 
 impl KeyPair {
     pub fn new() -> Self {
-        let secp = Secp256k1::new();
 
         let mut rng = OsRng;
         let mut secret_bytes = [0u8; 32];
@@ -33,7 +54,7 @@ impl KeyPair {
 
         let secret_key =
             PrivateKey::from_slice(&secret_bytes).expect("Failed to create secret key");
-        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        let public_key = PublicKey::from_secret_key(secp(), &secret_key);
 
         Self {
             secret_key,
@@ -50,8 +71,7 @@ impl KeyPair {
 
         let secret_key = PrivateKey::from_slice(&key_bytes).map_err(|_| KeyError::ParseError)?;
 
-        let secp = Secp256k1::new();
-        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        let public_key = PublicKey::from_secret_key(secp(), &secret_key);
 
         Ok(Self {
             secret_key,
@@ -59,6 +79,54 @@ impl KeyPair {
         })
     }
 
+    /// Parse a base58check WIF string: version byte, 32-byte secret,
+    /// optional compression flag and a 4-byte double-SHA256 checksum.
+    ///
+    /// The network comes from the version byte. Every non-mainnet network
+    /// shares the testnet byte, so those are reported as `Testnet10`.
+    pub fn from_wif(wif: &str) -> Result<(Self, Network), KeyError> {
+        let data = bs58::decode(wif)
+            .into_vec()
+            .map_err(|_| KeyError::InvalidFormat)?;
+
+        if data.len() != 37 && data.len() != 38 {
+            return Err(KeyError::InvalidLength);
+        }
+
+        let (body, checksum) = data.split_at(data.len() - 4);
+        if Sha256::digest(Sha256::digest(body))[..4] != *checksum {
+            return Err(KeyError::InvalidChecksum);
+        }
+
+        let network = match body[0] {
+            WIF_MAINNET_VERSION => Network::Mainnet,
+            WIF_TESTNET_VERSION => Network::Testnet10,
+            _ => return Err(KeyError::InvalidFormat),
+        };
+        if body.len() == 34 && body[33] != WIF_COMPRESSED_FLAG {
+            return Err(KeyError::InvalidFormat);
+        }
+
+        Ok((Self::from_hex(&hex::encode(&body[1..33]))?, network))
+    }
+
+    /// Encode the secret key as a compressed WIF string for the given network.
+    pub fn to_wif(&self, network: Network) -> String {
+        let version = match network {
+            Network::Mainnet => WIF_MAINNET_VERSION,
+            Network::Testnet10 | Network::Testnet11 | Network::Simnet => WIF_TESTNET_VERSION,
+        };
+
+        let mut data = Vec::with_capacity(38);
+        data.push(version);
+        data.extend_from_slice(&self.secret_key.secret_bytes());
+        data.push(WIF_COMPRESSED_FLAG);
+        let checksum = Sha256::digest(Sha256::digest(&data));
+        data.extend_from_slice(&checksum[..4]);
+
+        bs58::encode(data).into_string()
+    }
+
     pub fn to_hex(&self) -> String {
         hex::encode(self.secret_key.secret_bytes())
     }
@@ -89,6 +157,42 @@ impl KeyPair {
             public_key,
         }
     }
+
+    /// BIP-340 Schnorr signature over `message_hash(msg)`, verifiable with
+    /// `verify_message` against the x-only public key.
+    ///
+    /// Signed without auxiliary randomness, so the same key and message
+    /// always give the same signature.
+    pub fn sign_message(&self, msg: &[u8]) -> [u8; 64] {
+        let keypair = secp256k1::KeyPair::from_secret_key(secp(), &self.secret_key);
+        let sig = secp().sign_schnorr_no_aux_rand(&message_digest(msg), &keypair);
+        *sig.as_ref()
+    }
+}
+
+/// Keyed Blake2b-256 of `msg`, with `PersonalMessageSigningHash` as the key.
+/// This is what `sign_message` signs.
+pub fn message_hash(msg: &[u8]) -> [u8; 32] {
+    use blake2::digest::{consts::U32, Mac};
+    let mut hasher = blake2::Blake2bMac::<U32>::new_from_slice(MESSAGE_SIGNING_KEY)
+        .expect("key is shorter than 64 bytes");
+    hasher.update(msg);
+    hasher.finalize().into_bytes().into()
+}
+
+fn message_digest(msg: &[u8]) -> Message {
+    Message::from_slice(&message_hash(msg)).expect("hash is 32 bytes")
+}
+
+/// Check a `sign_message` signature against a 32-byte x-only public key.
+pub fn verify_message(xonly_pubkey: &[u8], msg: &[u8], sig: &[u8; 64]) -> bool {
+    let Ok(pubkey) = XOnlyPublicKey::from_slice(xonly_pubkey) else {
+        return false;
+    };
+    let Ok(sig) = secp256k1::schnorr::Signature::from_slice(sig) else {
+        return false;
+    };
+    secp().verify_schnorr(&sig, &message_digest(msg), &pubkey).is_ok()
 }
 
 impl Default for KeyPair {
@@ -124,4 +228,85 @@ mod tests {
         assert!(KeyPair::from_hex("123").is_err());
         assert!(KeyPair::from_hex("").is_err());
     }
+
+    #[test]
+    fn test_wif_known_vectors() {
+        let secret = "0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d";
+
+        let (compressed, network) = KeyPair::from_wif("KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617").unwrap();
+        assert_eq!(compressed.to_hex(), secret);
+        assert_eq!(network, Network::Mainnet);
+
+        let (uncompressed, network) = KeyPair::from_wif("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ").unwrap();
+        assert_eq!(uncompressed.to_hex(), secret);
+        assert_eq!(network, Network::Mainnet);
+
+        let (testnet, network) = KeyPair::from_wif("cMzLdeGd5vEqxB8B6VFQoRopQ3sLAAvEzDAoQgvX54xwofSWj1fx").unwrap();
+        assert_eq!(testnet.to_hex(), secret);
+        assert_eq!(network, Network::Testnet10);
+
+        assert_eq!(
+            compressed.to_wif(Network::Mainnet),
+            "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617"
+        );
+        assert_eq!(
+            compressed.to_wif(Network::Testnet10),
+            "cMzLdeGd5vEqxB8B6VFQoRopQ3sLAAvEzDAoQgvX54xwofSWj1fx"
+        );
+    }
+
+    #[test]
+    fn test_wif_roundtrip() {
+        let keypair = KeyPair::new();
+        for network in [Network::Mainnet, Network::Testnet10] {
+            let wif = keypair.to_wif(network);
+            let (recovered, recovered_network) = KeyPair::from_wif(&wif).unwrap();
+            assert_eq!(recovered.to_hex(), keypair.to_hex());
+            assert_eq!(recovered_network, network);
+        }
+    }
+
+    #[test]
+    fn test_wif_bad_checksum() {
+        // Last character altered
+        let result = KeyPair::from_wif("KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98618");
+        assert!(matches!(result, Err(KeyError::InvalidChecksum)));
+    }
+
+    #[test]
+    fn test_sign_message_known_vector() {
+        // Kaspa wallet test vector: secret key 3, zero auxiliary randomness
+        let keypair = KeyPair::from_hex(&format!("{:064x}", 3)).unwrap();
+        let sig = keypair.sign_message(b"Hello Kaspa!");
+        assert_eq!(
+            hex::encode(sig),
+            "40b9bb2be0ae02607279eda64015a8d86e3763279170340b8243f7ce5344d77aff1191598baf2fd26149cac3b4b12c2c433261c00834db6098cb172aa48ef522"
+        );
+        assert_eq!(sig, keypair.sign_message(b"Hello Kaspa!"));
+
+        let xonly = &keypair.public_key_bytes()[1..];
+        assert!(verify_message(xonly, b"Hello Kaspa!", &sig));
+        assert!(!verify_message(xonly, b"Hello Kaspa?", &sig));
+        assert!(!verify_message(&KeyPair::new().public_key_bytes()[1..], b"Hello Kaspa!", &sig));
+        assert!(!verify_message(b"not a key", b"Hello Kaspa!", &sig));
+    }
+
+    #[test]
+    fn test_message_signature_tampering() {
+        let keypair = KeyPair::new();
+        let msg = b"I control this address";
+        let sig = keypair.sign_message(msg);
+        let xonly = &keypair.public_key_bytes()[1..];
+        assert!(verify_message(xonly, msg, &sig));
+
+        for i in [0, 31, 32, 63] {
+            let mut tampered = sig;
+            tampered[i] ^= 0x01;
+            assert!(!verify_message(xonly, msg, &tampered), "byte {} flipped", i);
+        }
+        let mut altered = msg.to_vec();
+        altered[0] ^= 0x20;
+        assert!(!verify_message(xonly, &altered, &sig));
+        assert!(!verify_message(xonly, &msg[1..], &sig));
+    }
 }
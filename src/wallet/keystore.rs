@@ -0,0 +1,194 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+use super::key::{KeyError, KeyPair};
+
+#[derive(Error, Debug)]
+pub enum KeystoreError {
+    #[error("Wrong keystore password")]
+    WrongPassword,
+    #[error("Unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Invalid keystore: {0}")]
+    Invalid(String),
+    #[error("Invalid key in keystore: {0}")]
+    Key(#[from] KeyError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+const KEYSTORE_VERSION: u32 = 1;
+const KDF_NAME: &str = "scrypt";
+const CIPHER_NAME: &str = "chacha20-poly1305";
+
+/// scrypt cost parameters, stored in the file so they can be raised later
+/// without breaking existing keystores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// 32 MiB of memory per attempt.
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// A private key encrypted under a password, as stored on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub kdf: String,
+    pub kdf_params: ScryptParams,
+    pub salt: String,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    pub fn encrypt(keypair: &KeyPair, password: &str) -> Result<Self, KeystoreError> {
+        Self::encrypt_with(keypair, password, ScryptParams::default())
+    }
+
+    pub fn encrypt_with(
+        keypair: &KeyPair,
+        password: &str,
+        params: ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt, params)?.into());
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), keypair.to_bytes().as_slice())
+            .map_err(|e| KeystoreError::Invalid(e.to_string()))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf: KDF_NAME.to_string(),
+            kdf_params: params,
+            salt: hex::encode(salt),
+            cipher: CIPHER_NAME.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Fails with `WrongPassword` when the ciphertext doesn't authenticate.
+    pub fn decrypt(&self, password: &str) -> Result<KeyPair, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.kdf != KDF_NAME || self.cipher != CIPHER_NAME {
+            return Err(KeystoreError::Invalid(format!(
+                "unsupported kdf {} or cipher {}",
+                self.kdf, self.cipher
+            )));
+        }
+
+        let salt = decode_field("salt", &self.salt)?;
+        let nonce = decode_field("nonce", &self.nonce)?;
+        let ciphertext = decode_field("ciphertext", &self.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(KeystoreError::Invalid(format!(
+                "nonce is {} bytes, expected 12",
+                nonce.len()
+            )));
+        }
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt, self.kdf_params)?.into());
+        let secret = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| KeystoreError::WrongPassword)?;
+
+        Ok(KeyPair::from_hex(&hex::encode(secret))?)
+    }
+}
+
+/// Encrypt `keypair` under `password` and write it to `path` as JSON.
+pub fn save_keystore(
+    path: impl AsRef<Path>,
+    keypair: &KeyPair,
+    password: &str,
+) -> Result<Keystore, KeystoreError> {
+    let keystore = Keystore::encrypt(keypair, password)?;
+    let json = serde_json::to_string_pretty(&keystore)
+        .map_err(|e| KeystoreError::Invalid(e.to_string()))?;
+    std::fs::write(path, json)?;
+    Ok(keystore)
+}
+
+/// Read a keystore written by `save_keystore` and decrypt its key.
+pub fn load_keystore(path: impl AsRef<Path>, password: &str) -> Result<KeyPair, KeystoreError> {
+    let json = std::fs::read_to_string(path)?;
+    let keystore: Keystore =
+        serde_json::from_str(&json).map_err(|e| KeystoreError::Invalid(e.to_string()))?;
+    keystore.decrypt(password)
+}
+
+fn derive_key(
+    password: &str,
+    salt: &[u8],
+    params: ScryptParams,
+) -> Result<[u8; 32], KeystoreError> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| KeystoreError::Invalid(format!("bad scrypt parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| KeystoreError::Invalid(e.to_string()))?;
+    Ok(key)
+}
+
+fn decode_field(name: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|e| KeystoreError::Invalid(format!("{}: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap parameters so the tests don't spend seconds in scrypt
+    const TEST_PARAMS: ScryptParams = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let keypair = KeyPair::new();
+        let keystore = Keystore::encrypt_with(&keypair, "correct horse", TEST_PARAMS).unwrap();
+        assert!(!keystore.ciphertext.contains(&keypair.to_hex()));
+
+        let path = std::env::temp_dir().join(format!("keystore-{}.json", keypair.public_key_hex()));
+        std::fs::write(&path, serde_json::to_string(&keystore).unwrap()).unwrap();
+        let loaded = load_keystore(&path, "correct horse");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().to_bytes(), keypair.to_bytes());
+    }
+
+    #[test]
+    fn test_keystore_wrong_password() {
+        let keystore =
+            Keystore::encrypt_with(&KeyPair::new(), "correct horse", TEST_PARAMS).unwrap();
+        assert!(matches!(
+            keystore.decrypt("battery staple"),
+            Err(KeystoreError::WrongPassword)
+        ));
+    }
+}
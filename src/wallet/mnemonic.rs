@@ -0,0 +1,241 @@
+use pbkdf2::pbkdf2_hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use thiserror::Error;
+
+use super::hd::{ExtendedKey, HdError};
+
+#[derive(Error, Debug)]
+pub enum MnemonicError {
+    #[error("Invalid word count: {0} (expected 12, 15, 18, 21 or 24)")]
+    InvalidWordCount(usize),
+    #[error("Unknown mnemonic word: {0}")]
+    UnknownWord(String),
+    #[error("Invalid mnemonic checksum")]
+    InvalidChecksum,
+}
+
+// Official BIP39 English wordlist
+const WORDLIST: &str = include_str!("bip39_english.txt");
+const PBKDF2_ROUNDS: u32 = 2048;
+const VALID_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().collect()
+}
+
+/// Generate a random English mnemonic with 12, 15, 18, 21 or 24 words.
+pub fn generate_mnemonic(words: usize) -> Result<String, MnemonicError> {
+    if !VALID_WORD_COUNTS.contains(&words) {
+        return Err(MnemonicError::InvalidWordCount(words));
+    }
+
+    // 32 bits of entropy per 3 words
+    let mut entropy = vec![0u8; words * 4 / 3];
+    OsRng.fill_bytes(&mut entropy);
+
+    entropy_to_mnemonic(&entropy)
+}
+
+/// Encode 16-32 bytes of entropy (a multiple of 4) as a mnemonic phrase.
+pub fn entropy_to_mnemonic(entropy: &[u8]) -> Result<String, MnemonicError> {
+    let word_count = entropy.len() * 3 / 4;
+    if entropy.len() % 4 != 0 || !VALID_WORD_COUNTS.contains(&word_count) {
+        return Err(MnemonicError::InvalidWordCount(word_count));
+    }
+
+    // Checksum is the first ENT/32 bits of SHA256(entropy)
+    let checksum_bits = entropy.len() / 4;
+    let hash = Sha256::digest(entropy);
+
+    let mut bits: Vec<bool> = entropy
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+        .collect();
+    bits.extend((0..checksum_bits).map(|i| (hash[i / 8] >> (7 - i % 8)) & 1 == 1));
+
+    let list = wordlist();
+    let words: Vec<&str> = bits
+        .chunks(11)
+        .map(|chunk| chunk.iter().fold(0usize, |acc, &bit| (acc << 1) | bit as usize))
+        .map(|index| list[index])
+        .collect();
+
+    Ok(words.join(" "))
+}
+
+/// Check that every word is in the wordlist and the embedded checksum matches.
+pub fn validate_mnemonic(phrase: &str) -> Result<(), MnemonicError> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if !VALID_WORD_COUNTS.contains(&words.len()) {
+        return Err(MnemonicError::InvalidWordCount(words.len()));
+    }
+
+    let list = wordlist();
+    let mut bits = Vec::with_capacity(words.len() * 11);
+    for word in &words {
+        let index = list
+            .binary_search(word)
+            .map_err(|_| MnemonicError::UnknownWord(word.to_string()))?;
+        bits.extend((0..11).rev().map(|i| (index >> i) & 1 == 1));
+    }
+
+    let checksum_bits = words.len() / 3;
+    let entropy_bits = bits.len() - checksum_bits;
+    let entropy: Vec<u8> = bits[..entropy_bits]
+        .chunks(8)
+        .map(|chunk| chunk.iter().fold(0u8, |acc, &bit| (acc << 1) | bit as u8))
+        .collect();
+
+    let hash = Sha256::digest(&entropy);
+    let expected = (0..checksum_bits).map(|i| (hash[i / 8] >> (7 - i % 8)) & 1 == 1);
+    if !expected.eq(bits[entropy_bits..].iter().copied()) {
+        return Err(MnemonicError::InvalidChecksum);
+    }
+
+    Ok(())
+}
+
+/// Derive the 64-byte BIP39 seed: PBKDF2-HMAC-SHA512 with 2048 rounds and
+/// salt "mnemonic" + passphrase. Words are joined by single spaces; only the
+/// English (ASCII) wordlist is supported, so no NFKD normalization is applied.
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> [u8; 64] {
+    let normalized = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    let salt = format!("mnemonic{}", passphrase);
+
+    let mut seed = [0u8; 64];
+    pbkdf2_hmac::<Sha512>(normalized.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+    seed
+}
+
+/// A validated BIP39 English mnemonic phrase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    phrase: String,
+}
+
+impl Mnemonic {
+    /// Generate a random mnemonic with 12, 15, 18, 21 or 24 words.
+    pub fn generate(words: usize) -> Result<Self, MnemonicError> {
+        Ok(Self {
+            phrase: generate_mnemonic(words)?,
+        })
+    }
+
+    /// Parse and validate a phrase, normalizing whitespace between words.
+    pub fn from_phrase(phrase: &str) -> Result<Self, MnemonicError> {
+        validate_mnemonic(phrase)?;
+        Ok(Self {
+            phrase: phrase.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+    }
+
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.phrase.split(' ').count()
+    }
+
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        mnemonic_to_seed(&self.phrase, passphrase)
+    }
+
+    pub fn to_extended_key(&self, passphrase: &str) -> Result<ExtendedKey, HdError> {
+        ExtendedKey::from_seed(&self.to_seed(passphrase))
+    }
+}
+
+impl std::str::FromStr for Mnemonic {
+    type Err = MnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_phrase(s)
+    }
+}
+
+impl std::fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.phrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Vectors from the reference BIP39 test suite (passphrase "TREZOR")
+    const VECTORS: [(&str, &str, &str); 3] = [
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+            "dd48c104698c30cfe2b6142103248622fb7bb0ff692eebb00089b32d22484e1613912f0a5b694407be899ffd31ed3992c456cdf60f5d4564b8ba3f05a69890ad",
+        ),
+    ];
+
+    #[test]
+    fn test_wordlist() {
+        let list = wordlist();
+        assert_eq!(list.len(), 2048);
+        assert_eq!(list[0], "abandon");
+        assert_eq!(list[2047], "zoo");
+    }
+
+    #[test]
+    fn test_bip39_vectors() {
+        for (entropy, phrase, seed) in VECTORS {
+            let entropy = hex::decode(entropy).unwrap();
+            assert_eq!(entropy_to_mnemonic(&entropy).unwrap(), phrase);
+            assert!(validate_mnemonic(phrase).is_ok());
+            assert_eq!(hex::encode(mnemonic_to_seed(phrase, "TREZOR")), seed);
+        }
+    }
+
+    #[test]
+    fn test_generate_mnemonic() {
+        for count in VALID_WORD_COUNTS {
+            let phrase = generate_mnemonic(count).unwrap();
+            assert_eq!(phrase.split_whitespace().count(), count);
+            assert!(validate_mnemonic(&phrase).is_ok());
+        }
+        assert!(matches!(generate_mnemonic(13), Err(MnemonicError::InvalidWordCount(13))));
+    }
+
+    #[test]
+    fn test_invalid_mnemonic() {
+        let bad_checksum = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        assert!(matches!(validate_mnemonic(bad_checksum), Err(MnemonicError::InvalidChecksum)));
+
+        let unknown = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon kaspa";
+        assert!(matches!(validate_mnemonic(unknown), Err(MnemonicError::UnknownWord(_))));
+    }
+
+    #[test]
+    fn test_mnemonic_type() {
+        let (_, phrase, seed) = VECTORS[0];
+        let spaced = phrase.replace(' ', "  \n");
+        let mnemonic: Mnemonic = spaced.parse().unwrap();
+        assert_eq!(mnemonic.phrase(), phrase);
+        assert_eq!(mnemonic.word_count(), 12);
+        assert_eq!(hex::encode(mnemonic.to_seed("TREZOR")), seed);
+
+        let key = mnemonic.to_extended_key("TREZOR").unwrap();
+        let expected = ExtendedKey::from_seed(&hex::decode(seed).unwrap()).unwrap();
+        assert_eq!(key.keypair().to_hex(), expected.keypair().to_hex());
+
+        assert_eq!(Mnemonic::generate(24).unwrap().word_count(), 24);
+        assert!(Mnemonic::from_phrase("abandon abandon abandon").is_err());
+    }
+}
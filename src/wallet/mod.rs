@@ -1,11 +1,35 @@
 mod address;
+mod coin_selection;
 mod hd;
 mod kaspa_signer;
 mod key;
+mod keystore;
+mod mnemonic;
 mod transaction;
 
-pub use address::{extract_pubkey_hash_from_address, generate_address, validate_address, Network};
-pub use hd::{ExtendedKey, HdError};
-pub use kaspa_signer::{KaspaSignedTransaction, KaspaTransactionSigner};
-pub use key::{KeyPair, PrivateKey, PublicKeyCompressed};
-pub use transaction::{ScriptData, Transaction, TxInput, TxOutput};
+pub use address::{
+    extract_pubkey_hash_from_address, generate_address, validate_address,
+    xonly_pubkey_from_address, Network,
+};
+pub use coin_selection::{
+    is_mature, select_spendable, select_utxos, CoinSelectionError, SelectionStrategy,
+    COINBASE_MATURITY,
+};
+pub(crate) use hd::parse_path;
+pub use hd::{DerivationParams, ExtendedKey, HdError, HARDENED_OFFSET, KASPA_COIN_TYPE};
+pub use kaspa_signer::{
+    input_mass, sign_unsigned, JsonTransaction, KaspaSignedTransaction, KaspaTransactionSigner,
+    SignerInput, TxSigner, UnsignedInput, UnsignedOutput, UnsignedTx,
+};
+pub use key::{
+    message_hash, secp, verify_message, KeyError, KeyPair, PrivateKey, PublicKeyCompressed,
+};
+pub use keystore::{load_keystore, save_keystore, Keystore, KeystoreError, ScryptParams};
+pub use mnemonic::{
+    entropy_to_mnemonic, generate_mnemonic, mnemonic_to_seed, validate_mnemonic, Mnemonic,
+    MnemonicError,
+};
+pub use transaction::{
+    dust_threshold, is_dust, ScriptData, Transaction, TxInput, TxOutput, DUST_THRESHOLD,
+    P2PK_SCRIPT_LEN,
+};
@@ -1,8 +1,16 @@
-use secp256k1::{Message, Secp256k1};
+use secp256k1::constants::SCHNORR_PUBLIC_KEY_SIZE;
+use secp256k1::Message;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use super::key::KeyPair;
+use kaspa_consensus_core::constants::TX_VERSION;
+use kaspa_consensus_core::tx::{
+    ScriptPublicKey, Transaction as ConsensusTransaction, TransactionInput, TransactionOutpoint,
+    TransactionOutput,
+};
+
+use super::kaspa_signer::{compute_transaction_mass, SCHNORR_SIGNATURE_SCRIPT_LEN};
+use super::key::{secp, KeyPair};
 
 // Blake2b hash function for Kaspa transaction signing
 fn blake2b_hash(data: &[u8]) -> [u8; 32] {
@@ -22,6 +30,36 @@ pub enum TransactionError {
     SigningError(String),
 }
 
+const OP_RETURN: u8 = 0x6a;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+
+const MIN_RELAY_FEE_PER_KB: u64 = 1000;
+/// Outpoint, a 64-byte signature script and sequence.
+const P2PK_INPUT_SIZE: u64 = 148;
+/// Length of a P2PK script public key: a 32-byte key push and OP_CHECKSIG.
+pub const P2PK_SCRIPT_LEN: usize = 1 + SCHNORR_PUBLIC_KEY_SIZE + 1;
+
+/// Smallest standard value for an output to a P2PK address.
+pub const DUST_THRESHOLD: u64 = dust_threshold(P2PK_SCRIPT_LEN);
+
+/// Smallest value an output to a `script_len`-byte script public key can
+/// carry without the node refusing to relay it.
+///
+/// The relay rule treats an output as dust when
+/// `value * 1000 / (3 * spend size) < min relay fee`, where the spend size
+/// is the serialized output plus a P2PK input redeeming it. That is, when
+/// spending it would cost more than a third of its value.
+pub const fn dust_threshold(script_len: usize) -> u64 {
+    // Value, script version and script length prefix
+    let spend_size = 8 + 2 + 8 + script_len as u64 + P2PK_INPUT_SIZE;
+    3 * spend_size * MIN_RELAY_FEE_PER_KB / 1000
+}
+
+pub fn is_dust(amount: u64, script_len: usize) -> bool {
+    amount < dust_threshold(script_len)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxInput {
     pub txid: String,
@@ -66,15 +104,40 @@ impl ScriptData {
     pub fn to_script_vec(&self) -> Vec<u8> {
         let mut script = Vec::new();
         // OP_RETURN for public unencrypted messages
-        script.push(0x6a); // OP_RETURN
-        script.push(self.data.len() as u8);
+        script.push(OP_RETURN);
+        // Lengths above 75 would read as opcodes, so need an OP_PUSHDATA prefix
+        match self.data.len() {
+            len @ 0..=0x4b => script.push(len as u8),
+            len @ 0x4c..=0xff => script.extend_from_slice(&[OP_PUSHDATA1, len as u8]),
+            len => {
+                script.push(OP_PUSHDATA2);
+                script.extend_from_slice(&(len as u16).to_le_bytes());
+            }
+        }
         script.extend_from_slice(&self.data);
         script
     }
+
+    /// The data pushed by an `OP_RETURN <data>` output script, the inverse
+    /// of `to_script_vec`. `None` for any other script.
+    pub fn from_op_return_script(script: &[u8]) -> Option<Self> {
+        let data = match script {
+            [OP_RETURN, len @ 0..=0x4b, data @ ..] if data.len() == *len as usize => data,
+            [OP_RETURN, OP_PUSHDATA1, len, data @ ..] if data.len() == *len as usize => data,
+            [OP_RETURN, OP_PUSHDATA2, lo, hi, data @ ..]
+                if data.len() == u16::from_le_bytes([*lo, *hi]) as usize =>
+            {
+                data
+            }
+            _ => return None,
+        };
+        Some(Self::new_op_return(data.to_vec()))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Transaction {
+    /// Always `TX_VERSION` (0), the only version nodes accept.
     pub version: u32,
     pub inputs: Vec<TxInput>,
     pub outputs: Vec<TxOutput>,
@@ -86,7 +149,7 @@ pub struct Transaction {
 impl Transaction {
     pub fn new() -> Self {
         Self {
-            version: 0,
+            version: TX_VERSION as u32,
             inputs: Vec::new(),
             outputs: Vec::new(),
             lock_time: 0,
@@ -123,23 +186,48 @@ impl Transaction {
         });
     }
 
+    /// Fee at `fee_rate` sompi per gram of mass.
     pub fn estimate_fee(&self, fee_rate: u64) -> u64 {
-        let base_size = 12;
-        let input_size = 36 + 73 + 33 + 4;
-        let output_size = 8 + 1 + 34;
-
-        let payload_size = if let Some(payload) = &self.payload {
-            2 + payload.data.len()
-        } else {
-            0
-        };
-
-        let total_size = base_size
-            + (self.inputs.len() * input_size)
-            + (self.outputs.len() * output_size)
-            + payload_size;
+        self.estimate_mass() * fee_rate
+    }
 
-        ((total_size as u64 + 999) / 1000) * fee_rate
+    /// Mass as charged by the node once every input carries a Schnorr
+    /// signature, using the same formula as `KaspaTransactionSigner`.
+    pub fn estimate_mass(&self) -> u64 {
+        // Only sizes count toward the mass, so the outpoints can be blank
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| TransactionInput {
+                previous_outpoint: TransactionOutpoint::new(Default::default(), input.vout),
+                signature_script: vec![0u8; SCHNORR_SIGNATURE_SCRIPT_LEN],
+                sequence: input.sequence as u64,
+                sig_op_count: input.sig_op_count,
+            })
+            .collect();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|output| TransactionOutput {
+                value: output.amount,
+                script_public_key: ScriptPublicKey::new(0, output.script_pubkey.clone().into()),
+            })
+            .collect();
+        let payload = self
+            .payload
+            .as_ref()
+            .map(|payload| payload.data.clone())
+            .unwrap_or_default();
+
+        compute_transaction_mass(&ConsensusTransaction::new(
+            0,
+            inputs,
+            outputs,
+            self.lock_time as u64,
+            Default::default(),
+            0,
+            payload,
+        ))
     }
 
     /// Compute BIP-143 style sighash for a specific input
@@ -161,14 +249,14 @@ impl Transaction {
 
         let input = &self.inputs[input_index];
 
-        eprintln!(
-            "DEBUG: Computing Kaspa BIP-143 sighash for input {}:",
-            input_index
+        log::debug!(
+            "Computing sighash for input {} ({}:{}, amount {})",
+            input_index,
+            input.txid,
+            input.vout,
+            input.amount
         );
-        eprintln!("  txid: {}", input.txid);
-        eprintln!("  vout: {}", input.vout);
-        eprintln!("  amount: {}", input.amount);
-        eprintln!("  script_pubkey: {}", hex::encode(&input.script_pubkey));
+        log::trace!("  script_pubkey: {}", hex::encode(&input.script_pubkey));
 
         let mut buffer = Vec::new();
 
@@ -234,15 +322,15 @@ impl Transaction {
         // 18. SigHash type (1-byte)
         buffer.push(sighash_type);
 
-        eprintln!("DEBUG: BIP-143 buffer length: {}", buffer.len());
-        eprintln!(
-            "DEBUG: BIP-143 buffer (first 64 bytes): {}",
+        log::debug!("Sighash preimage length: {}", buffer.len());
+        log::trace!(
+            "Sighash preimage (first 64 bytes): {}",
             hex::encode(&buffer[..64.min(buffer.len())])
         );
 
         // Return Blake2b hash
         let hash = blake2b_hash(&buffer);
-        eprintln!("DEBUG: Final BIP-143 sighash: {}", hex::encode(&hash));
+        log::trace!("Sighash: {}", hex::encode(&hash));
         Ok(hash.to_vec())
     }
 
@@ -353,8 +441,8 @@ impl Transaction {
                     // Use OP_PUSHBYTES_65 + signature + hashtype
                     let mut script = vec![0x41u8]; // OP_PUSHBYTES_65
                     script.extend_from_slice(sig);
-                    eprintln!(
-                        "DEBUG: P2PK signature script (65-byte sig, {} bytes): {}",
+                    log::trace!(
+                        "P2PK signature script (65-byte sig, {} bytes): {}",
                         script.len(),
                         hex::encode(&script)
                     );
@@ -421,8 +509,8 @@ impl Transaction {
             .get_signature_message(input_index)
             .map_err(|e| TransactionError::SigningError(e.to_string()))?;
 
-        eprintln!(
-            "DEBUG: Signing input {} with sighash: {}",
+        log::trace!(
+            "Signing input {} with sighash: {}",
             input_index,
             hex::encode(&message_data)
         );
@@ -431,14 +519,13 @@ impl Transaction {
         let message = Message::from_slice(&message_data)
             .map_err(|e| TransactionError::SigningError(e.to_string()))?;
 
-        let secp = Secp256k1::new();
 
         // Kaspa uses Schnorr signatures (BIP-340), not ECDSA
         // Convert to x-only public key for Schnorr
-        let xonly_keypair = secp256k1::KeyPair::from_seckey_slice(&secp, &keypair.to_bytes())
+        let xonly_keypair = secp256k1::KeyPair::from_seckey_slice(secp(), &keypair.to_bytes())
             .map_err(|e| TransactionError::SigningError(e.to_string()))?;
 
-        let signature = secp.sign_schnorr_no_aux_rand(&message, &xonly_keypair);
+        let signature = secp().sign_schnorr_no_aux_rand(&message, &xonly_keypair);
 
         // Kaspa uses BIP-340 Schnorr signatures
         // Append SIGHASH_ALL (0x01) to signature for Kaspa
@@ -446,8 +533,8 @@ impl Transaction {
         let mut sig_bytes = signature.as_ref().to_vec();
         sig_bytes.push(sighash_type);
 
-        eprintln!(
-            "DEBUG: Schnorr signature with hashtype ({} bytes): {}",
+        log::trace!(
+            "Schnorr signature with hashtype ({} bytes): {}",
             sig_bytes.len(),
             hex::encode(&sig_bytes)
         );
@@ -455,8 +542,8 @@ impl Transaction {
         // Get x-only public key (32 bytes) for Kaspa
         let (xonly_pubkey, _) = xonly_keypair.x_only_public_key();
         let xonly_pubkey_bytes: [u8; 32] = xonly_pubkey.serialize();
-        eprintln!(
-            "DEBUG: X-only public key (32 bytes): {}",
+        log::trace!(
+            "X-only public key (32 bytes): {}",
             hex::encode(&xonly_pubkey_bytes)
         );
 
@@ -485,7 +572,7 @@ mod tests {
     #[test]
     fn test_transaction_creation() {
         let tx = Transaction::new();
-        assert_eq!(tx.version, 1);
+        assert_eq!(tx.version, TX_VERSION as u32);
         assert!(tx.inputs.is_empty());
         assert!(tx.outputs.is_empty());
     }
@@ -506,6 +593,7 @@ mod tests {
 
         assert_eq!(tx.inputs.len(), 1);
         assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(tx.version, TX_VERSION as u32);
     }
 
     #[test]
@@ -514,6 +602,24 @@ mod tests {
         let script = ScriptData::new_graffiti(payload.to_vec());
         assert!(script.is_graffiti);
         assert_eq!(script.data, payload);
+
+        for len in [11, 75, 76, 255, 256] {
+            let data = ScriptData::new_op_return(vec![0xab; len]);
+            let parsed = ScriptData::from_op_return_script(&data.to_script_vec()).unwrap();
+            assert_eq!(parsed.data, data.data);
+        }
+        assert!(ScriptData::from_op_return_script(&[OP_RETURN, 5, 1, 2]).is_none());
+        assert!(ScriptData::from_op_return_script(&[0x20; 34]).is_none());
+    }
+
+    #[test]
+    fn test_dust_threshold() {
+        assert_eq!(DUST_THRESHOLD, 600);
+        assert!(is_dust(599, P2PK_SCRIPT_LEN));
+        assert!(!is_dust(600, P2PK_SCRIPT_LEN));
+        // P2SH scripts are a byte longer, so need slightly more
+        assert!(is_dust(602, 35));
+        assert!(!is_dust(603, 35));
     }
 
     #[test]
@@ -533,4 +639,25 @@ mod tests {
         let fee = tx.estimate_fee(1000);
         assert!(fee > 0);
     }
+
+    #[test]
+    fn test_estimate_fee_matches_signer_mass() {
+        use crate::wallet::{generate_address, KaspaTransactionSigner, Network};
+
+        let keypair = KeyPair::from_hex(&"42".repeat(32)).unwrap();
+        let address = generate_address(keypair.public_key(), Network::Testnet10);
+        let script = kaspa_txscript::pay_to_address_script(&address.as_str().try_into().unwrap());
+        let txid = "11".repeat(32);
+
+        let mut tx = Transaction::new();
+        tx.add_input(txid.clone(), 0, 100_000, script.script().to_vec());
+        tx.add_output(address.clone(), 90_000, script.script().to_vec());
+
+        let mut signer = KaspaTransactionSigner::new();
+        signer.add_input(&txid, 0, 100_000, script.script()).unwrap();
+        signer.add_output(&address, 90_000).unwrap();
+
+        assert_eq!(tx.estimate_fee(1), signer.estimate_mass());
+        assert_eq!(tx.estimate_fee(3), signer.estimate_mass() * 3);
+    }
 }
@@ -0,0 +1,28 @@
+//! Runs against a live node's gRPC port, `KASPA_GRPC_URL` or a local
+//! testnet-10 node by default, and is skipped when none answers.
+#![cfg(feature = "grpc")]
+
+use kaspa_graffiti::commands::{get_balance_with, get_utxos_with};
+use kaspa_graffiti::rpc::{GrpcRpcClient, LOCAL_TESTNET10_GRPC};
+
+// The address of private key 1; it needn't hold funds
+const ADDRESS: &str = "kaspatest:qpumuen7l8wthtz45p3ftn58pvrs9xlumvkuu2xet8egzkcklqtes5z8rkmpd";
+
+#[tokio::test]
+async fn test_grpc_balance_and_utxos() {
+    let url = std::env::var("KASPA_GRPC_URL").unwrap_or_else(|_| LOCAL_TESTNET10_GRPC.to_string());
+    let client = match GrpcRpcClient::connect(&url).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("skipping, no node at {}: {}", url, e);
+            return;
+        }
+    };
+
+    let balance = get_balance_with(&client, ADDRESS).await.unwrap();
+    assert_eq!(balance.address, ADDRESS);
+    let utxos = get_utxos_with(&client, ADDRESS).await.unwrap();
+    assert!(utxos.iter().all(|u| u.amount > 0));
+
+    client.disconnect().await.unwrap();
+}